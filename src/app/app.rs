@@ -1,19 +1,51 @@
+use base64::Engine as _;
 use crossterm::{
+    cursor::MoveTo,
     event::{self, DisableMouseCapture, EnableMouseCapture},
     execute,
+    style::Print,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
     Terminal,
 };
 use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const MODULE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+// Step of the resize keys, in percent of the output area
+const RESIZE_STEP: i16 = 5;
+// Two clicks on the same cell within this interval are a double click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
 
 use crate::app::{
     Config,
+    Engine,
+    config::OutputMode,
+    edit::Action,
+    engine::Language,
+    jsonpath,
+    keymap::Context,
+    library::{Library, QueryEntry},
     widgets::{
+        ApplyEvent,
+        ApplyView,
+        Help,
         Input,
         Jq,
+        Picker,
+        PickerEvent,
+        PickerItem,
+        Prompt,
+        PromptEvent,
+        ShapeEvent,
+        ShapeView,
+        StatsView,
+        StatusBar,
+        Viewer,
         Drawable
     }
 };
@@ -55,18 +87,69 @@ impl Selected {
     }
 }
 
+// Popups drawn on top of the panes, they get every key event while open
+pub enum Overlay<'a> {
+    None,
+    Library(Picker<'a>),
+    SaveQuery(Prompt<'a>),
+    SaveProgram(Prompt<'a>),
+    ExportPatch(Prompt<'a>),
+    ExportResult(Prompt<'a>),
+    // Where to write the result, then the diff to confirm it
+    ApplyPath(Prompt<'a>),
+    ConfirmApply(ApplyView<'a>),
+    // Fields of an edit of the base document
+    Edit(Prompt<'a>, Action),
+    Help(Help<'a>),
+    Value(Viewer<'a>),
+    Shape(ShapeView<'a>),
+    Stats(StatsView<'a>),
+    // Schema violations of the input (true) or of the result
+    Violations(Picker<'a>, bool),
+}
+
 pub struct App<'a> {
     terminal: Box<Terminal<CrosstermBackend<io::Stdout>>>,
+    config: &'a Config,
     input: Input<'a>,
     json_output: Jq<'a>,
     selected: Selected,
+    library: Library,
+    overlay: Overlay<'a>,
+    status: StatusBar<'a>,
+    // Where the input was last drawn, and the last click, to handle the mouse
+    input_area: Rect,
+    last_click: Option<(Instant, u16, u16)>,
+    // Debug overlay with the time taken by the last query
+    show_timings: bool,
+    // The .jq file the query was loaded from, and its content on disk
+    program_file: Option<PathBuf>,
+    saved_program: String,
 }
 
-impl App<'_> {
-    pub fn new<'a>(json_file_path: String, config:&'a Config) -> Result<App<'a>, io::Error> {
+impl<'a> App<'a> {
+    pub fn new(
+        json_file_path: String,
+        compare_file_path: Option<String>,
+        initial_query: &str,
+        program_file: Option<PathBuf>,
+        library: Library,
+        engine: Engine,
+        config: &'a Config
+    ) -> Result<App<'a>, io::Error> {
+        // Read the input files before taking over the terminal,
+        // so that an error is printed normally
+        let mut json_output = Jq::new(json_file_path, engine, config)?;
+        if let Some(path) = compare_file_path {
+            json_output.compare_with(path)?;
+        }
+
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        execute!(stdout, EnterAlternateScreen)?;
+        if config.mouse_capture {
+            execute!(stdout, EnableMouseCapture)?;
+        }
 
         let backend = CrosstermBackend::new(stdout);
         let terminal = Box::new(Terminal::new(backend).unwrap());
@@ -74,15 +157,635 @@ impl App<'_> {
         let selected = Selected::Input;
         
         let mut input = Input::new("Input", ".", config);
+        input.set_value(initial_query);
         input.set_selected(&selected);
-        let json_output = Jq::new(json_file_path, config);
+        input.set_mode(json_output.language().name());
 
-        Ok(App{
+        let mut app = App{
             terminal,
+            config,
             input,
             json_output,
             selected,
-        })
+            library,
+            overlay: Overlay::None,
+            status: StatusBar::new(config),
+            input_area: Rect::default(),
+            last_click: None,
+            show_timings: false,
+            program_file,
+            saved_program: initial_query.to_string(),
+        };
+        app.update_program_title();
+        Ok(app)
+    }
+
+    fn update_program_title(&mut self) {
+        let title = self.program_file.as_ref().map(|path| {
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => path.display().to_string(),
+            };
+            if self.input.value() != self.saved_program {
+                format!("{} [modified]", name)
+            } else {
+                name
+            }
+        });
+        self.input.set_right_title(title);
+    }
+
+    fn write_program(&mut self, path: PathBuf) -> Result<(), io::Error> {
+        let mut program = self.input.value().to_string();
+        if !program.ends_with('\n') {
+            program.push('\n');
+        }
+        std::fs::write(&path, program)?;
+        self.status.set_message(format!("Saved to {}", path.display()));
+        self.saved_program = self.input.value().to_string();
+        self.program_file = Some(path);
+        self.update_program_title();
+        Ok(())
+    }
+
+    fn save_program(&mut self) {
+        match self.program_file.clone() {
+            Some(path) => {
+                // Nowhere to report the error yet, ask for another path instead
+                if let Err(error) = self.write_program(path.clone()) {
+                    let mut prompt = Prompt::new("Save program as…", &["Path"], self.config);
+                    prompt.set_error(format!("Error: {}", error));
+                    self.overlay = Overlay::SaveProgram(prompt);
+                }
+            },
+            None => {
+                self.overlay = Overlay::SaveProgram(
+                    Prompt::new("Save program as…", &["Path"], self.config)
+                );
+            }
+        }
+    }
+
+    // The live query runs on a sample of a large input, this can take a while
+    fn run_on_whole_file(&mut self) -> Result<(), io::Error> {
+        let running = "Running the query on the whole file…";
+        self.status.set_message(running.to_string());
+        self.render()?;
+        // The percentage is printed after the message of the status bar,
+        // the UI is not drawn while the query runs
+        let row = self.terminal.size()?.height.saturating_sub(1);
+        let terminal = &mut self.terminal;
+        let mut shown = None;
+        let result = self.json_output.run_on_whole_file(&mut self.input, &mut |done, total| {
+            let percent = done * 100 / total.max(1);
+            if shown != Some(percent) {
+                shown = Some(percent);
+                let _ = execute!(
+                    terminal.backend_mut(),
+                    MoveTo(running.chars().count() as u16, row),
+                    Print(format!(" {}%", percent))
+                );
+            }
+        });
+        // Redraw the status bar over the percentage
+        self.terminal.clear()?;
+        let message = match result {
+            Ok(elapsed) => format!("Ran the query on the whole file in {:.1}s", elapsed.as_secs_f64()),
+            Err(error) => error,
+        };
+        self.status.set_message(message);
+        Ok(())
+    }
+
+    // Insert a path in the query at the cursor
+    fn insert_path(&mut self, path: &str) {
+        // The identity query would only prefix the path with a dot
+        if self.input.value().trim() == "." {
+            self.input.set_value("");
+        }
+        self.input.insert(path);
+        self.json_output.apply_filter(&mut self.input);
+        self.update_program_title();
+    }
+
+    // Ask where to write the result, the input file by default
+    fn open_apply(&mut self) {
+        if let Err(error) = self.json_output.result_file() {
+            self.status.set_message(error);
+            return;
+        }
+        let mut prompt = Prompt::new("Apply the query to…", &["Path"], self.config);
+        prompt.set_value(0, self.json_output.input_path());
+        self.overlay = Overlay::ApplyPath(prompt);
+    }
+
+    fn toggle_edit(&mut self) {
+        if let Err(error) = self.json_output.toggle_edit() {
+            self.status.set_message(error);
+        }
+    }
+
+    // Ask for the fields of an edit of the base document, if it has any
+    fn start_edit(&mut self, action: Action) {
+        let fields = match self.json_output.edit_fields(action) {
+            Ok(fields) => fields,
+            Err(error) => {
+                self.status.set_message(error);
+                return;
+            }
+        };
+        if fields.is_empty() {
+            if let Err(error) = self.json_output.edit(action, &[], &mut self.input) {
+                self.status.set_message(error);
+            }
+            return;
+        }
+        let title = match action {
+            Action::Change => "Change the value",
+            Action::Rename => "Rename the key",
+            Action::Add => "Add after the node",
+            Action::Append => "Add at the end of the container",
+            Action::Delete => "Delete the node",
+        };
+        let labels: Vec<&str> = fields.iter().map(|(label, _)| *label).collect();
+        let mut prompt = Prompt::new(title, &labels, self.config);
+        for (i, (_, value)) in fields.iter().enumerate() {
+            prompt.set_value(i, value);
+        }
+        self.overlay = Overlay::Edit(prompt, action);
+    }
+
+    fn save_input(&mut self) {
+        match self.json_output.save_input() {
+            Ok(Some(backup)) => self.status.set_message(
+                format!("Saved the edits to the input file, the previous file is {}", backup.display())
+            ),
+            Ok(None) => self.status.set_message("Saved the edits to the input file".to_string()),
+            Err(error) => self.status.set_message(format!("Error: {}", error)),
+        }
+    }
+
+    // Keys of the base pane in edit mode, and `i` to enter it.
+    // Returns false if the key is not one of them.
+    fn handle_edit_event(&mut self, event: &event::KeyEvent) -> bool {
+        if !matches!(self.selected, Selected::JsonBase) || self.json_output.json_base.pending_input() {
+            return false;
+        }
+        let editing = self.json_output.context(&self.selected) == Context::Edit;
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Char('i'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => self.toggle_edit(),
+            event::KeyEvent {
+                code: event::KeyCode::Esc,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } if editing => self.toggle_edit(),
+            event::KeyEvent {
+                code: event::KeyCode::Char('c'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } if editing => self.start_edit(Action::Change),
+            event::KeyEvent {
+                code: event::KeyCode::Char('r'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } if editing => self.start_edit(Action::Rename),
+            event::KeyEvent {
+                code: event::KeyCode::Char('d'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } if editing => self.start_edit(Action::Delete),
+            event::KeyEvent {
+                code: event::KeyCode::Char('a'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } if editing => self.start_edit(Action::Add),
+            event::KeyEvent {
+                code: event::KeyCode::Char('A'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } if editing => self.start_edit(Action::Append),
+            event::KeyEvent {
+                code: event::KeyCode::Char('z'),
+                modifiers: event::KeyModifiers::CONTROL,
+                kind: _,
+                state: _,
+            } if editing => {
+                if !self.json_output.undo_edit(&mut self.input) {
+                    self.status.set_message("Nothing to undo".to_string());
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    // List the schema violations of the focused pane, the result unless the base pane is focused
+    fn open_violations(&mut self) {
+        let input = matches!(self.selected, Selected::JsonBase);
+        match self.json_output.violations(input) {
+            Ok((_, items)) if items.is_empty() => {
+                let pane = if input {"input"} else {"result"};
+                self.status.set_message(format!("The {} is valid", pane));
+            },
+            Ok((title, items)) => {
+                self.overlay = Overlay::Violations(Picker::new(&title, items, self.config), input);
+            },
+            Err(error) => self.status.set_message(error),
+        }
+    }
+
+    fn set_language(&mut self, language: Language) {
+        self.json_output.set_language(language, &mut self.input, &self.selected);
+        self.input.set_mode(language.name());
+    }
+
+    // Switch to the next query language, the query is kept as is
+    fn switch_language(&mut self) {
+        let language = self.json_output.language().next();
+        self.set_language(language);
+        self.status.set_message(format!("Query language: {}", language.name()));
+    }
+
+    // Replace the JSONPath query by its jq translation, and switch to jq
+    fn translate_to_jq(&mut self) {
+        if self.json_output.language() != Language::JsonPath {
+            self.status.set_message("Only JSONPath queries can be translated, Alt-L switches the language".to_string());
+            return;
+        }
+        match jsonpath::to_jq(self.input.value()) {
+            Ok(query) => {
+                self.input.set_value(&query);
+                self.set_language(Language::Jq);
+                self.update_program_title();
+                self.status.set_message("Translated to jq (experimental), check the result".to_string());
+            },
+            Err(error) => self.status.set_message(error),
+        }
+    }
+
+    // Append the sort of the result table to the query (ie: `| sort_by(.name)`)
+    fn sort_query_by_table(&mut self) {
+        if self.json_output.language() != Language::Jq {
+            self.status.set_message("Only jq queries can be sorted by the table".to_string());
+            return;
+        }
+        let expression = match self.json_output.json_filtered.take_table_sort() {
+            Some(expression) => expression,
+            None => {
+                self.status.set_message("Sort the table with s first".to_string());
+                return;
+            }
+        };
+        // On a new line, a trailing comment would comment the sort out
+        let query = format!("{}\n| {}", self.input.value(), expression);
+        self.input.set_value(&query);
+        self.json_output.apply_filter(&mut self.input);
+        self.update_program_title();
+    }
+
+    // Copy the result with an OSC 52 sequence, the terminal puts it in the
+    // clipboard (works over ssh, unlike a clipboard library)
+    fn copy_result(&mut self) -> Result<(), io::Error> {
+        let text = match self.json_output.formatted_result() {
+            Ok(text) => text,
+            Err(error) => {
+                self.status.set_message(error);
+                return Ok(());
+            }
+        };
+        let encoded = base64::engine::general_purpose::STANDARD.encode(text.as_bytes());
+        execute!(io::stdout(), Print(format!("\x1b]52;c;{}\x07", encoded)))?;
+        self.status.set_message(format!("Copied the result to the clipboard ({} bytes)", text.len()));
+        Ok(())
+    }
+
+    fn focus(&mut self, selected: Selected) {
+        self.selected = selected;
+        self.input.set_selected(&self.selected);
+        self.json_output.set_selected(&self.selected);
+    }
+
+    // Click to focus a pane (and move its cursor), double click to insert
+    // the path of the clicked node in the query, wheel to scroll
+    fn handle_mouse_event(&mut self, event: &event::MouseEvent) {
+        let (column, row) = (event.column, event.row);
+        match event.kind {
+            event::MouseEventKind::Down(event::MouseButton::Left) => {
+                let is_double_click = self.last_click.is_some_and(|(time, last_column, last_row)| {
+                    time.elapsed() < DOUBLE_CLICK_INTERVAL && last_column == column && last_row == row
+                });
+                self.last_click = if is_double_click {None} else {Some((Instant::now(), column, row))};
+
+                let area = self.input_area;
+                if column >= area.x && column < area.x + area.width && row >= area.y && row < area.y + area.height {
+                    self.focus(Selected::Input);
+                } else if let Some(selected) = self.json_output.click(column, row) {
+                    self.focus(selected);
+                    if is_double_click {
+                        if let Some(path) = self.json_output.cursor_path(&self.selected) {
+                            self.insert_path(&path);
+                        }
+                    }
+                }
+            },
+            event::MouseEventKind::ScrollDown => {
+                self.json_output.scroll(column, row, true);
+            },
+            event::MouseEventKind::ScrollUp => {
+                self.json_output.scroll(column, row, false);
+            },
+            _ => {}
+        }
+    }
+
+    // Cycle the focus, skipping the base pane while it is hidden
+    fn move_focus(&mut self, forward: bool) {
+        if forward {
+            self.selected.next();
+        } else {
+            self.selected.prev();
+        }
+        if matches!(self.selected, Selected::JsonBase) && self.json_output.base_hidden() {
+            if forward {
+                self.selected.next();
+            } else {
+                self.selected.prev();
+            }
+        }
+        self.input.set_selected(&self.selected);
+        self.json_output.set_selected(&self.selected);
+    }
+
+    fn toggle_base(&mut self) {
+        self.json_output.toggle_base();
+        if matches!(self.selected, Selected::JsonBase) && self.json_output.base_hidden() {
+            self.move_focus(false);
+        }
+    }
+
+    fn open_library(&mut self) {
+        let items = self.library
+            .entries()
+            .iter()
+            .map(|entry| PickerItem {
+                label: entry.name.clone(),
+                detail: if entry.tags.is_empty() {
+                    entry.description.clone()
+                } else {
+                    format!("{} [{}]", entry.description, entry.tags.join(", "))
+                },
+                haystack: entry.haystack(),
+            })
+            .collect();
+        self.overlay = Overlay::Library(Picker::new("Query library", items, self.config));
+    }
+
+    fn open_save_query(&mut self) {
+        self.overlay = Overlay::SaveQuery(
+            Prompt::new(
+                "Save current query as…",
+                &["Name", "Description", "Tags"],
+                self.config
+            )
+        );
+    }
+
+    fn handle_overlay_event(&mut self, event: &event::KeyEvent) {
+        match &mut self.overlay {
+            Overlay::None => {},
+            Overlay::Library(picker) => {
+                match picker.handle_event(event) {
+                    PickerEvent::None => {},
+                    PickerEvent::Close => {
+                        self.overlay = Overlay::None;
+                    },
+                    PickerEvent::Pick(index) => {
+                        let query = self.library.entries()[index].query.clone();
+                        self.input.set_value(&query);
+                        self.json_output.apply_filter(&mut self.input);
+                        self.update_program_title();
+                        self.overlay = Overlay::None;
+                    },
+                }
+            },
+            Overlay::SaveQuery(prompt) => {
+                match prompt.handle_event(event) {
+                    PromptEvent::None => {},
+                    PromptEvent::Cancel => {
+                        self.overlay = Overlay::None;
+                    },
+                    PromptEvent::Submit => {
+                        let name = prompt.value(0).trim().to_string();
+                        if name.is_empty() {
+                            prompt.set_error("A name is required".to_string());
+                            return;
+                        }
+                        let message = format!("Saved query '{}' to the library", name);
+                        self.library.upsert(QueryEntry {
+                            name,
+                            description: prompt.value(1).trim().to_string(),
+                            tags: prompt.value(2)
+                                .split(',')
+                                .map(|tag| tag.trim().to_string())
+                                .filter(|tag| !tag.is_empty())
+                                .collect(),
+                            query: self.input.value().to_string(),
+                        });
+                        match self.library.save() {
+                            Ok(_) => {
+                                self.status.set_message(message);
+                                self.overlay = Overlay::None;
+                            },
+                            Err(error) => {
+                                prompt.set_error(format!("Error: {}", error));
+                            }
+                        }
+                    },
+                }
+            },
+            Overlay::SaveProgram(prompt) => {
+                match prompt.handle_event(event) {
+                    PromptEvent::None => {},
+                    PromptEvent::Cancel => {
+                        self.overlay = Overlay::None;
+                    },
+                    PromptEvent::Submit => {
+                        let path = PathBuf::from(prompt.value(0).trim());
+                        if path.as_os_str().is_empty() {
+                            prompt.set_error("A path is required".to_string());
+                            return;
+                        }
+                        if let Err(error) = self.write_program(path) {
+                            if let Overlay::SaveProgram(prompt) = &mut self.overlay {
+                                prompt.set_error(format!("Error: {}", error));
+                            }
+                            return;
+                        }
+                        self.overlay = Overlay::None;
+                    },
+                }
+            },
+            Overlay::ExportPatch(prompt) => {
+                match prompt.handle_event(event) {
+                    PromptEvent::None => {},
+                    PromptEvent::Cancel => {
+                        self.overlay = Overlay::None;
+                    },
+                    PromptEvent::Submit => {
+                        let path = PathBuf::from(prompt.value(0).trim());
+                        if path.as_os_str().is_empty() {
+                            prompt.set_error("A path is required".to_string());
+                            return;
+                        }
+                        match self.json_output.export_patch(&path) {
+                            Ok(_) => {
+                                self.status.set_message(format!("Exported the patch to {}", path.display()));
+                                self.overlay = Overlay::None;
+                            },
+                            Err(error) => {
+                                prompt.set_error(format!("Error: {}", error));
+                            }
+                        }
+                    },
+                }
+            },
+            Overlay::ExportResult(prompt) => {
+                match prompt.handle_event(event) {
+                    PromptEvent::None => {},
+                    PromptEvent::Cancel => {
+                        self.overlay = Overlay::None;
+                    },
+                    PromptEvent::Submit => {
+                        let path = PathBuf::from(prompt.value(0).trim());
+                        if path.as_os_str().is_empty() {
+                            prompt.set_error("A path is required".to_string());
+                            return;
+                        }
+                        match self.json_output.export_result(&path) {
+                            Ok(_) => {
+                                self.status.set_message(format!("Saved the result to {}", path.display()));
+                                self.overlay = Overlay::None;
+                            },
+                            Err(error) => {
+                                prompt.set_error(format!("Error: {}", error));
+                            }
+                        }
+                    },
+                }
+            },
+            Overlay::ApplyPath(prompt) => {
+                match prompt.handle_event(event) {
+                    PromptEvent::None => {},
+                    PromptEvent::Cancel => {
+                        self.overlay = Overlay::None;
+                    },
+                    PromptEvent::Submit => {
+                        let path = PathBuf::from(prompt.value(0).trim());
+                        if path.as_os_str().is_empty() {
+                            prompt.set_error("A path is required".to_string());
+                            return;
+                        }
+                        let view = self.json_output.result_file().and_then(|content| {
+                            ApplyView::new(path, content, self.config).map_err(|error| format!("Error: {}", error))
+                        });
+                        match view {
+                            Ok(view) => self.overlay = Overlay::ConfirmApply(view),
+                            Err(error) => prompt.set_error(error),
+                        }
+                    },
+                }
+            },
+            Overlay::ConfirmApply(view) => {
+                match view.handle_event(event) {
+                    ApplyEvent::None => {},
+                    ApplyEvent::Cancel => {
+                        self.overlay = Overlay::None;
+                    },
+                    ApplyEvent::Confirm => {
+                        let path = view.path().clone();
+                        let message = match self.json_output.apply_to_file(&path, view.content(), &mut self.input) {
+                            Ok(Some(backup)) => format!("Wrote the result to {}, the previous file is {}", path.display(), backup.display()),
+                            Ok(None) => format!("Wrote the result to {}", path.display()),
+                            Err(error) => error,
+                        };
+                        self.status.set_message(message);
+                        self.overlay = Overlay::None;
+                    },
+                }
+            },
+            Overlay::Edit(prompt, action) => {
+                match prompt.handle_event(event) {
+                    PromptEvent::None => {},
+                    PromptEvent::Cancel => {
+                        self.overlay = Overlay::None;
+                    },
+                    PromptEvent::Submit => {
+                        let (action, values) = (*action, prompt.values());
+                        match self.json_output.edit(action, &values, &mut self.input) {
+                            Ok(_) => self.overlay = Overlay::None,
+                            Err(error) => {
+                                if let Overlay::Edit(prompt, _) = &mut self.overlay {
+                                    prompt.set_error(error);
+                                }
+                            },
+                        }
+                    },
+                }
+            },
+            Overlay::Help(help) => {
+                if help.handle_event(event) {
+                    self.overlay = Overlay::None;
+                }
+            },
+            Overlay::Value(viewer) => {
+                if viewer.handle_event(event) {
+                    self.overlay = Overlay::None;
+                }
+            },
+            Overlay::Shape(view) => {
+                match view.handle_event(event) {
+                    ShapeEvent::None => {},
+                    ShapeEvent::Close => {
+                        self.overlay = Overlay::None;
+                    },
+                    ShapeEvent::Insert(path) => {
+                        self.overlay = Overlay::None;
+                        self.insert_path(&path);
+                        self.focus(Selected::Input);
+                    },
+                }
+            },
+            Overlay::Stats(view) => {
+                if view.handle_event(event) {
+                    self.overlay = Overlay::None;
+                }
+            },
+            Overlay::Violations(picker, input) => {
+                let input = *input;
+                match picker.handle_event(event) {
+                    PickerEvent::None => {},
+                    PickerEvent::Close => {
+                        self.overlay = Overlay::None;
+                    },
+                    PickerEvent::Pick(index) => {
+                        self.overlay = Overlay::None;
+                        self.json_output.select_violation(input, index);
+                        self.focus(if input {Selected::JsonBase} else {Selected::JsonFiltered});
+                    },
+                }
+            },
+        }
     }
 
     fn clean_up(&mut self) -> Result<(), io::Error>{
@@ -90,9 +793,11 @@ impl App<'_> {
         disable_raw_mode()?;
         execute!(
             self.terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
+            LeaveAlternateScreen
         )?;
+        if self.config.mouse_capture {
+            execute!(self.terminal.backend_mut(), DisableMouseCapture)?;
+        }
         self.terminal.show_cursor()?;
         Ok(())
     }
@@ -111,6 +816,16 @@ impl App<'_> {
             self.json_output.apply_filter(&mut self.input);
             self.render()?;
             while is_running {
+                // Wake up regularly to re-run the query when a jq module is edited
+                if !event::poll(MODULE_POLL_INTERVAL)? {
+                    if self.json_output.modules_changed() {
+                        self.json_output.apply_filter(&mut self.input);
+                        self.render()?;
+                    } else if self.status.expire() {
+                        self.render()?;
+                    }
+                    continue;
+                }
                 match event::read().unwrap() {
                     event::Event::Key(event::KeyEvent {
                         code: event::KeyCode::Char('c'),
@@ -120,34 +835,302 @@ impl App<'_> {
                     }) => {
                         is_running = false;
                     }
+                    event::Event::Key(event) if !matches!(self.overlay, Overlay::None) => {
+                        self.handle_overlay_event(&event);
+                    }
+                    event::Event::Mouse(event::MouseEvent {
+                        kind: event::MouseEventKind::Moved | event::MouseEventKind::Drag(_),
+                        column: _,
+                        row: _,
+                        modifiers: _,
+                    }) => {
+                        // Nothing changes, don't redraw on every motion
+                        continue;
+                    }
+                    event::Event::Mouse(event) if matches!(self.overlay, Overlay::None) => {
+                        self.handle_mouse_event(&event);
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(1),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.overlay = Overlay::Help(Help::new(self.config));
+                    }
+                    // `?` is a jq operator, it only opens the help outside of the input
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('?'),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) if !matches!(self.selected, Selected::Input) => {
+                        self.overlay = Overlay::Help(Help::new(self.config));
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('v'),
+                        modifiers: event::KeyModifiers::NONE,
+                        kind: _,
+                        state: _,
+                    }) if !matches!(self.selected, Selected::Input) => {
+                        if let Some((path, value)) = self.json_output.cursor_value(&self.selected) {
+                            self.overlay = Overlay::Value(Viewer::new(path, &value, self.config));
+                        }
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('S'),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) if matches!(self.selected, Selected::JsonFiltered)
+                        && self.json_output.context(&self.selected) == Context::Table => {
+                        self.sort_query_by_table();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('o'),
+                        modifiers: event::KeyModifiers::CONTROL,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.open_library();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('b'),
+                        modifiers: event::KeyModifiers::CONTROL,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.open_save_query();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(2),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        if self.json_output.language() == Language::Jq {
+                            self.json_output.toggle_pipeline(&mut self.input, &self.selected);
+                        } else {
+                            self.status.set_message("The pipeline debugger only splits jq queries".to_string());
+                        }
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(3),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.toggle_diff(&mut self.input, &self.selected);
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('p'),
+                        modifiers: event::KeyModifiers::CONTROL,
+                        kind: _,
+                        state: _,
+                    }) if self.json_output.diff_active() => {
+                        self.overlay = Overlay::ExportPatch(
+                            Prompt::new("Export diff as JSON Patch to…", &["Path"], self.config)
+                        );
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('s'),
+                        modifiers: event::KeyModifiers::CONTROL,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        if matches!(self.selected, Selected::JsonBase) && self.json_output.editing() {
+                            self.save_input();
+                        } else {
+                            self.save_program();
+                        }
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('r'),
+                        modifiers: event::KeyModifiers::CONTROL,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.run_on_whole_file()?;
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('l'),
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.switch_language();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('t'),
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.translate_to_jq();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('r'),
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.toggle_output_mode(OutputMode::Raw);
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('j'),
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.toggle_output_mode(OutputMode::Join);
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('v'),
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.open_violations();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('x'),
+                        modifiers: event::KeyModifiers::CONTROL,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.overlay = Overlay::ExportResult(
+                            Prompt::new("Save the result to…", &["Path"], self.config)
+                        );
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('a'),
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.open_apply();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Char('y'),
+                        modifiers: event::KeyModifiers::CONTROL,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.copy_result()?;
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Left | event::KeyCode::Up,
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.resize(-RESIZE_STEP);
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::Right | event::KeyCode::Down,
+                        modifiers: event::KeyModifiers::ALT,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.resize(RESIZE_STEP);
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(4),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.toggle_direction();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(5),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.swap_panes();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(6),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.toggle_base();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(7),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.json_output.toggle_zoom();
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(8),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.show_timings = !self.show_timings;
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(9),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        match self.json_output.input_shape() {
+                            Ok(shape) => self.overlay = Overlay::Shape(ShapeView::new(shape, self.config)),
+                            Err(error) => self.status.set_message(error),
+                        }
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::F(10),
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        match self.json_output.result_stats() {
+                            Ok(stats) => self.overlay = Overlay::Stats(StatsView::new(stats, self.config)),
+                            Err(error) => self.status.set_message(error),
+                        }
+                    }
                     event::Event::Key(event::KeyEvent {
                         code: event::KeyCode::Tab,
                         modifiers: event::KeyModifiers::CONTROL | event::KeyModifiers::SHIFT | event::KeyModifiers::SUPER,
                         kind: _,
                         state: _,
                     }) => {
-                        self.selected.prev();
-                        self.input.set_selected(&self.selected);
-                        self.json_output.set_selected(&self.selected);
-                    }                    
+                        self.move_focus(false);
+                    }
                     event::Event::Key(event::KeyEvent {
                         code: event::KeyCode::Tab,
                         modifiers: event::KeyModifiers::NONE,
                         kind: _,
                         state: _,
                     }) => {
-                        self.selected.next();
-                        self.input.set_selected(&self.selected);
-                        self.json_output.set_selected(&self.selected);
+                        self.move_focus(true);
+                    }
+                    event::Event::Key(event::KeyEvent {
+                        code: event::KeyCode::BackTab,
+                        modifiers: _,
+                        kind: _,
+                        state: _,
+                    }) => {
+                        self.move_focus(false);
                     }
                     event::Event::Key(event) => {
                         match self.selected {
                             Selected::Input => {
                                 self.input.handle_event(&event);
                                 self.json_output.apply_filter(&mut self.input);
+                                self.update_program_title();
                             },
                             Selected::JsonFiltered | Selected::JsonBase => {
-                                self.json_output.handle_event(&event);
+                                if !self.handle_edit_event(&event) {
+                                    self.json_output.handle_event(&event);
+                                }
                             },
                         }
                     }
@@ -165,20 +1148,25 @@ impl App<'_> {
         }
 
         let size = self.terminal.size()?;
-        // input widget should be 3 rows high
-        let input_area = ratatui::layout::Rect::new(
-            size.x,
-            size.y,
-            size.width,
-            3,
-        );
-        let output_area = ratatui::layout::Rect::new(
-            size.x,
-            size.y + 3,
-            size.width,
-            size.height - 3,
-        );
+        // input widget should be 3 rows high, the status bar 1 row,
+        // the output gets what is left (possibly nothing on tiny terminals)
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(size);
+        let (input_area, output_area, status_area) = (areas[0], areas[1], areas[2]);
+        self.input_area = input_area;
 
+        self.status.set_context(match self.overlay {
+            Overlay::None => self.json_output.context(&self.selected),
+            _ => Context::Popup,
+        });
+
+        let start = Instant::now();
         self.terminal.draw(|f| {
             match self.input.draw(f, input_area) {
                 Ok(_) => {},
@@ -192,9 +1180,36 @@ impl App<'_> {
                     panic!("Error while drawing json widget")
                 }
             }
+            if self.status.draw(f, status_area).is_err() {
+                panic!("Error while drawing status bar")
+            }
+            let overlay = match &self.overlay {
+                Overlay::None => Ok(()),
+                Overlay::Library(picker) => picker.draw(f, size),
+                Overlay::SaveQuery(prompt) => prompt.draw(f, size),
+                Overlay::SaveProgram(prompt) => prompt.draw(f, size),
+                Overlay::ExportPatch(prompt) => prompt.draw(f, size),
+                Overlay::ExportResult(prompt) => prompt.draw(f, size),
+                Overlay::ApplyPath(prompt) => prompt.draw(f, size),
+                Overlay::ConfirmApply(view) => view.draw(f, size),
+                Overlay::Edit(prompt, _) => prompt.draw(f, size),
+                Overlay::Help(help) => help.draw(f, size),
+                Overlay::Value(viewer) => viewer.draw(f, size),
+                Overlay::Shape(view) => view.draw(f, size),
+                Overlay::Stats(view) => view.draw(f, size),
+                Overlay::Violations(picker, _) => picker.draw(f, size),
+            };
+            if overlay.is_err() {
+                panic!("Error while drawing overlay widget")
+            }
+            // Shows the time taken by the previous frame
+            if self.show_timings && self.json_output.timings.draw(f, output_area).is_err() {
+                panic!("Error while drawing timings")
+            }
             // self.input.draw(f, input_area);
             // self.json_output.draw(f, output_area);
         })?;
+        self.json_output.timings.render = start.elapsed();
         Ok(())
     }
 }
@@ -1,4 +1,9 @@
-use ratatui::style::Color;
+use std::path::PathBuf;
+
+use ratatui::{
+    layout::Direction,
+    style::Color,
+};
 
 
 pub struct _Color {
@@ -10,6 +15,11 @@ pub struct _Color {
     pub cursor_background: Color,
     pub cursor_foreground: Color,
     pub json_cursor_foreground: Color,
+    pub highlight_background: Color,
+    pub highlight_marker: Color,
+    pub diff_added_foreground: Color,
+    pub diff_removed_foreground: Color,
+    pub diff_modified_foreground: Color,
 }
 
 pub struct _Json {
@@ -18,12 +28,62 @@ pub struct _Json {
     pub json_number: Color,
     pub json_boolean: Color,
     pub json_null: Color,
+    pub json_escape: Color,
     pub indent: usize,
+    // Wrap long lines instead of scrolling them horizontally
+    pub soft_wrap: bool,
+    // Shorten strings longer than elide_length in the middle
+    pub elide_strings: bool,
+    pub elide_length: usize,
+    // Show the invisible characters (ie: zero width space) as \u escapes
+    pub escape_non_printable: bool,
+}
+
+pub struct _Layout {
+    // Direction of the split between the result and the base pane
+    pub direction: Direction,
+    // Whether the result pane goes first (left or top)
+    pub result_first: bool,
+    // Size of the first pane, in percent of the output area
+    pub ratio: u16,
+    // Below this width the panes are stacked vertically
+    pub stack_below_width: u16,
+}
+
+pub struct _Large {
+    // Inputs from this size (in bytes) are memory mapped and sampled
+    pub threshold: u64,
+    // Number of values the live query runs on
+    pub sample_size: usize,
+    // Sample values at random instead of taking the first ones
+    pub random_sample: bool,
+}
+
+pub struct _Schema {
+    // JSON Schemas validating the input and each result
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+}
+
+// How the results are printed, like jq's default, -r and -j
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputMode {
+    Json,
+    // Strings without quotes nor escapes, one result per line
+    Raw,
+    // Raw, without a newline after each result
+    Join,
 }
 
 pub struct Config {
     pub color: _Color,
     pub json: _Json,
+    pub layout: _Layout,
+    pub large: _Large,
+    pub schema: _Schema,
+    // Capturing the mouse disables the terminal text selection
+    pub mouse_capture: bool,
+    pub output_mode: OutputMode,
 }
 
 impl Config {
@@ -38,6 +98,11 @@ impl Config {
                 cursor_background: Color::White,
                 cursor_foreground: Color::Reset,
                 json_cursor_foreground: Color::Green,
+                highlight_background: Color::DarkGray,
+                highlight_marker: Color::Magenta,
+                diff_added_foreground: Color::Green,
+                diff_removed_foreground: Color::Red,
+                diff_modified_foreground: Color::Yellow,
             },
             json: _Json {
                 json_key: Color::Cyan,
@@ -45,8 +110,30 @@ impl Config {
                 json_number: Color::Blue,
                 json_boolean: Color::Yellow,
                 json_null: Color::Red,
+                json_escape: Color::Magenta,
                 indent: 2,
+                soft_wrap: false,
+                elide_strings: false,
+                elide_length: 32,
+                escape_non_printable: false,
+            },
+            layout: _Layout {
+                direction: Direction::Horizontal,
+                result_first: true,
+                ratio: 50,
+                stack_below_width: 80,
+            },
+            large: _Large {
+                threshold: 256 * 1024 * 1024,
+                sample_size: 1000,
+                random_sample: false,
+            },
+            schema: _Schema {
+                input: None,
+                output: None,
             },
+            mouse_capture: true,
+            output_mode: OutputMode::Json,
         }
     }
 }
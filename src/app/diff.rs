@@ -0,0 +1,168 @@
+// Structural diff between two JSON values.
+// Arrays are compared index by index, objects key by key.
+// The changes can be exported as a JSON Patch document (RFC 6902).
+
+use serde_json::{json, Number, Value};
+
+pub enum Change {
+    Add { path: Vec<Value>, value: Value },
+    Remove { path: Vec<Value> },
+    Replace { path: Vec<Value>, value: Value },
+}
+
+// Value of a number as a double, like jq. Numbers out of range are the
+// largest double, as jq prints them.
+fn number_value(n: &Number) -> f64 {
+    let value = n.to_string().parse::<f64>().unwrap_or(f64::NAN);
+    if value.is_infinite() {
+        f64::MAX.copysign(value)
+    } else {
+        value
+    }
+}
+
+// Equality with the numbers compared by value. With `arbitrary_precision`
+// serde_json compares them as written, while jq rewrites them
+// (ie: `1.10` is output as `1.1` and `1e2` as `100`).
+pub fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a == b || number_value(a) == number_value(b),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        },
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| same(a, b)))
+        },
+        (a, b) => a == b,
+    }
+}
+
+pub fn diff(a: &Value, b: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(a, b, &mut Vec::new(), &mut changes);
+    changes
+}
+
+fn diff_into(a: &Value, b: &Value, path: &mut Vec<Value>, changes: &mut Vec<Change>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, a_value) in a {
+                path.push(Value::String(key.clone()));
+                match b.get(key) {
+                    Some(b_value) => diff_into(a_value, b_value, path, changes),
+                    None => changes.push(Change::Remove { path: path.clone() }),
+                }
+                path.pop();
+            }
+            for (key, b_value) in b {
+                if !a.contains_key(key) {
+                    path.push(Value::String(key.clone()));
+                    changes.push(Change::Add { path: path.clone(), value: b_value.clone() });
+                    path.pop();
+                }
+            }
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            let common = std::cmp::min(a.len(), b.len());
+            for i in 0..common {
+                path.push(Value::from(i));
+                diff_into(&a[i], &b[i], path, changes);
+                path.pop();
+            }
+            for (i, value) in b.iter().enumerate().skip(common) {
+                path.push(Value::from(i));
+                changes.push(Change::Add { path: path.clone(), value: value.clone() });
+                path.pop();
+            }
+            // Remove from the end so that the indexes stay valid when applied in order
+            for i in (common..a.len()).rev() {
+                path.push(Value::from(i));
+                changes.push(Change::Remove { path: path.clone() });
+                path.pop();
+            }
+        },
+        (a, b) => {
+            if !same(a, b) {
+                changes.push(Change::Replace { path: path.clone(), value: b.clone() });
+            }
+        },
+    }
+}
+
+// JSON Pointer (RFC 6901) of a jq style path
+pub fn pointer(path: &[Value]) -> String {
+    let mut pointer = String::new();
+    for element in path {
+        pointer.push('/');
+        match element {
+            Value::String(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            other => pointer.push_str(&other.to_string()),
+        }
+    }
+    pointer
+}
+
+pub fn json_patch(changes: &[Change]) -> Value {
+    Value::Array(
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Add { path, value } => json!({
+                    "op": "add",
+                    "path": pointer(path),
+                    "value": value,
+                }),
+                Change::Remove { path } => json!({
+                    "op": "remove",
+                    "path": pointer(path),
+                }),
+                Change::Replace { path, value } => json!({
+                    "op": "replace",
+                    "path": pointer(path),
+                    "value": value,
+                }),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::engine::Engine;
+
+    const TRICKY: &str = include_str!("../../examples/tricky.json");
+
+    fn parse(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn same_numbers_by_value() {
+        assert!(same(&parse("1.10"), &parse("1.1")));
+        assert!(same(&parse("1e2"), &parse("100")));
+        assert!(same(&parse("-0"), &parse("0")));
+        assert!(same(&parse("1e400"), &parse("1.7976931348623157e+308")));
+        assert!(same(&parse("12345678901234567890"), &parse("12345678901234567000")));
+        assert!(!same(&parse("1.1"), &parse("1.2")));
+        assert!(!same(&parse("1"), &parse("\"1\"")));
+        assert!(same(&parse(r#"{"a": [1.10, 1e2]}"#), &parse(r#"{"a": [1.1, 100]}"#)));
+        assert!(!same(&parse(r#"{"a": 1}"#), &parse(r#"{"b": 1}"#)));
+        assert!(!same(&parse("[1, 2]"), &parse("[1]")));
+    }
+
+    // jq rewrites the numbers of its output, the identity changes nothing
+    #[test]
+    fn identity_has_no_changes() {
+        let engine = Engine::new(Vec::new(), std::path::PathBuf::from("."));
+        let output = engine.run(".", &engine.parse(TRICKY)).unwrap();
+        let changes = diff(&parse(TRICKY), &parse(&output));
+        assert!(changes.is_empty(), "{}", json_patch(&changes));
+    }
+
+    #[test]
+    fn changed_numbers() {
+        let changes = diff(&parse(r#"{"a": 1.10, "b": 2}"#), &parse(r#"{"a": 1.1, "b": 3}"#));
+        assert_eq!(json_patch(&changes), json!([{"op": "replace", "path": "/b", "value": 3}]));
+    }
+}
@@ -0,0 +1,461 @@
+// Flattened JSON document displayed by the Json widget.
+// Nodes are stored in document order, each container is directly followed
+// by its descendants. A large document costs one small entry per node,
+// the styled lines are only built for the lines being drawn.
+// The parent and the children of every node are indexed, the node at a
+// path is found without walking the siblings.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+
+// With `arbitrary_precision`, serde_json gives a number as a map with this
+// single key, its value is the number as written (ie: `1.10`), only the
+// exponent is normalized (ie: `1E2` is `1e+2`)
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+pub enum Key {
+    Root,
+    Name(Box<str>),
+    Index(usize),
+}
+
+pub enum Kind {
+    Null,
+    Bool(bool),
+    Number(Box<str>),
+    String(Box<str>),
+    Array,
+    Object,
+}
+
+pub struct Node {
+    pub key: Key,
+    pub kind: Kind,
+    pub depth: u32,
+    // The next sibling is at index + 1 + descendants
+    pub descendants: usize,
+    pub children: usize,
+    // Last child of its parent, not followed by a comma
+    pub last: bool,
+}
+
+impl Node {
+    pub fn is_container(&self) -> bool {
+        matches!(self.kind, Kind::Array | Kind::Object)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Part {
+    // `"key": [` of an expanded container
+    Open,
+    // `]` of an expanded container
+    Close,
+    // A scalar, an empty or a folded container
+    Whole,
+}
+
+#[derive(Clone, Copy)]
+pub struct DocLine {
+    pub node: usize,
+    pub part: Part,
+}
+
+pub struct Document {
+    nodes: Vec<Node>,
+    // Container of every node, the root is its own parent
+    parents: Vec<usize>,
+    // Children of the containers, grouped by container: those of a node
+    // are at `first_child[node]..first_child[node] + children`
+    child_list: Vec<usize>,
+    first_child: Vec<usize>,
+}
+
+impl Document {
+    // Fails if `raw` is not exactly one JSON value
+    pub fn parse(raw: &str) -> Result<Document, serde_json::Error> {
+        let mut nodes = Vec::new();
+        let mut deserializer = serde_json::Deserializer::from_str(raw);
+        NodeSeed { nodes: &mut nodes, key: Key::Root, depth: 0 }.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        nodes[0].last = true;
+        let mut parents = vec![0; nodes.len()];
+        let mut child_list = Vec::with_capacity(nodes.len() - 1);
+        let mut first_child = vec![0; nodes.len()];
+        for (index, node) in nodes.iter().enumerate() {
+            first_child[index] = child_list.len();
+            let end = index + 1 + node.descendants;
+            let mut child = index + 1;
+            while child < end {
+                parents[child] = index;
+                child_list.push(child);
+                child += 1 + nodes[child].descendants;
+            }
+        }
+        Ok(Document { nodes, parents, child_list, first_child })
+    }
+
+    pub fn node(&self, index: usize) -> &Node {
+        &self.nodes[index]
+    }
+
+    pub fn children(&self, index: usize) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        let first = self.first_child[index];
+        self.child_list[first..first + self.nodes[index].children].iter().copied()
+    }
+
+    // Node at a jq style path (ie: ["a",0])
+    pub fn find(&self, path: &[Value]) -> Option<usize> {
+        let mut index = 0;
+        for element in path {
+            index = match (&self.nodes[index].kind, element) {
+                // jq keeps the last of duplicated keys
+                (Kind::Object, Value::String(name)) => self.children(index)
+                    .rev()
+                    .find(|child| matches!(&self.nodes[*child].key, Key::Name(key) if **key == **name))?,
+                (Kind::Array, Value::Number(i)) => {
+                    let i = usize::try_from(i.as_u64()?).ok()?;
+                    if i >= self.nodes[index].children {
+                        return None;
+                    }
+                    self.child_list[self.first_child[index] + i]
+                },
+                _ => return None,
+            };
+        }
+        Some(index)
+    }
+
+    // Container of a node, None for the root
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        match index {
+            0 => None,
+            _ => Some(self.parents[index]),
+        }
+    }
+
+    // jq style path of a node
+    pub fn path(&self, index: usize) -> Vec<Value> {
+        let mut path = Vec::new();
+        let mut current = index;
+        while current != 0 {
+            match &self.nodes[current].key {
+                Key::Name(name) => path.push(Value::String(name.to_string())),
+                Key::Index(i) => path.push(Value::from(*i)),
+                Key::Root => {},
+            }
+            current = self.parents[current];
+        }
+        path.reverse();
+        path
+    }
+
+    pub fn to_value(&self, index: usize) -> Value {
+        match &self.nodes[index].kind {
+            Kind::Null => Value::Null,
+            Kind::Bool(b) => Value::Bool(*b),
+            Kind::Number(n) => serde_json::from_str(n).unwrap_or(Value::Null),
+            Kind::String(s) => Value::String(s.to_string()),
+            Kind::Array => Value::Array(
+                self.children(index).map(|child| self.to_value(child)).collect()
+            ),
+            Kind::Object => Value::Object(
+                self.children(index)
+                    .map(|child| {
+                        let key = match &self.nodes[child].key {
+                            Key::Name(name) => name.to_string(),
+                            _ => String::new(),
+                        };
+                        (key, self.to_value(child))
+                    })
+                    .collect()
+            ),
+        }
+    }
+
+    // Lines of the document with the `folded` containers collapsed,
+    // and the line range of every node. Nodes hidden in a folded
+    // container get the line of that container.
+    pub fn lines(&self, folded: &HashSet<usize>) -> (Vec<DocLine>, Vec<(usize, usize)>) {
+        let mut lines = Vec::new();
+        let mut ranges = vec![(0, 0); self.nodes.len()];
+        // Expanded containers waiting for their closing line
+        let mut open: Vec<usize> = Vec::new();
+        let mut index = 0;
+        while index < self.nodes.len() {
+            while let Some(&container) = open.last() {
+                if index <= container + self.nodes[container].descendants {
+                    break;
+                }
+                lines.push(DocLine { node: container, part: Part::Close });
+                ranges[container].1 = lines.len() - 1;
+                open.pop();
+            }
+            let node = &self.nodes[index];
+            let line = lines.len();
+            if node.is_container() && node.children > 0 && !folded.contains(&index) {
+                lines.push(DocLine { node: index, part: Part::Open });
+                ranges[index] = (line, line);
+                open.push(index);
+                index += 1;
+            } else {
+                lines.push(DocLine { node: index, part: Part::Whole });
+                for range in &mut ranges[index..=index + node.descendants] {
+                    *range = (line, line);
+                }
+                index += 1 + node.descendants;
+            }
+        }
+        while let Some(container) = open.pop() {
+            lines.push(DocLine { node: container, part: Part::Close });
+            ranges[container].1 = lines.len() - 1;
+        }
+        (lines, ranges)
+    }
+}
+
+// Appends the deserialized value, and its descendants, to `nodes`
+struct NodeSeed<'n> {
+    nodes: &'n mut Vec<Node>,
+    key: Key,
+    depth: u32,
+}
+
+impl NodeSeed<'_> {
+    fn push(self, kind: Kind) {
+        self.nodes.push(Node {
+            key: self.key,
+            kind,
+            depth: self.depth,
+            descendants: 0,
+            children: 0,
+            last: false,
+        });
+    }
+
+    // Push a container, `fill` appends its children at the given depth
+    // and returns how many there were and the index of the last one
+    fn push_container<E, F>(self, kind: Kind, fill: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Vec<Node>, u32) -> Result<(usize, Option<usize>), E>,
+    {
+        let nodes = self.nodes;
+        let depth = self.depth;
+        let index = nodes.len();
+        nodes.push(Node {
+            key: self.key,
+            kind,
+            depth,
+            descendants: 0,
+            children: 0,
+            last: false,
+        });
+        let (children, last_child) = fill(nodes, depth + 1)?;
+        if let Some(last_child) = last_child {
+            nodes[last_child].last = true;
+        }
+        let descendants = nodes.len() - index - 1;
+        let node = &mut nodes[index];
+        node.children = children;
+        node.descendants = descendants;
+        Ok(())
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for NodeSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NodeSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        self.push(Kind::Null);
+        Ok(())
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<(), E> {
+        self.push(Kind::Bool(b));
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<(), E> {
+        self.push(Kind::Number(n.to_string().into()));
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<(), E> {
+        self.push(Kind::Number(n.to_string().into()));
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<(), E> {
+        self.push(Kind::Number(Value::from(n).to_string().into()));
+        Ok(())
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<(), E> {
+        self.push(Kind::String(s.into()));
+        Ok(())
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<(), E> {
+        self.push(Kind::String(s.into_boxed_str()));
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.push_container(Kind::Array, |nodes, depth| {
+            let mut count = 0;
+            let mut last_child = None;
+            loop {
+                let child = nodes.len();
+                let seed = NodeSeed { nodes: &mut *nodes, key: Key::Index(count), depth };
+                if seq.next_element_seed(seed)?.is_none() {
+                    break;
+                }
+                last_child = Some(child);
+                count += 1;
+            }
+            Ok((count, last_child))
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let first_key = map.next_key::<String>()?;
+        if first_key.as_deref() == Some(NUMBER_TOKEN) {
+            let number: String = map.next_value()?;
+            self.push(Kind::Number(number.into_boxed_str()));
+            return Ok(());
+        }
+        self.push_container(Kind::Object, |nodes, depth| {
+            let mut count = 0;
+            let mut last_child = None;
+            let mut key = first_key;
+            while let Some(name) = key {
+                let child = nodes.len();
+                map.next_value_seed(NodeSeed { nodes: &mut *nodes, key: Key::Name(name.into_boxed_str()), depth })?;
+                last_child = Some(child);
+                count += 1;
+                key = map.next_key::<String>()?;
+            }
+            Ok((count, last_child))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRICKY: &str = include_str!("../../examples/tricky.json");
+
+    fn key(document: &Document, index: usize) -> String {
+        match &document.node(index).key {
+            Key::Name(name) => name.to_string(),
+            Key::Index(i) => i.to_string(),
+            Key::Root => String::new(),
+        }
+    }
+
+    fn number(document: &Document, path: &[Value]) -> String {
+        let index = document.find(path).unwrap_or_else(|| panic!("no node at {:?}", path));
+        match &document.node(index).kind {
+            Kind::Number(n) => n.to_string(),
+            _ => panic!("not a number at {:?}", path),
+        }
+    }
+
+    #[test]
+    fn keys_in_document_order() {
+        let document = Document::parse(TRICKY).unwrap();
+        let keys: Vec<String> = document.children(0).map(|child| key(&document, child)).collect();
+        assert_eq!(keys, [
+            "zebra", "apple", "", "with space", "with \"quotes\"", "with\\backslash",
+            "unicode é ü 日本", "escaped é", "10", "9", "numbers", "duplicated", "duplicated", "nested",
+        ]);
+        let nested = document.find(&[Value::from("nested"), Value::from(0)]).unwrap();
+        let keys: Vec<String> = document.children(nested).map(|child| key(&document, child)).collect();
+        assert_eq!(keys, ["b", "a"]);
+    }
+
+    // Only the exponent is normalized, see NUMBER_TOKEN
+    #[test]
+    fn numbers_as_written() {
+        let document = Document::parse(TRICKY).unwrap();
+        let cases = [
+            ("u64_max", "18446744073709551615"),
+            ("above_u64", "18446744073709551616"),
+            ("id", "12345678901234567890"),
+            ("i64_min", "-9223372036854775808"),
+            ("below_i64", "-9223372036854775809"),
+            ("trailing_zero", "1.10"),
+            ("many_zeros", "1.000000"),
+            ("negative_zero", "-0"),
+            ("negative_zero_decimal", "-0.0"),
+            ("exponent", "1e+2"),
+            ("exponent_upper", "1e+2"),
+            ("exponent_negative", "2.5e-3"),
+            ("huge_exponent", "1e+400"),
+            ("tiny", "1e-400"),
+            ("pi", "3.14159265358979323846264338327950288"),
+            ("max_safe", "9007199254740993"),
+            ("zero", "0"),
+        ];
+        for (name, text) in cases {
+            assert_eq!(number(&document, &[Value::from("numbers"), Value::from(name)]), text, "{}", name);
+        }
+    }
+
+    #[test]
+    fn last_duplicated_key_found() {
+        let document = Document::parse(TRICKY).unwrap();
+        let index = document.find(&[Value::from("duplicated")]).unwrap();
+        assert!(matches!(&document.node(index).kind, Kind::Number(n) if &**n == "2"));
+        assert_eq!(document.path(index), [Value::from("duplicated")]);
+    }
+
+    // Every node is found at its path, and is a child of its parent
+    #[test]
+    fn paths_and_parents() {
+        let document = Document::parse(r#"{"a": [1, [2, {"b": []}], {}], "c": {"d": null}, "e": 3}"#).unwrap();
+        let node_count = document.node(0).descendants + 1;
+        for index in 0..node_count {
+            let path = document.path(index);
+            assert_eq!(document.find(&path), Some(index), "{:?}", path);
+            match document.parent(index) {
+                Some(parent) => assert!(document.children(parent).any(|child| child == index)),
+                None => assert_eq!(index, 0),
+            }
+        }
+        let b = document.find(&[Value::from("a"), Value::from(1), Value::from(1), Value::from("b")]).unwrap();
+        assert!(matches!(document.node(b).kind, Kind::Array));
+        assert_eq!(document.path(b), [Value::from("a"), Value::from(1), Value::from(1), Value::from("b")]);
+        assert_eq!(document.children(0).count(), 3);
+        assert_eq!(document.children(b).count(), 0);
+        assert_eq!(document.find(&[Value::from("a"), Value::from(3)]), None);
+        assert_eq!(document.find(&[Value::from("a"), Value::from("b")]), None);
+        assert_eq!(document.find(&[Value::from("e"), Value::from(0)]), None);
+        assert_eq!(document.find(&[Value::from("f")]), None);
+    }
+}
@@ -0,0 +1,539 @@
+// Edits of the input document. Each edit replaces a single range of its
+// text, the rest of the file keeps its formatting (indentation, spacing,
+// numbers and escapes as written), and is undone by the reverse replacement.
+
+use std::ops::Range;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app::document::{Document, Key, Kind};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    // Replace the scalar under the cursor
+    Change,
+    // Rename the key of an object member
+    Rename,
+    Delete,
+    // Add a node after the one under the cursor
+    Add,
+    // Add a node at the end of the container under the cursor
+    Append,
+}
+
+// Replacement of `removed`, at `start` in the text, by `inserted`
+pub struct Splice {
+    pub start: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl Splice {
+    pub fn apply(&self, text: &mut String) {
+        text.replace_range(self.start..self.start + self.removed.len(), &self.inserted);
+    }
+
+    pub fn reverse(&self) -> Splice {
+        Splice {
+            start: self.start,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+}
+
+// Where a node is written: its key (quotes included) and its value
+struct Span {
+    key: Option<Range<usize>>,
+    value: Range<usize>,
+}
+
+impl Span {
+    fn start(&self) -> usize {
+        self.key.as_ref().map_or(self.value.start, |key| key.start)
+    }
+}
+
+// Spans of the nodes in document order, like the nodes of a Document.
+// The text is known to be valid JSON, the scanner doesn't check it.
+struct Scanner<'t> {
+    bytes: &'t [u8],
+    pos: usize,
+    spans: Vec<Span>,
+}
+
+impl Scanner<'_> {
+    // 0 past the end, never a valid JSON byte
+    fn peek(&self) -> u8 {
+        self.bytes.get(self.pos).copied().unwrap_or(0)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), b' ' | b'\t' | b'\n' | b'\r') {
+            self.pos += 1;
+        }
+    }
+
+    fn string(&mut self) -> Range<usize> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                0 => break,
+                b'"' => {
+                    self.pos += 1;
+                    break;
+                },
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+        start..self.pos
+    }
+
+    fn value(&mut self, key: Option<Range<usize>>) {
+        self.skip_whitespace();
+        let start = self.pos;
+        let index = self.spans.len();
+        self.spans.push(Span { key, value: start..start });
+        match self.peek() {
+            b'{' | b'[' => {
+                let close = if self.peek() == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        0 => break,
+                        b',' => self.pos += 1,
+                        c if c == close => {
+                            self.pos += 1;
+                            break;
+                        },
+                        _ if close == b'}' => {
+                            let key = self.string();
+                            self.skip_whitespace();
+                            // The colon
+                            self.pos += 1;
+                            self.value(Some(key));
+                        },
+                        _ => self.value(None),
+                    }
+                }
+            },
+            b'"' => {
+                self.string();
+            },
+            _ => {
+                while !matches!(self.peek(), 0 | b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r') {
+                    self.pos += 1;
+                }
+            },
+        }
+        self.spans[index].value.end = self.pos;
+    }
+}
+
+fn spans(raw: &str, document: &Document) -> Result<Vec<Span>, String> {
+    let mut scanner = Scanner { bytes: raw.as_bytes(), pos: 0, spans: Vec::new() };
+    scanner.value(None);
+    if scanner.spans.len() != document.node(0).descendants + 1 {
+        return Err("Can't locate the nodes in the text of the document".to_string());
+    }
+    Ok(scanner.spans)
+}
+
+// Whitespace before `pos` on its line, or up to the previous token
+fn whitespace_before(raw: &str, pos: usize) -> &str {
+    let before = &raw[..pos];
+    &before[before.trim_end_matches([' ', '\t', '\n', '\r']).len()..]
+}
+
+// Indentation of the line of `pos`
+fn line_indent(raw: &str, pos: usize) -> &str {
+    let line = &raw[raw[..pos].rfind('\n').map_or(0, |i| i + 1)..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+// Indentation of the first indented line, the unit of a pretty printed document
+fn indent_unit(raw: &str) -> &str {
+    raw.lines()
+        .skip(1)
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("  ")
+}
+
+// A value printed like the document `raw`: on one line, or indented with the same unit
+pub fn format_like(raw: &str, value: &Value) -> String {
+    if !raw.trim().contains('\n') {
+        return value.to_string();
+    }
+    let mut output = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent_unit(raw).as_bytes());
+    // Writing to a Vec can't fail
+    let _ = value.serialize(&mut serde_json::Serializer::with_formatter(&mut output, formatter));
+    String::from_utf8(output).unwrap_or_default()
+}
+
+// The value as typed, checked to be one JSON value
+fn parse_value(text: &str) -> Result<String, String> {
+    let text = text.trim();
+    match serde_json::from_str::<Value>(text) {
+        Ok(_) => Ok(text.to_string()),
+        Err(_) => Err("Not a JSON value, strings need quotes".to_string()),
+    }
+}
+
+fn check_key(document: &Document, parent: usize, key: &str, except: Option<usize>) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("A key is required".to_string());
+    }
+    let exists = document.children(parent)
+        .filter(|child| Some(*child) != except)
+        .any(|child| matches!(&document.node(child).key, Key::Name(name) if **name == *key));
+    if exists {
+        return Err(format!("The key '{}' already exists", key));
+    }
+    Ok(())
+}
+
+fn child_path(document: &Document, parent: usize, key: Value) -> Vec<Value> {
+    let mut path = document.path(parent);
+    path.push(key);
+    path
+}
+
+// The fields to fill for an action on a node, with their initial values
+pub fn fields(raw: &str, document: &Document, node: usize, action: Action) -> Result<Vec<(&'static str, String)>, String> {
+    let member = |container: usize| match document.node(container).kind {
+        Kind::Object => vec![("Key", String::new()), ("Value", String::new())],
+        _ => vec![("Value", String::new())],
+    };
+    match action {
+        Action::Change => {
+            if document.node(node).is_container() {
+                return Err("Only scalars can be changed, add or delete the nodes of a container".to_string());
+            }
+            let spans = spans(raw, document)?;
+            Ok(vec![("Value", raw[spans[node].value.clone()].to_string())])
+        },
+        Action::Rename => match &document.node(node).key {
+            Key::Name(name) => Ok(vec![("Key", name.to_string())]),
+            _ => Err("Only the keys of an object can be renamed".to_string()),
+        },
+        Action::Delete => match document.parent(node) {
+            Some(_) => Ok(Vec::new()),
+            None => Err("The whole document can't be deleted".to_string()),
+        },
+        Action::Add => match document.parent(node) {
+            Some(parent) => Ok(member(parent)),
+            None => Err("Nothing can be added after the whole document".to_string()),
+        },
+        Action::Append if document.node(node).is_container() => Ok(member(node)),
+        Action::Append => Err("Not an array or an object, `a` adds a node after it".to_string()),
+    }
+}
+
+// Apply an action with the values of its fields. Returns the change of the
+// text and the path of the node to select afterwards.
+pub fn apply(raw: &str, document: &Document, node: usize, action: Action, values: &[String]) -> Result<(Splice, Vec<Value>), String> {
+    let spans = spans(raw, document)?;
+    let splice = |range: Range<usize>, inserted: String| Splice {
+        start: range.start,
+        removed: raw[range].to_string(),
+        inserted,
+    };
+    // `"key": value` or `value`, depending on the container
+    let member = |container: usize, values: &[String], separator: &str| -> Result<(String, Value), String> {
+        match document.node(container).kind {
+            Kind::Object => {
+                let key = values.first().map(String::as_str).unwrap_or_default();
+                check_key(document, container, key, None)?;
+                let value = parse_value(values.get(1).map(String::as_str).unwrap_or_default())?;
+                let text = format!("{}{}{}", Value::from(key), separator, value);
+                Ok((text, Value::from(key)))
+            },
+            _ => {
+                let value = parse_value(values.first().map(String::as_str).unwrap_or_default())?;
+                Ok((value, Value::from(document.node(container).children)))
+            },
+        }
+    };
+    let node_span = &spans[node];
+    match action {
+        Action::Change => {
+            let value = parse_value(values.first().map(String::as_str).unwrap_or_default())?;
+            Ok((splice(node_span.value.clone(), value), document.path(node)))
+        },
+        Action::Rename => {
+            let (parent, key) = match (document.parent(node), &node_span.key) {
+                (Some(parent), Some(key)) => (parent, key.clone()),
+                _ => return Err("Only the keys of an object can be renamed".to_string()),
+            };
+            let name = values.first().map(String::as_str).unwrap_or_default();
+            check_key(document, parent, name, Some(node))?;
+            Ok((splice(key, Value::from(name).to_string()), child_path(document, parent, Value::from(name))))
+        },
+        Action::Delete => {
+            let parent = document.parent(node).ok_or("The whole document can't be deleted")?;
+            let siblings: Vec<usize> = document.children(parent).collect();
+            let position = siblings.iter().position(|sibling| *sibling == node).unwrap_or_default();
+            let next = siblings.get(position + 1);
+            let previous = position.checked_sub(1).map(|i| siblings[i]);
+            // Remove the separator with the node, an array item is
+            // then replaced by the next one at the same path
+            let (range, path) = match (next, previous) {
+                (Some(next), _) => (
+                    node_span.start()..spans[*next].start(),
+                    match document.node(parent).kind {
+                        Kind::Object => document.path(*next),
+                        _ => document.path(node),
+                    }
+                ),
+                (None, Some(previous)) => (spans[previous].value.end..node_span.value.end, document.path(previous)),
+                (None, None) => {
+                    let container = &spans[parent].value;
+                    (container.start + 1..container.end - 1, document.path(parent))
+                },
+            };
+            Ok((splice(range, String::new()), path))
+        },
+        Action::Add | Action::Append => {
+            let (container, after) = match action {
+                Action::Add => (
+                    document.parent(node).ok_or("Nothing can be added after the whole document")?,
+                    Some(node)
+                ),
+                _ if !document.node(node).is_container() => {
+                    return Err("Not an array or an object, `a` adds a node after it".to_string());
+                },
+                _ => (node, document.children(node).next_back()),
+            };
+            // Write the new node like its sibling, or like the document
+            let multiline = raw.trim().contains('\n');
+            let separator = match after.and_then(|sibling| spans[sibling].key.as_ref().map(|key| (sibling, key))) {
+                Some((sibling, key)) => &raw[key.end..spans[sibling].value.start],
+                None if multiline => ": ",
+                None => ":",
+            };
+            let (text, key) = member(container, values, separator)?;
+            let path_key = match (action, &document.node(container).kind) {
+                (Action::Add, Kind::Array) => {
+                    Value::from(document.children(container).position(|child| child == node).unwrap_or_default() + 1)
+                },
+                _ => key,
+            };
+            let path = child_path(document, container, path_key);
+            match after {
+                Some(sibling) => {
+                    let end = spans[sibling].value.end;
+                    // Spaced like the next sibling, the first one follows the bracket
+                    let next = document.children(container).skip_while(|child| *child != sibling).nth(1);
+                    let indent = whitespace_before(raw, spans[next.unwrap_or(sibling)].start());
+                    Ok((splice(end..end, format!(",{}{}", indent, text)), path))
+                },
+                None => {
+                    let range = &spans[container].value;
+                    let inserted = if multiline {
+                        let indent = line_indent(raw, range.start);
+                        format!("\n{}{}{}\n{}", indent, indent_unit(raw), text, indent)
+                    } else {
+                        text
+                    };
+                    Ok((splice(range.start + 1..range.end - 1, inserted), path))
+                },
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPACT: &str = r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#;
+
+    const PRETTY: &str = r#"{
+    "a": 1,
+    "b": [
+        1,
+        2,
+        3
+    ],
+    "c": {
+        "d": "x\"y\\z"
+    },
+    "q\"k\\": "\\",
+    "e": [],
+    "f": {},
+    "g": [
+        9
+    ]
+}
+"#;
+
+    fn run(raw: &str, at: &[Value], action: Action, values: &[&str]) -> Result<(String, Vec<Value>), String> {
+        let document = Document::parse(raw).unwrap();
+        let node = document.find(at).unwrap_or_else(|| panic!("no node at {:?}", at));
+        let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        let (splice, selected) = apply(raw, &document, node, action, &values)?;
+        let mut text = raw.to_string();
+        splice.apply(&mut text);
+        let mut undone = text.clone();
+        splice.reverse().apply(&mut undone);
+        assert_eq!(undone, raw, "undo of {:?}", text);
+        Ok((text, selected))
+    }
+
+    // The action gives the expected document, keys in order, on both fixtures
+    fn check(at: &[Value], action: Action, values: &[&str], expected: &str, selected: &[Value]) {
+        let expected: Value = serde_json::from_str(expected).unwrap();
+        for raw in [COMPACT, PRETTY] {
+            let (text, path) = run(raw, at, action, values).unwrap_or_else(|error| panic!("{:?}: {}", at, error));
+            let parsed: Value = serde_json::from_str(&text).unwrap_or_else(|error| panic!("{}: {}", error, text));
+            assert_eq!(parsed.to_string(), expected.to_string(), "{:?} on {}", at, raw);
+            assert_eq!(path, selected, "selected path of {:?}", at);
+        }
+    }
+
+    fn check_error(at: &[Value], action: Action, values: &[&str]) {
+        for raw in [COMPACT, PRETTY] {
+            assert!(run(raw, at, action, values).is_err(), "{:?} accepted", at);
+        }
+    }
+
+    #[test]
+    fn change() {
+        check(&[Value::from("a")], Action::Change, &["42"],
+            r#"{"a":42,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("a")]);
+        check(&[Value::from("c"), Value::from("d")], Action::Change, &[r#" "new \" \\" "#],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"new \" \\"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("c"), Value::from("d")]);
+        check(&[Value::from("q\"k\\")], Action::Change, &["[true]"],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":[true],"e":[],"f":{},"g":[9]}"#,
+            &[Value::from("q\"k\\")]);
+        check_error(&[Value::from("a")], Action::Change, &["not json"]);
+    }
+
+    #[test]
+    fn rename() {
+        check(&[Value::from("c")], Action::Rename, &["new\"key"],
+            r#"{"a":1,"b":[1,2,3],"new\"key":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("new\"key")]);
+        check(&[Value::from("q\"k\\")], Action::Rename, &["plain"],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"plain":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("plain")]);
+        // Renamed to itself
+        check(&[Value::from("a")], Action::Rename, &["a"], COMPACT, &[Value::from("a")]);
+        check_error(&[Value::from("a")], Action::Rename, &["b"]);
+        check_error(&[Value::from("a")], Action::Rename, &["q\"k\\"]);
+        check_error(&[Value::from("a")], Action::Rename, &[""]);
+        check_error(&[Value::from("b"), Value::from(0)], Action::Rename, &["x"]);
+    }
+
+    #[test]
+    fn add() {
+        check(&[Value::from("b"), Value::from(0)], Action::Add, &["7"],
+            r#"{"a":1,"b":[1,7,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("b"), Value::from(1)]);
+        check(&[Value::from("b"), Value::from(2)], Action::Add, &["4"],
+            r#"{"a":1,"b":[1,2,3,4],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("b"), Value::from(3)]);
+        check(&[Value::from("q\"k\\")], Action::Add, &["z", "null"],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","z":null,"e":[],"f":{},"g":[9]}"#,
+            &[Value::from("z")]);
+        check(&[Value::from("g")], Action::Add, &["h", "\"last\""],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9],"h":"last"}"#,
+            &[Value::from("h")]);
+        check_error(&[Value::from("a")], Action::Add, &["b", "1"]);
+        check_error(&[Value::from("a")], Action::Add, &["new", "not json"]);
+        check_error(&[], Action::Add, &["1"]);
+    }
+
+    #[test]
+    fn append() {
+        check(&[Value::from("b")], Action::Append, &["4"],
+            r#"{"a":1,"b":[1,2,3,4],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("b"), Value::from(3)]);
+        check(&[Value::from("e")], Action::Append, &["1"],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[1],"f":{},"g":[9]}"#,
+            &[Value::from("e"), Value::from(0)]);
+        check(&[Value::from("f")], Action::Append, &["k", "{}"],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{"k":{}},"g":[9]}"#,
+            &[Value::from("f"), Value::from("k")]);
+        check(&[Value::from("c")], Action::Append, &["e", "2"],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z","e":2},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("c"), Value::from("e")]);
+        check_error(&[Value::from("c")], Action::Append, &["d", "2"]);
+        check_error(&[Value::from("a")], Action::Append, &["1"]);
+    }
+
+    #[test]
+    fn delete() {
+        // First, middle and last item, then the only one
+        check(&[Value::from("b"), Value::from(0)], Action::Delete, &[],
+            r#"{"a":1,"b":[2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("b"), Value::from(0)]);
+        check(&[Value::from("b"), Value::from(1)], Action::Delete, &[],
+            r#"{"a":1,"b":[1,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("b"), Value::from(1)]);
+        check(&[Value::from("b"), Value::from(2)], Action::Delete, &[],
+            r#"{"a":1,"b":[1,2],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("b"), Value::from(1)]);
+        check(&[Value::from("g"), Value::from(0)], Action::Delete, &[],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[]}"#,
+            &[Value::from("g")]);
+        // Same with the members of objects
+        check(&[Value::from("a")], Action::Delete, &[],
+            r#"{"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("b")]);
+        check(&[Value::from("q\"k\\")], Action::Delete, &[],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"e":[],"f":{},"g":[9]}"#,
+            &[Value::from("e")]);
+        check(&[Value::from("g")], Action::Delete, &[],
+            r#"{"a":1,"b":[1,2,3],"c":{"d":"x\"y\\z"},"q\"k\\":"\\","e":[],"f":{}}"#,
+            &[Value::from("f")]);
+        check(&[Value::from("c"), Value::from("d")], Action::Delete, &[],
+            r#"{"a":1,"b":[1,2,3],"c":{},"q\"k\\":"\\","e":[],"f":{},"g":[9]}"#,
+            &[Value::from("c")]);
+        check_error(&[], Action::Delete, &[]);
+    }
+
+    // The rest of the file keeps its formatting
+    #[test]
+    fn formatting_kept() {
+        let (text, _) = run(PRETTY, &[Value::from("b"), Value::from(0)], Action::Add, &["7"]).unwrap();
+        assert!(text.contains("[\n        1,\n        7,\n        2,"), "{}", text);
+        let (text, _) = run(PRETTY, &[Value::from("e")], Action::Append, &["1"]).unwrap();
+        assert!(text.contains("\"e\": [\n        1\n    ],"), "{}", text);
+        let (text, _) = run(PRETTY, &[Value::from("b"), Value::from(2)], Action::Delete, &[]).unwrap();
+        assert!(text.contains("[\n        1,\n        2\n    ],"), "{}", text);
+        let (text, _) = run(COMPACT, &[Value::from("q\"k\\")], Action::Add, &["z", "1"]).unwrap();
+        assert!(text.contains(r#""q\"k\\":"\\","z":1,"e""#), "{}", text);
+    }
+
+    #[test]
+    fn fields_of_actions() {
+        let document = Document::parse(COMPACT).unwrap();
+        let node = |at: &[Value]| document.find(at).unwrap();
+        let fields_of = |at: &[Value], action| fields(COMPACT, &document, node(at), action);
+        assert_eq!(fields_of(&[Value::from("c"), Value::from("d")], Action::Change), Ok(vec![("Value", r#""x\"y\\z""#.to_string())]));
+        assert_eq!(fields_of(&[Value::from("q\"k\\")], Action::Rename), Ok(vec![("Key", "q\"k\\".to_string())]));
+        assert_eq!(fields_of(&[Value::from("b"), Value::from(0)], Action::Add), Ok(vec![("Value", String::new())]));
+        assert_eq!(fields_of(&[Value::from("f")], Action::Append), Ok(vec![("Key", String::new()), ("Value", String::new())]));
+        assert!(fields_of(&[Value::from("b")], Action::Change).is_err());
+        assert!(fields_of(&[Value::from("b"), Value::from(0)], Action::Rename).is_err());
+        assert!(fields_of(&[], Action::Delete).is_err());
+        assert!(fields_of(&[Value::from("a")], Action::Append).is_err());
+    }
+
+    #[test]
+    fn formatted_like_the_document() {
+        let value: Value = serde_json::from_str(r#"{"a":[1,{"b":null}]}"#).unwrap();
+        assert_eq!(format_like(COMPACT, &value), r#"{"a":[1,{"b":null}]}"#);
+        assert_eq!(format_like(PRETTY, &value), "{\n    \"a\": [\n        1,\n        {\n            \"b\": null\n        }\n    ]\n}");
+        assert_eq!(format_like("[\n\t1\n]", &value), "{\n\t\"a\": [\n\t\t1,\n\t\t{\n\t\t\t\"b\": null\n\t\t}\n\t]\n}");
+    }
+}
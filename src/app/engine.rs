@@ -0,0 +1,557 @@
+// Thin wrapper around libjq, used to run the live query.
+// jq-rs does not let us set the module search path, and compile errors
+// (including the ones raised inside modules) are printed on stderr.
+// Talking to jq-sys directly solves both.
+// JSONPath and JMESPath queries run on the same parsed input, converted
+// to serde values.
+
+use std::cell::{OnceCell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde_json::Value;
+
+use jq_sys::{
+    jq_compile, jq_format_error, jq_get_error_message, jq_halted, jq_init, jq_next,
+    jq_set_attr, jq_set_error_cb, jq_start, jq_state, jq_teardown, jv, jv_array,
+    jv_array_append, jv_copy, jv_dump_string, jv_free, jv_get_kind, jv_invalid_get_msg,
+    jv_invalid_has_msg, jv_kind_JV_KIND_INVALID, jv_kind_JV_KIND_STRING, jv_parser,
+    jv_parser_free, jv_parser_new, jv_parser_next, jv_parser_set_buf, jv_string,
+    jv_string_value, JV_PARSE_STREAMING,
+};
+
+use crate::app::{jmespath, jsonpath};
+
+// The parser takes the buffer length as an int, larger inputs are fed
+// to it in chunks
+const CHUNK_SIZE: usize = 1 << 30;
+// Chunks of a memory mapped input, small enough to report the progress
+const PROGRESS_CHUNK_SIZE: usize = 16 << 20;
+
+// Total size of the results memoized for an input
+const MEMO_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Jq,
+    JsonPath,
+    JmesPath,
+}
+
+impl Language {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Jq => "jq",
+            Language::JsonPath => "JSONPath",
+            Language::JmesPath => "JMESPath",
+        }
+    }
+
+    // The language Alt-L switches to
+    pub fn next(&self) -> Language {
+        match self {
+            Language::Jq => Language::JsonPath,
+            Language::JsonPath => Language::JmesPath,
+            Language::JmesPath => Language::Jq,
+        }
+    }
+}
+
+// Owned jv, freed on drop
+struct Jv {
+    ptr: jv,
+}
+
+impl Jv {
+    fn string(value: &str) -> Jv {
+        // Paths and attribute names never contain a nul byte
+        let value = CString::new(value).unwrap_or_default();
+        Jv { ptr: unsafe { jv_string(value.as_ptr()) } }
+    }
+
+    fn copy(&self) -> Jv {
+        Jv { ptr: unsafe { jv_copy(self.ptr) } }
+    }
+
+    fn is_valid(&self) -> bool {
+        unsafe { jv_get_kind(self.ptr) != jv_kind_JV_KIND_INVALID }
+    }
+
+    fn is_string(&self) -> bool {
+        unsafe { jv_get_kind(self.ptr) == jv_kind_JV_KIND_STRING }
+    }
+
+    fn has_msg(&self) -> bool {
+        unsafe { jv_invalid_has_msg(jv_copy(self.ptr)) != 0 }
+    }
+
+    fn msg(&self) -> Jv {
+        Jv { ptr: unsafe { jv_invalid_get_msg(jv_copy(self.ptr)) } }
+    }
+
+    fn to_json(&self) -> String {
+        let dump = Jv { ptr: unsafe { jv_dump_string(jv_copy(self.ptr), 0) } };
+        dump.string_value()
+    }
+
+    // Only meaningful for string values
+    fn string_value(&self) -> String {
+        unsafe { c_string(jv_string_value(self.ptr)) }
+    }
+
+    // Give the jv to a libjq function consuming its argument
+    fn into_raw(self) -> jv {
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        ptr
+    }
+}
+
+impl Drop for Jv {
+    fn drop(&mut self) {
+        unsafe { jv_free(self.ptr) }
+    }
+}
+
+unsafe fn c_string(value: *const c_char) -> String {
+    CStr::from_ptr(value).to_string_lossy().into_owned()
+}
+
+// Called by libjq for each error, `data` points to the Vec<String>
+// collecting the messages of the current run
+unsafe extern "C" fn collect_error(data: *mut c_void, msg: jv) {
+    let errors = &mut *(data as *mut Vec<String>);
+    let formatted = Jv { ptr: jq_format_error(msg) };
+    errors.push(formatted.string_value());
+}
+
+// Results of the programs already run on an input, by language,
+// the oldest ones are dropped past MEMO_BYTES
+struct Memo {
+    results: HashMap<(Language, String), Result<String, String>>,
+    order: VecDeque<(Language, String)>,
+    bytes: usize,
+}
+
+impl Memo {
+    fn size(result: &Result<String, String>) -> usize {
+        match result {
+            Ok(output) => output.len(),
+            Err(error) => error.len(),
+        }
+    }
+
+    fn insert(&mut self, language: Language, program: &str, result: &Result<String, String>) {
+        let size = Self::size(result);
+        if size > MEMO_BYTES {
+            return;
+        }
+        while self.bytes + size > MEMO_BYTES {
+            let Some(oldest) = self.order.pop_front() else { break };
+            if let Some(result) = self.results.remove(&oldest) {
+                self.bytes -= Self::size(&result);
+            }
+        }
+        self.results.insert((language, program.to_string()), result.clone());
+        self.order.push_back((language, program.to_string()));
+        self.bytes += size;
+    }
+
+    fn clear(&mut self) {
+        self.results.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+}
+
+// Input parsed once by jq, to run any number of programs on it
+pub struct Parsed {
+    values: Vec<Jv>,
+    // The invalid value ending the input, with the parse error if any
+    end: Jv,
+    // The values converted for the JSONPath and JMESPath evaluators,
+    // on their first query
+    json: OnceCell<Result<Vec<Value>, String>>,
+    memo: RefCell<Memo>,
+}
+
+impl Parsed {
+    fn is_empty(&self) -> bool {
+        self.values.is_empty() && !self.end.has_msg()
+    }
+
+    pub fn is_memoized(&self, language: Language, program: &str) -> bool {
+        self.memo.borrow().results.contains_key(&(language, program.to_string()))
+    }
+
+    pub fn memoized(&self) -> usize {
+        self.memo.borrow().results.len()
+    }
+
+    // The results are outdated (ie: a module was edited)
+    pub fn forget(&self) {
+        self.memo.borrow_mut().clear();
+    }
+
+    fn json(&self) -> Result<&[Value], String> {
+        self.json
+            .get_or_init(|| {
+                let mut values = self.values.iter().chain(std::iter::once(&self.end));
+                to_values(&mut || values.next().map(Jv::copy))
+            })
+            .as_deref()
+            .map_err(|error| error.clone())
+    }
+}
+
+// Convert the input values to serde values, `next_input` is the same as
+// in `Engine::run_with`
+fn to_values(next_input: &mut dyn FnMut() -> Option<Jv>) -> Result<Vec<Value>, String> {
+    let mut values = Vec::new();
+    while let Some(input) = next_input() {
+        if !input.is_valid() {
+            if input.has_msg() {
+                return Err(format!("jq: error: {}", input.msg().string_value()));
+            }
+            break;
+        }
+        values.push(serde_json::from_str(&input.to_json()).map_err(|error| error.to_string())?);
+    }
+    Ok(values)
+}
+
+pub struct Engine {
+    lib_dirs: Vec<PathBuf>,
+    // Directory relative module paths are resolved from
+    program_origin: PathBuf,
+    // Module files with their last modification time
+    modules: Vec<(PathBuf, Option<SystemTime>)>,
+    // Parse the input as [path, leaf] events, like `jq --stream`
+    streaming: bool,
+    language: Language,
+}
+
+impl Engine {
+    // Build the module search path the same way jq does:
+    // -L directories first, then $JQ_LIBRARY_PATH, then ~/.jq.
+    // A ~/.jq *file* is included automatically by libjq itself.
+    pub fn new(cli_lib_dirs: Vec<PathBuf>, program_origin: PathBuf) -> Engine {
+        let mut lib_dirs = cli_lib_dirs;
+        if let Some(paths) = std::env::var_os("JQ_LIBRARY_PATH") {
+            lib_dirs.extend(std::env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+        }
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        if let Some(home) = &home {
+            lib_dirs.push(home.join(".jq"));
+        }
+        let lib_dirs = lib_dirs
+            .into_iter()
+            .map(|dir| match (dir.strip_prefix("~"), &home) {
+                (Ok(rest), Some(home)) => home.join(rest),
+                _ => dir,
+            })
+            .collect();
+
+        let mut engine = Engine {
+            lib_dirs,
+            program_origin,
+            modules: Vec::new(),
+            streaming: false,
+            language: Language::Jq,
+        };
+        engine.modules = engine.scan_modules();
+        engine
+    }
+
+    fn scan_modules(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        fn scan(path: &Path, depth: usize, modules: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+            if path.is_dir() {
+                // Module directories are small, but do not follow a symlink loop forever
+                if depth > 8 {
+                    return;
+                }
+                if let Ok(entries) = std::fs::read_dir(path) {
+                    let mut entries: Vec<PathBuf> = entries
+                        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                        .collect();
+                    entries.sort();
+                    for entry in entries {
+                        scan(&entry, depth + 1, modules);
+                    }
+                }
+            } else if depth == 0 || path.extension().is_some_and(|ext| ext == "jq" || ext == "json") {
+                let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+                modules.push((path.to_path_buf(), modified));
+            }
+        }
+
+        let mut modules = Vec::new();
+        for dir in &self.lib_dirs {
+            if dir.exists() {
+                scan(dir, 0, &mut modules);
+            }
+        }
+        modules
+    }
+
+    // Check if a module was added, removed or edited since the last call
+    pub fn modules_changed(&mut self) -> bool {
+        let modules = self.scan_modules();
+        if modules != self.modules {
+            self.modules = modules;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_streaming(&mut self, streaming: bool) {
+        self.streaming = streaming;
+    }
+
+    pub fn streaming(&self) -> bool {
+        self.streaming
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+    }
+
+    // Parse the input once, see `run`
+    pub fn parse(&self, data: &str) -> Parsed {
+        let mut values = Vec::new();
+        let mut parser = unsafe { Parser::new(data.as_bytes(), self.streaming) };
+        loop {
+            let value = unsafe { parser.next() };
+            if !value.is_valid() {
+                return Parsed {
+                    values,
+                    end: value,
+                    json: OnceCell::new(),
+                    memo: RefCell::new(Memo { results: HashMap::new(), order: VecDeque::new(), bytes: 0 }),
+                };
+            }
+            values.push(value);
+        }
+    }
+
+    // Run the program on every value of `input`, returning one compact
+    // JSON value per line, or the error messages reported by jq.
+    // The result is memoized, running the program again is free.
+    pub fn run(&self, program: &str, input: &Parsed) -> Result<String, String> {
+        self.run_as(self.language, program, input)
+    }
+
+    // Same as `run`, in another language than the current one
+    // (ie: the jq `path(...)` of the selected nodes)
+    pub fn run_as(&self, language: Language, program: &str, input: &Parsed) -> Result<String, String> {
+        if input.is_empty() {
+            return Ok(String::new());
+        }
+        if let Some(result) = input.memo.borrow().results.get(&(language, program.to_string())) {
+            return result.clone();
+        }
+        let result = match language {
+            Language::Jq => {
+                let mut values = input.values.iter().chain(std::iter::once(&input.end));
+                self.run_with(program, &mut || values.next().map(Jv::copy))
+            },
+            Language::JsonPath => input.json().and_then(|values| jsonpath::run(program, values)),
+            Language::JmesPath => input.json().and_then(|values| jmespath::run(program, values)),
+        };
+        input.memo.borrow_mut().insert(language, program, &result);
+        result
+    }
+
+    // Same as `run`, for an input parsed as it is read and too large to
+    // be kept parsed (ie: a memory mapped file). `progress` gets the bytes
+    // parsed so far and the size of the input.
+    pub fn run_bytes(&self, program: &str, data: &[u8], progress: &mut dyn FnMut(usize, usize)) -> Result<String, String> {
+        let mut parser = unsafe { Parser::new(data, self.streaming) };
+        parser.chunk_size = PROGRESS_CHUNK_SIZE;
+        let mut next_input = || Some(unsafe { parser.next_reporting(progress) });
+        match self.language {
+            Language::Jq => self.run_with(program, &mut next_input),
+            Language::JsonPath => to_values(&mut next_input).and_then(|values| jsonpath::run(program, &values)),
+            Language::JmesPath => to_values(&mut next_input).and_then(|values| jmespath::run(program, &values)),
+        }
+    }
+
+    // `next_input` returns the input values, then an invalid one
+    fn run_with(&self, program: &str, next_input: &mut dyn FnMut() -> Option<Jv>) -> Result<String, String> {
+        let program = CString::new(program).map_err(|error| error.to_string())?;
+
+        let mut errors: Vec<String> = Vec::new();
+        let mut state = unsafe { jq_init() };
+        if state.is_null() {
+            return Err("jq: error: failed to initialize".to_string());
+        }
+        let result = unsafe {
+            jq_set_error_cb(
+                state,
+                Some(collect_error),
+                &mut errors as *mut Vec<String> as *mut c_void,
+            );
+            self.set_attributes(state);
+            if jq_compile(state, program.as_ptr()) == 0 {
+                Err(String::new())
+            } else {
+                Self::execute(state, next_input)
+            }
+        };
+        unsafe { jq_teardown(&mut state) };
+
+        match result {
+            Ok(output) => Ok(output),
+            Err(error) => {
+                if !error.is_empty() {
+                    errors.push(error);
+                }
+                if errors.is_empty() {
+                    errors.push("jq: error: unknown error".to_string());
+                }
+                Err(errors.join("\n"))
+            }
+        }
+    }
+
+    unsafe fn set_attributes(&self, state: *mut jq_state) {
+        let mut dirs = Jv { ptr: jv_array() };
+        for dir in &self.lib_dirs {
+            let dir = Jv::string(&dir.to_string_lossy());
+            dirs = Jv { ptr: jv_array_append(dirs.into_raw(), dir.into_raw()) };
+        }
+        jq_set_attr(
+            state,
+            Jv::string("JQ_LIBRARY_PATH").into_raw(),
+            dirs.into_raw(),
+        );
+        jq_set_attr(
+            state,
+            Jv::string("PROGRAM_ORIGIN").into_raw(),
+            Jv::string(&self.program_origin.to_string_lossy()).into_raw(),
+        );
+    }
+
+    unsafe fn execute(state: *mut jq_state, next_input: &mut dyn FnMut() -> Option<Jv>) -> Result<String, String> {
+        let mut output = String::new();
+
+        while let Some(input) = next_input() {
+            if !input.is_valid() {
+                if input.has_msg() {
+                    return Err(format!("jq: error: {}", input.msg().string_value()));
+                }
+                // No more input
+                return Ok(output);
+            }
+
+            // jq_start takes ownership of the input
+            jq_start(state, input.into_raw(), 0);
+            let mut value = Jv { ptr: jq_next(state) };
+            while value.is_valid() {
+                output.push_str(&value.to_json());
+                output.push('\n');
+                value = Jv { ptr: jq_next(state) };
+            }
+
+            if jq_halted(state) != 0 {
+                // halt_error puts its message in the error message
+                let message = Jv { ptr: jq_get_error_message(state) };
+                if message.is_string() {
+                    return Err(message.string_value());
+                } else if message.is_valid() {
+                    return Err(message.to_json());
+                }
+                return Ok(output);
+            } else if value.has_msg() {
+                let msg = value.msg();
+                return Err(if msg.is_string() {
+                    format!("jq: error: {}", msg.string_value())
+                } else {
+                    format!("jq: error (not a string): {}", msg.to_json())
+                });
+            }
+        }
+        Ok(output)
+    }
+}
+
+// Parser for a stream of JSON values
+struct Parser<'d> {
+    ptr: *mut jv_parser,
+    // The part of the input not given to the parser yet. The parser keeps
+    // a pointer to the current chunk, the input must outlive it.
+    remaining: &'d [u8],
+    size: usize,
+    chunk_size: usize,
+}
+
+impl<'d> Parser<'d> {
+    unsafe fn new(data: &'d [u8], streaming: bool) -> Parser<'d> {
+        let flags = if streaming { JV_PARSE_STREAMING as i32 } else { 0 };
+        Parser { ptr: jv_parser_new(flags), remaining: data, size: data.len(), chunk_size: CHUNK_SIZE }
+    }
+
+    unsafe fn next(&mut self) -> Jv {
+        self.next_reporting(&mut |_, _| {})
+    }
+
+    // `progress` gets the bytes parsed and the input size before each chunk
+    unsafe fn next_reporting(&mut self, progress: &mut dyn FnMut(usize, usize)) -> Jv {
+        loop {
+            let value = Jv { ptr: jv_parser_next(self.ptr) };
+            if value.is_valid() || value.has_msg() || self.remaining.is_empty() {
+                return value;
+            }
+            progress(self.size - self.remaining.len(), self.size);
+            // The parser is waiting for the next chunk, the last one
+            // is not partial
+            let (chunk, rest) = self.remaining.split_at(self.remaining.len().min(self.chunk_size));
+            jv_parser_set_buf(self.ptr, chunk.as_ptr() as *const c_char, chunk.len() as i32, !rest.is_empty() as i32);
+            self.remaining = rest;
+        }
+    }
+}
+
+impl Drop for Parser<'_> {
+    fn drop(&mut self) {
+        unsafe { jv_parser_free(self.ptr) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_of_chunks() {
+        let data = br#"[1, 2, 3] {"a": "bcd"} 4"#;
+        let mut reported = Vec::new();
+        let mut values = 0;
+        unsafe {
+            let mut parser = Parser::new(data, false);
+            parser.chunk_size = 10;
+            while parser.next_reporting(&mut |done, total| reported.push((done, total))).is_valid() {
+                values += 1;
+            }
+        }
+        assert_eq!(values, 3);
+        assert_eq!(reported, vec![(0, data.len()), (10, data.len()), (20, data.len())]);
+    }
+
+    #[test]
+    fn run_on_bytes() {
+        let engine = Engine::new(Vec::new(), PathBuf::from("."));
+        let mut calls = 0;
+        let result = engine.run_bytes("[.[]?] | length", b"[1, 2] {\"a\": 3} 4", &mut |_, _| calls += 1);
+        assert_eq!(result, Ok("2\n1\n0\n".to_string()));
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,942 @@
+// A JMESPath interpreter (https://jmespath.org/specification.html), the
+// query language of the AWS CLI. The expression is parsed once by a Pratt
+// parser, with the binding powers of the reference implementation, then
+// evaluated on each value of the input.
+// The `jmespath` crate is not used: it is not among the dependencies the
+// build can fetch, and it evaluates on its own `Variable` type, so every
+// input value would be converted from and back to serde_json. The compliance
+// cases in tests/jmespath check this interpreter instead.
+
+use std::cmp::Ordering;
+
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Comparator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    QuotedIdentifier(String),
+    // `json` or 'raw string'
+    Literal(Value),
+    Number(i64),
+    Dot,
+    Star,
+    // []
+    Flatten,
+    // [?
+    Filter,
+    Lbracket,
+    Rbracket,
+    Lbrace,
+    Rbrace,
+    Lparen,
+    Rparen,
+    Comma,
+    Colon,
+    Pipe,
+    Or,
+    And,
+    Not,
+    // &
+    Expref,
+    // @
+    Current,
+    Comparator(Comparator),
+    Eof,
+}
+
+enum Ast {
+    Current,
+    Field(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Literal(Value),
+    Subexpression(Box<Ast>, Box<Ast>),
+    // The right side evaluated on each element of the left side, nulls are skipped
+    Projection(Box<Ast>, Box<Ast>),
+    // Same, on the values of an object
+    ValueProjection(Box<Ast>, Box<Ast>),
+    Flatten(Box<Ast>),
+    // Left side, condition and right side of `a[?cond].b`
+    Filter(Box<Ast>, Box<Ast>, Box<Ast>),
+    Pipe(Box<Ast>, Box<Ast>),
+    Or(Box<Ast>, Box<Ast>),
+    And(Box<Ast>, Box<Ast>),
+    Not(Box<Ast>),
+    Comparison(Comparator, Box<Ast>, Box<Ast>),
+    MultiList(Vec<Ast>),
+    MultiHash(Vec<(String, Ast)>),
+    Function(String, Vec<Ast>),
+    // &expression, only valid as a function argument (ie: sort_by(@, &name))
+    Expref(Box<Ast>),
+}
+
+fn syntax_error(position: usize, message: &str) -> String {
+    format!("jmespath: syntax error at column {}: {}", position + 1, message)
+}
+
+fn error(message: String) -> String {
+    format!("jmespath: error: {}", message)
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (position, c) = chars[i];
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let (token, length) = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            },
+            '.' => (Token::Dot, 1),
+            '*' => (Token::Star, 1),
+            '@' => (Token::Current, 1),
+            ',' => (Token::Comma, 1),
+            ':' => (Token::Colon, 1),
+            '(' => (Token::Lparen, 1),
+            ')' => (Token::Rparen, 1),
+            '{' => (Token::Lbrace, 1),
+            '}' => (Token::Rbrace, 1),
+            ']' => (Token::Rbracket, 1),
+            '[' => match next {
+                Some('?') => (Token::Filter, 2),
+                Some(']') => (Token::Flatten, 2),
+                _ => (Token::Lbracket, 1),
+            },
+            '|' if next == Some('|') => (Token::Or, 2),
+            '|' => (Token::Pipe, 1),
+            '&' if next == Some('&') => (Token::And, 2),
+            '&' => (Token::Expref, 1),
+            '!' if next == Some('=') => (Token::Comparator(Comparator::Ne), 2),
+            '!' => (Token::Not, 1),
+            '=' if next == Some('=') => (Token::Comparator(Comparator::Eq), 2),
+            '<' if next == Some('=') => (Token::Comparator(Comparator::Le), 2),
+            '<' => (Token::Comparator(Comparator::Lt), 1),
+            '>' if next == Some('=') => (Token::Comparator(Comparator::Ge), 2),
+            '>' => (Token::Comparator(Comparator::Gt), 1),
+            '-' | '0'..='9' => {
+                let length = 1 + chars[i + 1..].iter().take_while(|(_, c)| c.is_ascii_digit()).count();
+                let end = chars.get(i + length).map_or(source.len(), |(end, _)| *end);
+                let number = source[position..end]
+                    .parse()
+                    .map_err(|_| syntax_error(position, "invalid number"))?;
+                (Token::Number(number), length)
+            },
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let length = 1 + chars[i + 1..]
+                    .iter()
+                    .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+                    .count();
+                let end = chars.get(i + length).map_or(source.len(), |(end, _)| *end);
+                (Token::Identifier(source[position..end].to_string()), length)
+            },
+            '"' | '\'' | '`' => {
+                let mut end = i + 1;
+                while end < chars.len() && chars[end].1 != c {
+                    if chars[end].1 == '\\' {
+                        end += 1;
+                    }
+                    end += 1;
+                }
+                if end >= chars.len() {
+                    return Err(syntax_error(position, &format!("unterminated {}", c)));
+                }
+                let text = &source[position + 1..chars[end].0];
+                let token = match c {
+                    '"' => Token::QuotedIdentifier(
+                        serde_json::from_str(&format!("\"{}\"", text))
+                            .map_err(|_| syntax_error(position, "invalid quoted identifier"))?
+                    ),
+                    '\'' => Token::Literal(Value::String(text.replace("\\'", "'"))),
+                    _ => Token::Literal(
+                        serde_json::from_str(text.replace("\\`", "`").trim())
+                            .map_err(|_| syntax_error(position, "invalid JSON literal"))?
+                    ),
+                };
+                (token, end + 1 - i)
+            },
+            _ => return Err(syntax_error(position, &format!("unexpected character '{}'", c))),
+        };
+        tokens.push((token, position));
+        i += length;
+    }
+    tokens.push((Token::Eof, source.len()));
+    Ok(tokens)
+}
+
+fn binding_power(token: &Token) -> u8 {
+    match token {
+        Token::Pipe => 1,
+        Token::Or => 2,
+        Token::And => 3,
+        Token::Comparator(_) => 5,
+        Token::Flatten => 9,
+        Token::Star => 20,
+        Token::Filter => 21,
+        Token::Dot => 40,
+        Token::Not => 45,
+        Token::Lbrace => 50,
+        Token::Lbracket => 55,
+        Token::Lparen => 60,
+        _ => 0,
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+}
+
+impl Parser {
+    fn current(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.index].1
+    }
+
+    fn lookahead(&self, offset: usize) -> &Token {
+        // Eof is always last
+        &self.tokens[std::cmp::min(self.index + offset, self.tokens.len() - 1)].0
+    }
+
+    fn advance(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.index].clone();
+        self.index = std::cmp::min(self.index + 1, self.tokens.len() - 1);
+        token
+    }
+
+    fn expect(&mut self, token: Token, name: &str) -> Result<(), String> {
+        if *self.current() != token {
+            return Err(self.unexpected(&format!("expected {}", name)));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn unexpected(&self, message: &str) -> String {
+        match self.current() {
+            Token::Eof => syntax_error(self.position(), &format!("unexpected end of the expression, {}", message)),
+            _ => syntax_error(self.position(), message),
+        }
+    }
+
+    fn expression(&mut self, binding: u8) -> Result<Ast, String> {
+        let (token, position) = self.advance();
+        let mut left = self.nud(token, position)?;
+        while binding < binding_power(self.current()) {
+            let (token, position) = self.advance();
+            left = self.led(token, position, left)?;
+        }
+        Ok(left)
+    }
+
+    // Expressions starting with the token
+    fn nud(&mut self, token: Token, position: usize) -> Result<Ast, String> {
+        match token {
+            Token::Literal(value) => Ok(Ast::Literal(value)),
+            Token::Identifier(name) => Ok(Ast::Field(name)),
+            Token::QuotedIdentifier(name) => {
+                if *self.current() == Token::Lparen {
+                    return Err(syntax_error(position, "a quoted identifier can't be a function name"));
+                }
+                Ok(Ast::Field(name))
+            },
+            Token::Star => {
+                let right = self.projection_rhs(binding_power(&Token::Star))?;
+                Ok(Ast::ValueProjection(Box::new(Ast::Current), Box::new(right)))
+            },
+            Token::Filter => self.filter(Ast::Current),
+            Token::Lbrace => self.multi_hash(),
+            Token::Lparen => {
+                let expression = self.expression(0)?;
+                self.expect(Token::Rparen, ")")?;
+                Ok(expression)
+            },
+            Token::Flatten => {
+                let right = self.projection_rhs(binding_power(&Token::Flatten))?;
+                Ok(Ast::Projection(Box::new(Ast::Flatten(Box::new(Ast::Current))), Box::new(right)))
+            },
+            Token::Not => Ok(Ast::Not(Box::new(self.expression(binding_power(&Token::Not))?))),
+            Token::Lbracket => match self.current() {
+                Token::Number(_) | Token::Colon => {
+                    let index = self.bracket_index()?;
+                    self.projection_if_slice(Ast::Current, index)
+                },
+                Token::Star if *self.lookahead(1) == Token::Rbracket => {
+                    self.advance();
+                    self.advance();
+                    let right = self.projection_rhs(binding_power(&Token::Star))?;
+                    Ok(Ast::Projection(Box::new(Ast::Current), Box::new(right)))
+                },
+                _ => self.multi_list(),
+            },
+            Token::Current => Ok(Ast::Current),
+            Token::Expref => Ok(Ast::Expref(Box::new(self.expression(binding_power(&Token::Expref))?))),
+            Token::Eof => Err(syntax_error(position, "unexpected end of the expression")),
+            _ => Err(syntax_error(position, "unexpected token")),
+        }
+    }
+
+    // Expressions continuing `left` with the token
+    fn led(&mut self, token: Token, position: usize, left: Ast) -> Result<Ast, String> {
+        match token {
+            Token::Dot => {
+                if *self.current() == Token::Star {
+                    self.advance();
+                    let right = self.projection_rhs(binding_power(&Token::Dot))?;
+                    Ok(Ast::ValueProjection(Box::new(left), Box::new(right)))
+                } else {
+                    let right = self.dot_rhs(binding_power(&Token::Dot))?;
+                    Ok(Ast::Subexpression(Box::new(left), Box::new(right)))
+                }
+            },
+            Token::Pipe => Ok(Ast::Pipe(Box::new(left), Box::new(self.expression(binding_power(&token))?))),
+            Token::Or => Ok(Ast::Or(Box::new(left), Box::new(self.expression(binding_power(&token))?))),
+            Token::And => Ok(Ast::And(Box::new(left), Box::new(self.expression(binding_power(&token))?))),
+            Token::Comparator(comparator) => {
+                let right = self.expression(binding_power(&token))?;
+                Ok(Ast::Comparison(comparator, Box::new(left), Box::new(right)))
+            },
+            Token::Lparen => {
+                let name = match left {
+                    Ast::Field(name) => name,
+                    _ => return Err(syntax_error(position, "invalid function name")),
+                };
+                let mut arguments = Vec::new();
+                while *self.current() != Token::Rparen {
+                    arguments.push(self.expression(0)?);
+                    match self.current() {
+                        Token::Comma => {
+                            self.advance();
+                        },
+                        Token::Rparen => {},
+                        _ => return Err(self.unexpected("expected , or )")),
+                    }
+                }
+                self.advance();
+                Ok(Ast::Function(name, arguments))
+            },
+            Token::Filter => self.filter(left),
+            Token::Flatten => {
+                let right = self.projection_rhs(binding_power(&Token::Flatten))?;
+                Ok(Ast::Projection(Box::new(Ast::Flatten(Box::new(left))), Box::new(right)))
+            },
+            Token::Lbracket => match self.current() {
+                Token::Number(_) | Token::Colon => {
+                    let index = self.bracket_index()?;
+                    self.projection_if_slice(left, index)
+                },
+                _ => {
+                    self.expect(Token::Star, "a number, : or *")?;
+                    self.expect(Token::Rbracket, "]")?;
+                    let right = self.projection_rhs(binding_power(&Token::Star))?;
+                    Ok(Ast::Projection(Box::new(left), Box::new(right)))
+                },
+            },
+            _ => Err(syntax_error(position, "unexpected token")),
+        }
+    }
+
+    // `[?condition]` applied to `left`, the `[?` is consumed
+    fn filter(&mut self, left: Ast) -> Result<Ast, String> {
+        let condition = self.expression(0)?;
+        self.expect(Token::Rbracket, "]")?;
+        let right = if *self.current() == Token::Flatten {
+            Ast::Current
+        } else {
+            self.projection_rhs(binding_power(&Token::Filter))?
+        };
+        Ok(Ast::Filter(Box::new(left), Box::new(condition), Box::new(right)))
+    }
+
+    // What a projection applies to each element, the rest of the
+    // expression binding tighter than the projection
+    fn projection_rhs(&mut self, binding: u8) -> Result<Ast, String> {
+        if binding_power(self.current()) < 10 {
+            return Ok(Ast::Current);
+        }
+        match self.current() {
+            Token::Lbracket | Token::Filter => self.expression(binding),
+            Token::Dot => {
+                self.advance();
+                self.dot_rhs(binding)
+            },
+            _ => Err(self.unexpected("expected ., [ or [?")),
+        }
+    }
+
+    fn dot_rhs(&mut self, binding: u8) -> Result<Ast, String> {
+        match self.current() {
+            Token::Identifier(_) | Token::QuotedIdentifier(_) | Token::Star => self.expression(binding),
+            Token::Lbracket => {
+                self.advance();
+                self.multi_list()
+            },
+            Token::Lbrace => {
+                self.advance();
+                self.multi_hash()
+            },
+            _ => Err(self.unexpected("expected an identifier, *, [ or { after .")),
+        }
+    }
+
+    // `[0]` or `[start:stop:step]`, the `[` is consumed
+    fn bracket_index(&mut self) -> Result<Ast, String> {
+        let mut parts = [None; 3];
+        let mut part = 0;
+        loop {
+            match self.current() {
+                Token::Rbracket => break,
+                Token::Colon if part < 2 => part += 1,
+                Token::Number(n) if parts[part].is_none() => parts[part] = Some(*n),
+                _ => return Err(self.unexpected("expected a number, : or ]")),
+            }
+            self.advance();
+        }
+        self.advance();
+        match parts {
+            [Some(index), None, None] if part == 0 => Ok(Ast::Index(index)),
+            [_, _, Some(0)] => Err(syntax_error(self.position(), "the step of a slice can't be 0")),
+            [start, stop, step] => Ok(Ast::Slice(start, stop, step)),
+        }
+    }
+
+    fn projection_if_slice(&mut self, left: Ast, index: Ast) -> Result<Ast, String> {
+        let is_slice = matches!(index, Ast::Slice(..));
+        let expression = Ast::Subexpression(Box::new(left), Box::new(index));
+        if !is_slice {
+            return Ok(expression);
+        }
+        let right = self.projection_rhs(binding_power(&Token::Star))?;
+        Ok(Ast::Projection(Box::new(expression), Box::new(right)))
+    }
+
+    // `[a, b]`, the `[` is consumed
+    fn multi_list(&mut self) -> Result<Ast, String> {
+        let mut items = Vec::new();
+        loop {
+            items.push(self.expression(0)?);
+            match self.advance() {
+                (Token::Comma, _) => {},
+                (Token::Rbracket, _) => return Ok(Ast::MultiList(items)),
+                (_, position) => return Err(syntax_error(position, "expected , or ]")),
+            }
+        }
+    }
+
+    // `{key: a, other: b}`, the `{` is consumed
+    fn multi_hash(&mut self) -> Result<Ast, String> {
+        let mut items = Vec::new();
+        loop {
+            let key = match self.advance() {
+                (Token::Identifier(key) | Token::QuotedIdentifier(key), _) => key,
+                (_, position) => return Err(syntax_error(position, "expected a key")),
+            };
+            self.expect(Token::Colon, ":")?;
+            items.push((key, self.expression(0)?));
+            match self.advance() {
+                (Token::Comma, _) => {},
+                (Token::Rbrace, _) => return Ok(Ast::MultiHash(items)),
+                (_, position) => return Err(syntax_error(position, "expected , or }")),
+            }
+        }
+    }
+}
+
+fn parse(source: &str) -> Result<Ast, String> {
+    let mut parser = Parser { tokens: tokenize(source)?, index: 0 };
+    let ast = parser.expression(0)?;
+    if *parser.current() != Token::Eof {
+        return Err(syntax_error(parser.position(), "unexpected token"));
+    }
+    Ok(ast)
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+        Value::Number(_) => true,
+    }
+}
+
+// Numbers are equal whatever their representation (ie: 1 and 1.0)
+fn is_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| is_equal(a, b))
+        },
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| is_equal(a, b)))
+        },
+        _ => a == b,
+    }
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 9007199254740992.0 {
+        Value::from(n as i64)
+    } else {
+        serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+// Index from the end when negative, like slices
+fn resolve_index(index: i64, length: usize) -> Option<usize> {
+    let index = if index < 0 { length as i64 + index } else { index };
+    (0..length as i64).contains(&index).then_some(index as usize)
+}
+
+fn slice(items: &[Value], start: Option<i64>, stop: Option<i64>, step: Option<i64>) -> Vec<Value> {
+    let length = items.len() as i64;
+    let step = step.unwrap_or(1);
+    // Clamp the bounds to the valid positions for the direction of the step
+    let clamp = |bound: i64| {
+        let bound = if bound < 0 { bound + length } else { bound };
+        if step > 0 {
+            bound.clamp(0, length)
+        } else {
+            bound.clamp(-1, length - 1)
+        }
+    };
+    let (default_start, default_stop) = if step > 0 { (0, length) } else { (length - 1, -1) };
+    let mut i = start.map_or(default_start, clamp);
+    let stop = stop.map_or(default_stop, clamp);
+    let mut result = Vec::new();
+    while (step > 0 && i < stop) || (step < 0 && i > stop) {
+        result.push(items[i as usize].clone());
+        i += step;
+    }
+    result
+}
+
+fn project<'v>(values: impl Iterator<Item = &'v Value>, right: &Ast) -> Result<Value, String> {
+    let mut result = Vec::new();
+    for value in values {
+        let value = search(right, value)?;
+        if !value.is_null() {
+            result.push(value);
+        }
+    }
+    Ok(Value::Array(result))
+}
+
+fn search(ast: &Ast, value: &Value) -> Result<Value, String> {
+    Ok(match ast {
+        Ast::Current => value.clone(),
+        Ast::Field(name) => value.as_object().and_then(|object| object.get(name)).cloned().unwrap_or(Value::Null),
+        Ast::Index(index) => match value {
+            Value::Array(items) => resolve_index(*index, items.len()).map_or(Value::Null, |i| items[i].clone()),
+            _ => Value::Null,
+        },
+        Ast::Slice(start, stop, step) => match value {
+            Value::Array(items) => Value::Array(slice(items, *start, *stop, *step)),
+            _ => Value::Null,
+        },
+        Ast::Literal(literal) => literal.clone(),
+        Ast::Subexpression(left, right) | Ast::Pipe(left, right) => search(right, &search(left, value)?)?,
+        Ast::Projection(left, right) => match search(left, value)? {
+            Value::Array(items) => project(items.iter(), right)?,
+            _ => Value::Null,
+        },
+        Ast::ValueProjection(left, right) => match search(left, value)? {
+            Value::Object(object) => project(object.values(), right)?,
+            _ => Value::Null,
+        },
+        Ast::Flatten(inner) => match search(inner, value)? {
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .flat_map(|item| match item {
+                        Value::Array(inner) => inner,
+                        item => vec![item],
+                    })
+                    .collect()
+            ),
+            _ => Value::Null,
+        },
+        Ast::Filter(left, condition, right) => match search(left, value)? {
+            Value::Array(items) => {
+                let mut kept = Vec::new();
+                for item in items {
+                    if is_truthy(&search(condition, &item)?) {
+                        kept.push(item);
+                    }
+                }
+                project(kept.iter(), right)?
+            },
+            _ => Value::Null,
+        },
+        Ast::Or(left, right) => {
+            let left = search(left, value)?;
+            if is_truthy(&left) { left } else { search(right, value)? }
+        },
+        Ast::And(left, right) => {
+            let left = search(left, value)?;
+            if is_truthy(&left) { search(right, value)? } else { left }
+        },
+        Ast::Not(inner) => Value::Bool(!is_truthy(&search(inner, value)?)),
+        Ast::Comparison(comparator, left, right) => {
+            let (left, right) = (search(left, value)?, search(right, value)?);
+            match comparator {
+                Comparator::Eq => Value::Bool(is_equal(&left, &right)),
+                Comparator::Ne => Value::Bool(!is_equal(&left, &right)),
+                // Only numbers are ordered
+                _ => match (left.as_f64(), right.as_f64()) {
+                    (Some(a), Some(b)) => Value::Bool(match comparator {
+                        Comparator::Lt => a < b,
+                        Comparator::Le => a <= b,
+                        Comparator::Gt => a > b,
+                        _ => a >= b,
+                    }),
+                    _ => Value::Null,
+                },
+            }
+        },
+        Ast::MultiList(items) => match value {
+            Value::Null => Value::Null,
+            _ => Value::Array(items.iter().map(|item| search(item, value)).collect::<Result<_, _>>()?),
+        },
+        Ast::MultiHash(items) => match value {
+            Value::Null => Value::Null,
+            _ => {
+                let mut object = Map::new();
+                for (key, item) in items {
+                    object.insert(key.clone(), search(item, value)?);
+                }
+                Value::Object(object)
+            },
+        },
+        Ast::Function(name, arguments) => call(name, arguments, value)?,
+        Ast::Expref(_) => return Err(error("&expression is only valid as a function argument".to_string())),
+    })
+}
+
+enum Argument<'a> {
+    Value(Value),
+    Expression(&'a Ast),
+}
+
+// Accessors of the arguments of a function, checking their type
+struct Arguments<'a> {
+    name: &'a str,
+    arguments: Vec<Argument<'a>>,
+}
+
+impl<'a> Arguments<'a> {
+    fn invalid(&self, index: usize, expected: &str) -> String {
+        error(format!("argument {} of {}() must be {}", index + 1, self.name, expected))
+    }
+
+    fn value(&self, index: usize) -> Result<&Value, String> {
+        match &self.arguments[index] {
+            Argument::Value(value) => Ok(value),
+            Argument::Expression(_) => Err(self.invalid(index, "a value, not an &expression")),
+        }
+    }
+
+    fn expression(&self, index: usize) -> Result<&'a Ast, String> {
+        match &self.arguments[index] {
+            Argument::Expression(expression) => Ok(expression),
+            Argument::Value(_) => Err(self.invalid(index, "an &expression")),
+        }
+    }
+
+    fn number(&self, index: usize) -> Result<f64, String> {
+        self.value(index)?.as_f64().ok_or_else(|| self.invalid(index, "a number"))
+    }
+
+    fn string(&self, index: usize) -> Result<&str, String> {
+        self.value(index)?.as_str().ok_or_else(|| self.invalid(index, "a string"))
+    }
+
+    fn array(&self, index: usize) -> Result<&Vec<Value>, String> {
+        self.value(index)?.as_array().ok_or_else(|| self.invalid(index, "an array"))
+    }
+
+    fn object(&self, index: usize) -> Result<&Map<String, Value>, String> {
+        self.value(index)?.as_object().ok_or_else(|| self.invalid(index, "an object"))
+    }
+
+    fn numbers(&self, index: usize) -> Result<Vec<f64>, String> {
+        self.array(index)?
+            .iter()
+            .map(|value| value.as_f64().ok_or_else(|| self.invalid(index, "an array of numbers")))
+            .collect()
+    }
+
+    // Keys to sort or compare by: all numbers or all strings
+    fn check_sortable(&self, index: usize, keys: &[Value]) -> Result<(), String> {
+        if keys.iter().all(Value::is_number) || keys.iter().all(Value::is_string) {
+            Ok(())
+        } else {
+            Err(self.invalid(index, "made of numbers or of strings"))
+        }
+    }
+
+    // The keys of an array given by the expression of `max_by`, `min_by` and `sort_by`
+    fn keys(&self, items: &[Value]) -> Result<Vec<Value>, String> {
+        let expression = self.expression(1)?;
+        let keys = items.iter().map(|item| search(expression, item)).collect::<Result<Vec<_>, _>>()?;
+        self.check_sortable(1, &keys)?;
+        Ok(keys)
+    }
+}
+
+fn ordering(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().unwrap_or(0.0).total_cmp(&b.as_f64().unwrap_or(0.0)),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+fn call(name: &str, arguments: &[Ast], current: &Value) -> Result<Value, String> {
+    let arity = match name {
+        "abs" | "avg" | "ceil" | "floor" | "keys" | "length" | "max" | "min" | "reverse" | "sort"
+            | "sum" | "to_array" | "to_number" | "to_string" | "type" | "values" => 1..=1,
+        "contains" | "ends_with" | "join" | "map" | "max_by" | "min_by" | "sort_by" | "starts_with" => 2..=2,
+        "merge" | "not_null" => 1..=usize::MAX,
+        _ => return Err(error(format!("unknown function {}()", name))),
+    };
+    if !arity.contains(&arguments.len()) {
+        let count = if arity.start() == arity.end() {
+            arity.start().to_string()
+        } else {
+            format!("at least {}", arity.start())
+        };
+        return Err(error(format!("{}() takes {} arguments, got {}", name, count, arguments.len())));
+    }
+    let mut values = Vec::new();
+    for argument in arguments {
+        values.push(match argument {
+            Ast::Expref(expression) => Argument::Expression(expression),
+            argument => Argument::Value(search(argument, current)?),
+        });
+    }
+    let args = Arguments { name, arguments: values };
+
+    Ok(match name {
+        "abs" => number(args.number(0)?.abs()),
+        "ceil" => number(args.number(0)?.ceil()),
+        "floor" => number(args.number(0)?.floor()),
+        "avg" => {
+            let numbers = args.numbers(0)?;
+            if numbers.is_empty() {
+                Value::Null
+            } else {
+                number(numbers.iter().sum::<f64>() / numbers.len() as f64)
+            }
+        },
+        "sum" => number(args.numbers(0)?.iter().sum()),
+        "contains" => match args.value(0)? {
+            Value::Array(items) => {
+                let search = args.value(1)?;
+                Value::Bool(items.iter().any(|item| is_equal(item, search)))
+            },
+            Value::String(s) => Value::Bool(s.contains(args.string(1)?)),
+            _ => return Err(args.invalid(0, "an array or a string")),
+        },
+        "starts_with" => Value::Bool(args.string(0)?.starts_with(args.string(1)?)),
+        "ends_with" => Value::Bool(args.string(0)?.ends_with(args.string(1)?)),
+        "join" => {
+            let glue = args.string(0)?;
+            let items = args.array(1)?
+                .iter()
+                .map(|item| item.as_str().ok_or_else(|| args.invalid(1, "an array of strings")))
+                .collect::<Result<Vec<_>, _>>()?;
+            Value::String(items.join(glue))
+        },
+        "keys" => Value::Array(args.object(0)?.keys().map(|key| Value::String(key.clone())).collect()),
+        "values" => Value::Array(args.object(0)?.values().cloned().collect()),
+        "length" => match args.value(0)? {
+            Value::String(s) => Value::from(s.chars().count()),
+            Value::Array(items) => Value::from(items.len()),
+            Value::Object(object) => Value::from(object.len()),
+            _ => return Err(args.invalid(0, "a string, an array or an object")),
+        },
+        "map" => {
+            let expression = args.expression(0)?;
+            let items = args.array(1)?;
+            Value::Array(items.iter().map(|item| search(expression, item)).collect::<Result<_, _>>()?)
+        },
+        "max" | "min" => {
+            let items = args.array(0)?;
+            args.check_sortable(0, items)?;
+            let found = if name == "max" {
+                items.iter().max_by(|a, b| ordering(a, b))
+            } else {
+                items.iter().min_by(|a, b| ordering(a, b))
+            };
+            found.cloned().unwrap_or(Value::Null)
+        },
+        "max_by" | "min_by" => {
+            let items = args.array(0)?;
+            let keys = args.keys(items)?;
+            let found = if name == "max_by" {
+                (0..items.len()).max_by(|a, b| ordering(&keys[*a], &keys[*b]))
+            } else {
+                (0..items.len()).min_by(|a, b| ordering(&keys[*a], &keys[*b]))
+            };
+            found.map_or(Value::Null, |i| items[i].clone())
+        },
+        "sort" => {
+            let mut items = args.array(0)?.clone();
+            args.check_sortable(0, &items)?;
+            items.sort_by(ordering);
+            Value::Array(items)
+        },
+        "sort_by" => {
+            let items = args.array(0)?;
+            let keys = args.keys(items)?;
+            let mut order: Vec<usize> = (0..items.len()).collect();
+            order.sort_by(|a, b| ordering(&keys[*a], &keys[*b]));
+            Value::Array(order.into_iter().map(|i| items[i].clone()).collect())
+        },
+        "merge" => {
+            let mut merged = Map::new();
+            for index in 0..arguments.len() {
+                for (key, value) in args.object(index)? {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+            Value::Object(merged)
+        },
+        "not_null" => {
+            let mut found = Value::Null;
+            for index in 0..arguments.len() {
+                let value = args.value(index)?;
+                if !value.is_null() {
+                    found = value.clone();
+                    break;
+                }
+            }
+            found
+        },
+        "reverse" => match args.value(0)? {
+            Value::Array(items) => Value::Array(items.iter().rev().cloned().collect()),
+            Value::String(s) => Value::String(s.chars().rev().collect()),
+            _ => return Err(args.invalid(0, "an array or a string")),
+        },
+        "to_array" => match args.value(0)? {
+            Value::Array(items) => Value::Array(items.clone()),
+            value => Value::Array(vec![value.clone()]),
+        },
+        "to_number" => match args.value(0)? {
+            Value::Number(n) => Value::Number(n.clone()),
+            Value::String(s) => s.trim().parse::<f64>().map_or(Value::Null, number),
+            _ => Value::Null,
+        },
+        "to_string" => match args.value(0)? {
+            Value::String(s) => Value::String(s.clone()),
+            value => Value::String(value.to_string()),
+        },
+        "type" => Value::String(type_name(args.value(0)?).to_string()),
+        _ => return Err(error(format!("unknown function {}()", name))),
+    })
+}
+
+// Evaluate the expression on each value, one compact JSON result per line
+// like the jq engine
+pub fn run(program: &str, values: &[Value]) -> Result<String, String> {
+    let ast = parse(program)?;
+    let mut output = String::new();
+    for value in values {
+        output.push_str(&search(&ast, value)?.to_string());
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cases of the compliance suite of the specification
+    // (https://github.com/jmespath/jmespath.test), in its format: each
+    // expression applied to `given` gives `result`, or fails with `error`
+    // ("syntax" when it doesn't parse, any other error when it fails on `given`).
+    fn check(file: &str, source: &str) {
+        let groups: Vec<Value> = serde_json::from_str(source).unwrap_or_else(|error| panic!("{}: {}", file, error));
+        let mut count = 0;
+        for group in &groups {
+            for case in group["cases"].as_array().unwrap() {
+                let expression = case["expression"].as_str().unwrap();
+                let result = parse(expression).and_then(|ast| search(&ast, &group["given"]));
+                match (&case["error"], result) {
+                    (Value::Null, Ok(result)) => assert!(
+                        is_equal(&result, &case["result"]),
+                        "{}: {}: {} instead of {}", file, expression, result, case["result"]
+                    ),
+                    (Value::Null, Err(message)) => panic!("{}: {}: {}", file, expression, message),
+                    (error, result) if error == "syntax" => {
+                        assert!(parse(expression).is_err(), "{}: {:?} parsed", file, expression);
+                        assert!(result.is_err());
+                    },
+                    (_, result) => {
+                        parse(expression).unwrap_or_else(|message| panic!("{}: {}: {}", file, expression, message));
+                        if let Ok(result) = result {
+                            panic!("{}: {}: {} instead of an error", file, expression, result);
+                        }
+                    },
+                }
+                count += 1;
+            }
+        }
+        assert!(count > 0, "{}: no cases", file);
+    }
+
+    macro_rules! compliance {
+        ($($name:ident),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let file = concat!("tests/jmespath/", stringify!($name), ".json");
+                    check(file, include_str!(concat!("../../tests/jmespath/", stringify!($name), ".json")));
+                }
+            )*
+        };
+    }
+
+    compliance!(
+        basic, boolean, current, escape, filters, functions, functions_with_expressions,
+        indices, literals, multiselect, pipes, slices, syntax,
+    );
+
+    #[test]
+    fn run_on_each_value() {
+        let values = [serde_json::json!({"a": 1}), serde_json::json!({"a": [1, 2]}), serde_json::json!([])];
+        assert_eq!(run("a", &values).unwrap(), "1\n[1,2]\nnull\n");
+        assert!(run("a[", &values).unwrap_err().starts_with("jmespath: syntax error at column 3"));
+        assert!(run("abs(a)", &values).unwrap_err().starts_with("jmespath: error:"));
+    }
+}
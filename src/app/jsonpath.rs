@@ -0,0 +1,540 @@
+// JSONPath queries, evaluated by jsonpath_lib. The kubectl flavor is
+// accepted too: `{.items[*].metadata.name}` or `.items[0]` without the `$`.
+// `to_jq` translates a path to jq, the result pane then highlights the
+// nodes it selects in the base document.
+
+use jsonpath_lib::Compiled;
+use serde_json::Value;
+
+use crate::app::widgets::json::jq_path;
+
+// `$.a`, `{.a}` and `.a` are the same path
+fn normalize(path: &str) -> String {
+    let path = path.trim();
+    let path = match path.strip_prefix('{').and_then(|path| path.strip_suffix('}')) {
+        Some(inner) => inner.trim(),
+        None => path,
+    };
+    if path.starts_with('.') || path.starts_with('[') {
+        format!("${}", path)
+    } else {
+        path.to_string()
+    }
+}
+
+// Select the nodes of each value, one compact JSON node per line
+// like the jq engine
+pub fn run(program: &str, values: &[Value]) -> Result<String, String> {
+    let path = Compiled::compile(&normalize(program))
+        .map_err(|error| format!("jsonpath: error: {}", error.trim()))?;
+    let mut output = String::new();
+    for value in values {
+        let nodes = path
+            .select(value)
+            .map_err(|error| format!("jsonpath: error: {}", error.to_string().trim()))?;
+        for node in nodes {
+            output.push_str(&node.to_string());
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+enum Selector {
+    Name(String),
+    Index(i64),
+    Wildcard,
+    Slice(Option<i64>, Option<i64>),
+    // The condition, already translated to jq
+    Filter(String),
+}
+
+fn unsupported(what: &str) -> String {
+    format!("Can't translate {} to jq", what)
+}
+
+fn is_name_char(c: char) -> bool {
+    !matches!(c, '.' | '[' | ']' | '(' | ')' | ',' | ' ' | '=' | '!' | '<' | '>' | '&' | '|')
+}
+
+// Parses a path, or the `@.a.b` and `$.a` paths of a filter condition
+struct Reader<'a> {
+    source: &'a str,
+    position: usize,
+    // The condition refers to the root (`$`), bound to `$root` in jq
+    uses_root: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.position += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        self.position = self.source.len() - self.rest().trim_start().len();
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let length = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn number(&mut self) -> Result<i64, String> {
+        self.skip_spaces();
+        let start = self.position;
+        self.eat("-");
+        self.take_while(|c| c.is_ascii_digit());
+        self.source[start..self.position]
+            .parse()
+            .map_err(|_| unsupported(&format!("the number at column {}", start + 1)))
+    }
+
+    // A 'single' or "double" quoted string
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.position;
+        let quote = self.peek().unwrap_or('\'');
+        self.position += 1;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                },
+                c if c == quote => {
+                    self.position += i + 1;
+                    return Ok(value);
+                },
+                c => value.push(c),
+            }
+        }
+        Err(unsupported(&format!("the unterminated string at column {}", start + 1)))
+    }
+
+    // The selectors of `[...]`, the `[` is consumed
+    fn bracket(&mut self) -> Result<Vec<Selector>, String> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_spaces();
+            let selector = match self.peek() {
+                Some('*') => {
+                    self.position += 1;
+                    Selector::Wildcard
+                },
+                Some('?') => {
+                    self.position += 1;
+                    Selector::Filter(self.filter()?)
+                },
+                Some('\'' | '"') => Selector::Name(self.string()?),
+                Some(':') => {
+                    self.position += 1;
+                    self.slice(None)?
+                },
+                Some('-' | '0'..='9') => {
+                    let index = self.number()?;
+                    self.skip_spaces();
+                    if self.eat(":") {
+                        self.slice(Some(index))?
+                    } else {
+                        Selector::Index(index)
+                    }
+                },
+                _ => return Err(unsupported(&format!("the selector at column {}", self.position + 1))),
+            };
+            selectors.push(selector);
+            self.skip_spaces();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            if !self.eat(",") {
+                return Err(unsupported(&format!("the selector at column {}", self.position + 1)));
+            }
+        }
+    }
+
+    // The end of `[start:end]`, the `:` is consumed
+    fn slice(&mut self, start: Option<i64>) -> Result<Selector, String> {
+        self.skip_spaces();
+        let end = match self.peek() {
+            Some('-' | '0'..='9') => Some(self.number()?),
+            _ => None,
+        };
+        self.skip_spaces();
+        if self.peek() == Some(':') {
+            return Err(unsupported("a slice with a step"));
+        }
+        Ok(Selector::Slice(start, end))
+    }
+
+    // The segments following `$` or `@`: (descendant, selectors)
+    fn segments(&mut self, in_filter: bool) -> Result<Vec<(bool, Vec<Selector>)>, String> {
+        let mut segments = Vec::new();
+        loop {
+            let descendant = self.eat("..");
+            if self.eat("[") {
+                segments.push((descendant, self.bracket()?));
+            } else if descendant || self.eat(".") {
+                if self.eat("*") {
+                    segments.push((descendant, vec![Selector::Wildcard]));
+                } else if self.eat("[") {
+                    segments.push((descendant, self.bracket()?));
+                } else {
+                    let name = self.take_while(is_name_char);
+                    if name.is_empty() {
+                        return Err(unsupported(&format!("the path at column {}", self.position + 1)));
+                    }
+                    segments.push((descendant, vec![Selector::Name(name.to_string())]));
+                }
+            } else if in_filter || self.position == self.source.len() {
+                return Ok(segments);
+            } else {
+                return Err(unsupported(&format!("the path at column {}", self.position + 1)));
+            }
+        }
+    }
+
+    // The condition of `[?(...)]`, translated to jq. The `?` is consumed,
+    // up to the closing `]`.
+    fn filter(&mut self) -> Result<String, String> {
+        let mut jq: Vec<String> = Vec::new();
+        let mut depth = 0;
+        loop {
+            self.skip_spaces();
+            let start = self.position;
+            let Some(c) = self.peek() else {
+                return Err(unsupported("the unterminated filter"));
+            };
+            match c {
+                ']' if depth == 0 => break,
+                '(' => {
+                    self.position += 1;
+                    depth += 1;
+                    jq.push("(".to_string());
+                },
+                ')' => {
+                    self.position += 1;
+                    depth -= 1;
+                    jq.push(")".to_string());
+                },
+                '@' | '$' => {
+                    self.position += 1;
+                    let segments = self.segments(true)?;
+                    let base = if c == '$' {
+                        self.uses_root = true;
+                        "$root"
+                    } else {
+                        "."
+                    };
+                    let path = path_to_jq(base, &segments)?;
+                    jq.push(if path.contains(" | ") { format!("({})", path) } else { path });
+                },
+                '\'' | '"' => {
+                    let value = self.string()?;
+                    jq.push(Value::String(value).to_string());
+                },
+                '-' | '0'..='9' => {
+                    let number = self.take_while(|c| c.is_ascii_digit() || matches!(c, '-' | '.' | 'e' | 'E' | '+'));
+                    jq.push(number.to_string());
+                },
+                _ if self.eat("&&") => jq.push("and".to_string()),
+                _ if self.eat("||") => jq.push("or".to_string()),
+                _ if ["==", "!=", "<=", ">=", "<", ">"].iter().any(|op| self.eat(op)) => {
+                    jq.push(self.source[start..self.position].to_string());
+                },
+                _ if self.eat("=~") => {
+                    self.skip_spaces();
+                    let regex = self.regex()?;
+                    let operand = jq.pop().ok_or_else(|| unsupported("=~ without a left operand"))?;
+                    jq.push(format!("({} | test({}))", operand, regex));
+                },
+                c if c.is_ascii_alphabetic() => {
+                    let word = self.take_while(|c| c.is_ascii_alphanumeric());
+                    if !matches!(word, "true" | "false" | "null") {
+                        return Err(unsupported(&format!("`{}` in the filter", word)));
+                    }
+                    jq.push(word.to_string());
+                },
+                _ => return Err(unsupported(&format!("`{}` in the filter", c))),
+            }
+        }
+        // The parentheses of `?(...)` are not needed in `select(...)`
+        if jq.first().is_some_and(|token| token == "(") && jq.last().is_some_and(|token| token == ")") {
+            let mut depth = 0;
+            let closing = jq.iter().position(|token| {
+                depth += match token.as_str() { "(" => 1, ")" => -1, _ => 0 };
+                depth == 0
+            });
+            if closing == Some(jq.len() - 1) {
+                jq.pop();
+                jq.remove(0);
+            }
+        }
+        // A path alone tests the existence of the node, not its value
+        let is_comparison = |token: &str| matches!(token, "==" | "!=" | "<=" | ">=" | "<" | ">");
+        let mut condition = String::new();
+        for (i, token) in jq.iter().enumerate() {
+            if i > 0 && jq[i - 1] != "(" && token != ")" {
+                condition.push(' ');
+            }
+            let is_path = token.starts_with('.') || token.starts_with("$root");
+            let compared = (i > 0 && is_comparison(&jq[i - 1])) || jq.get(i + 1).is_some_and(|next| is_comparison(next));
+            if is_path && !compared {
+                condition.push_str(&format!("{} != null", token));
+            } else if jq.len() == 1 {
+                // A lone `(.a | test(...))`
+                condition.push_str(token.strip_prefix('(').and_then(|token| token.strip_suffix(')')).unwrap_or(token));
+            } else {
+                condition.push_str(token);
+            }
+        }
+        Ok(condition)
+    }
+
+    // `/regex/flags` as the arguments of jq's `test`
+    fn regex(&mut self) -> Result<String, String> {
+        if !self.eat("/") {
+            return Err(unsupported("=~ without a /regex/"));
+        }
+        let mut regex = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    regex.push(c);
+                    if let Some((_, escaped)) = chars.next() {
+                        regex.push(escaped);
+                    }
+                },
+                '/' => {
+                    self.position += i + 1;
+                    let flags = self.take_while(|c| c.is_ascii_alphabetic());
+                    return Ok(if flags.is_empty() {
+                        Value::String(regex).to_string()
+                    } else {
+                        format!("{}; {}", Value::String(regex), Value::String(flags.to_string()))
+                    });
+                },
+                c => regex.push(c),
+            }
+        }
+        Err(unsupported("the unterminated regex"))
+    }
+}
+
+// jq stages of the segments, applied to `base` (`.` or `$root`)
+fn path_to_jq(base: &str, segments: &[(bool, Vec<Selector>)]) -> Result<String, String> {
+    let mut stages: Vec<String> = vec![base.to_string()];
+    // Append a path to the last stage if it is a path
+    fn push_path(stages: &mut Vec<String>, path: &str) {
+        let last = stages
+            .last_mut()
+            .filter(|last| (last.starts_with('.') || last.starts_with('$')) && *last != "..");
+        match last {
+            Some(last) if last == "." => *last = path.to_string(),
+            Some(last) => last.push_str(path.strip_prefix('.').filter(|_| path.starts_with(".[")).unwrap_or(path)),
+            None => stages.push(path.to_string()),
+        }
+    }
+
+    for (descendant, selectors) in segments {
+        if *descendant {
+            match stages.last_mut() {
+                Some(last) if last == "." => *last = "..".to_string(),
+                _ => stages.push("..".to_string()),
+            }
+            if let [Selector::Name(name)] = selectors.as_slice() {
+                stages.push("objects".to_string());
+                stages.push(format!("select(has({}))", Value::String(name.clone())));
+            } else if let [Selector::Index(index)] = selectors.as_slice() {
+                stages.push("arrays".to_string());
+                stages.push(if *index < 0 {
+                    format!("select(length >= {})", -index)
+                } else {
+                    format!("select(length > {})", index)
+                });
+            }
+        }
+        let path = match selectors.as_slice() {
+            [Selector::Name(name)] => jq_path(&[Value::String(name.clone())]),
+            [Selector::Index(index)] => format!(".[{}]", index),
+            [Selector::Wildcard] if *descendant => ".[]?".to_string(),
+            [Selector::Wildcard] => ".[]".to_string(),
+            [Selector::Slice(start, end)] => format!(
+                ".[{}:{}][]",
+                start.map_or(String::new(), |n| n.to_string()),
+                end.map_or(String::new(), |n| n.to_string())
+            ),
+            [Selector::Filter(condition)] => {
+                push_path(&mut stages, if *descendant {".[]?"} else {".[]"});
+                stages.push(format!("select({})", condition));
+                continue;
+            },
+            _ if *descendant => return Err(unsupported("a union after ..")),
+            selectors if selectors.iter().all(|selector| matches!(selector, Selector::Name(_))) => {
+                let names: Vec<String> = selectors
+                    .iter()
+                    .filter_map(|selector| match selector {
+                        Selector::Name(name) => Some(Value::String(name.clone()).to_string()),
+                        _ => None,
+                    })
+                    .collect();
+                format!(".[{}]", names.join(", "))
+            },
+            selectors if selectors.iter().all(|selector| matches!(selector, Selector::Index(_))) => {
+                let indices: Vec<String> = selectors
+                    .iter()
+                    .filter_map(|selector| match selector {
+                        Selector::Index(index) => Some(index.to_string()),
+                        _ => None,
+                    })
+                    .collect();
+                format!(".[{}]", indices.join(", "))
+            },
+            _ => return Err(unsupported("a union of different selectors")),
+        };
+        // The descendants are of any type, the path may not apply to them
+        if *descendant && !path.ends_with('?') && !matches!(selectors.as_slice(), [Selector::Name(_) | Selector::Index(_)]) {
+            push_path(&mut stages, &format!("{}?", path));
+            continue;
+        }
+        push_path(&mut stages, &path);
+    }
+    Ok(stages.join(" | "))
+}
+
+// Translate a JSONPath query to jq (ie: `$.items[?(@.price < 10)].name` to
+// `.items[] | select(.price < 10) | .name`). Experimental: JSONPath
+// implementations disagree on the details, the common paths are supported.
+pub fn to_jq(path: &str) -> Result<String, String> {
+    let path = normalize(path);
+    let mut reader = Reader { source: &path, position: 0, uses_root: false };
+    if !reader.eat("$") {
+        return Err(unsupported("a path not starting with $"));
+    }
+    let segments = reader.segments(false)?;
+    let jq = path_to_jq(".", &segments)?;
+    Ok(if reader.uses_root { format!(". as $root | {}", jq) } else { jq })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::engine::Engine;
+
+    const STORE: &str = r#"{
+        "max": 10,
+        "items": [
+            {"name": "apple", "price": 3, "tag": "x", "isbn": "1-2"},
+            {"name": "Avocado", "price": 12, "tag": "y"},
+            {"name": "banana", "price": 8, "parts": {"name": "peel"}},
+            {"name": "cherry", "price": 10, "tag": "x"}
+        ],
+        "a": {"b": [1, 2, 3], "x y": true},
+        "book": [{"isbn": "3-4"}, {"title": "no isbn"}]
+    }"#;
+
+    #[test]
+    fn translated() {
+        for (path, jq) in [
+            ("$.a.b", ".a.b"),
+            ("{.items[*].name}", ".items[].name"),
+            (".items[0]", ".items[0]"),
+            ("$..name", r#".. | objects | select(has("name")) | .name"#),
+            ("$.a.b[0:2]", ".a.b[0:2][]"),
+            ("$.a.b[:2]", ".a.b[:2][]"),
+            ("$.a.b[-1]", ".a.b[-1]"),
+            ("$.a.b[-2:]", ".a.b[-2:][]"),
+            ("$.a.b[0,2]", ".a.b[0, 2]"),
+            ("$['a','max']", r#".["a", "max"]"#),
+            ("$.a['x y']", r#".a."x y""#),
+            ("$..[0]", ".. | arrays | select(length > 0) | .[0]"),
+            ("$..*", ".. | .[]?"),
+            ("$.items[?(@.price < 10)].name", ".items[] | select(.price < 10) | .name"),
+            ("$.items[?(@.price < 10 && @.tag == 'x')]", r#".items[] | select(.price < 10 and .tag == "x")"#),
+            ("$.items[?(@.price > 10 || @.tag == \"x\")]", r#".items[] | select(.price > 10 or .tag == "x")"#),
+            ("$.items[?(@.name =~ /^a/i)]", r#".items[] | select(.name | test("^a"; "i"))"#),
+            ("$.items[?(@.price < $.max)]", ". as $root | .items[] | select(.price < $root.max)"),
+            ("$.items[?(@.tag)]", ".items[] | select(.tag != null)"),
+            ("$..book[?(@.isbn)]", r#".. | objects | select(has("book")) | .book[] | select(.isbn != null)"#),
+        ] {
+            assert_eq!(to_jq(path), Ok(jq.to_string()), "{}", path);
+        }
+    }
+
+    #[test]
+    fn not_translated() {
+        for (path, error) in [
+            ("$.a[?(@.b || !@.c)]", "`!` in the filter"),
+            ("$.a[0:4:2]", "a slice with a step"),
+            ("$.a[0,'b']", "a union of different selectors"),
+            ("$..['a','b']", "a union after .."),
+            ("a.b", "a path not starting with $"),
+            ("$.a[?(@.b == foo)]", "`foo` in the filter"),
+            ("$.a[?(length(@) > 1)]", "`length` in the filter"),
+            ("$.a[?(@.b", "the unterminated filter"),
+            ("$.a['b", "the unterminated string at column 5"),
+        ] {
+            assert_eq!(to_jq(path), Err(unsupported(error)), "{}", path);
+        }
+    }
+
+    // The nodes of an output, in a stable order
+    fn nodes(output: &str) -> Vec<String> {
+        let mut nodes: Vec<String> = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap().to_string())
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    // The translation selects what jsonpath_lib does (which has no `=~`)
+    #[test]
+    fn same_nodes_as_jsonpath() {
+        let engine = Engine::new(Vec::new(), std::path::PathBuf::from("."));
+        let input = engine.parse(STORE);
+        let store: Value = serde_json::from_str(STORE).unwrap();
+        for path in [
+            "$.a.b",
+            "{.items[*].name}",
+            ".items[0]",
+            "$..name",
+            "$.a.b[0:2]",
+            "$.a.b[-1]",
+            "$.a.b[0,2]",
+            "$.a['x y']",
+            "$.items[?(@.price < 10)].name",
+            "$.items[?(@.price < 10 && @.tag == 'x')]",
+            "$.items[?(@.price < $.max)]",
+            "$.items[?(@.tag)]",
+            "$..book[?(@.isbn)]",
+            "$.a.b[-2:]",
+            "$..[0]",
+            "$..*",
+        ] {
+            let expected = run(path, std::slice::from_ref(&store)).unwrap();
+            let translated = engine.run(&to_jq(path).unwrap(), &input).unwrap();
+            assert!(!expected.is_empty(), "{}", path);
+            assert_eq!(nodes(&translated), nodes(&expected), "{}", path);
+        }
+        let translated = engine.run(&to_jq("$.items[?(@.name =~ /^a/i)].name").unwrap(), &input).unwrap();
+        assert_eq!(translated, "\"apple\"\n\"Avocado\"\n");
+    }
+}
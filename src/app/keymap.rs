@@ -0,0 +1,204 @@
+// Key bindings, listed in the help popup and the status bar.
+// This is documentation only: app.rs and the widgets match the keys themselves,
+// so a new key has to be added in both places. The test below checks that the
+// Ctrl, Alt, function and Tab keys dispatched by app.rs are all listed here.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Context {
+    Global,
+    Input,
+    Json,
+    Table,
+    Edit,
+    Pipeline,
+    Diff,
+    Compare,
+    Popup,
+}
+
+impl Context {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Context::Global => "Global",
+            Context::Input => "Input",
+            Context::Json => "JSON panes",
+            Context::Table => "Table view",
+            Context::Edit => "Edit mode (base pane)",
+            Context::Pipeline => "Pipeline stages",
+            Context::Diff => "Diff",
+            Context::Compare => "Compare",
+            Context::Popup => "Popups",
+        }
+    }
+}
+
+pub struct Binding {
+    pub context: Context,
+    pub keys: &'static str,
+    pub action: &'static str,
+    // Shown in the status bar while the context is focused
+    pub hint: bool,
+}
+
+const fn binding(context: Context, keys: &'static str, action: &'static str, hint: bool) -> Binding {
+    Binding { context, keys, action, hint }
+}
+
+pub const BINDINGS: &[Binding] = &[
+    binding(Context::Global, "Ctrl-C", "quit", true),
+    binding(Context::Global, "Tab / Shift-Tab", "focus the next / previous pane", true),
+    binding(Context::Global, "F1", "show this help", true),
+    binding(Context::Global, "Ctrl-O", "open the query library", false),
+    binding(Context::Global, "Ctrl-B", "save the query to the library", false),
+    binding(Context::Global, "Ctrl-S", "save the program to a file", false),
+    binding(Context::Global, "Alt-L", "switch the query language (jq, JSONPath, JMESPath)", false),
+    binding(Context::Global, "Alt-T", "translate the JSONPath query to jq (experimental)", false),
+    binding(Context::Global, "Alt-R / Alt-J", "toggle the raw / joined output (jq -r / -j)", false),
+    binding(Context::Global, "Ctrl-X", "save the result to a file", false),
+    binding(Context::Global, "Alt-A", "write the result over the input file (or another one), after a diff", false),
+    binding(Context::Global, "Alt-V", "list the schema violations of the focused pane", false),
+    binding(Context::Global, "Ctrl-Y", "copy the result to the clipboard", false),
+    binding(Context::Global, "Ctrl-R", "run the query on the whole file (large inputs)", false),
+    binding(Context::Global, "F2", "toggle the pipeline debugger", false),
+    binding(Context::Global, "F3", "toggle the diff mode", false),
+    binding(Context::Global, "F4", "stack the panes / put them side by side", false),
+    binding(Context::Global, "F5", "swap the panes", false),
+    binding(Context::Global, "F6", "hide / show the base pane", false),
+    binding(Context::Global, "F7", "zoom the focused pane", false),
+    binding(Context::Global, "F8", "show the parse / query / render timings", false),
+    binding(Context::Global, "F9", "show the shape of the input (paths, types, examples)", false),
+    binding(Context::Global, "F10", "show statistics of the result", false),
+    binding(Context::Global, "Alt-←/→ Alt-↑/↓", "resize the panes", false),
+    binding(Context::Global, "Click", "focus a pane, move the cursor to a line", false),
+    binding(Context::Global, "Double-click", "insert the path of a node in the query", false),
+    binding(Context::Global, "Wheel", "scroll the pane under the pointer", false),
+    binding(Context::Input, "←/→", "move the cursor", false),
+    binding(Context::Input, "Home / Ctrl-A", "go to the start", false),
+    binding(Context::Input, "End / Ctrl-E", "go to the end", false),
+    binding(Context::Input, "Ctrl-L", "clear the query", true),
+    binding(Context::Json, "↑/↓", "move the cursor", true),
+    binding(Context::Json, "PgUp/PgDn", "move by a page", false),
+    binding(Context::Json, "Ctrl-U / Ctrl-D", "move by half a page", false),
+    binding(Context::Json, "g / G", "go to the first / last line", false),
+    binding(Context::Json, ":N Enter", "go to line N", false),
+    binding(Context::Json, "n / N", "next / previous selected node", true),
+    binding(Context::Json, "Enter / Space / Click ▾▸", "fold / unfold the node", true),
+    binding(Context::Json, "←/→ zh/zl", "scroll horizontally", false),
+    binding(Context::Json, "w", "toggle soft wrap", false),
+    binding(Context::Json, "e", "toggle eliding long strings", false),
+    binding(Context::Json, "u", "toggle \\u escapes for invisible characters", false),
+    binding(Context::Json, "v", "show the full value of the node", true),
+    binding(Context::Json, "t", "show an array of objects as a table", false),
+    binding(Context::Json, "i", "edit the input document (base pane)", false),
+    binding(Context::Json, "?", "show this help", false),
+    binding(Context::Table, "↑/↓ PgUp/PgDn", "select a row", true),
+    binding(Context::Table, "←/→", "select a column", true),
+    binding(Context::Table, "g / G", "go to the first / last row", false),
+    binding(Context::Table, "s", "sort by the column (ascending, descending, none)", true),
+    binding(Context::Table, "S", "add the sort to the query (result pane)", false),
+    binding(Context::Table, "x / X", "hide the column / show all the columns", true),
+    binding(Context::Table, "v", "show the full value of the cell", false),
+    binding(Context::Table, "t", "back to the tree view", true),
+    binding(Context::Edit, "c", "change the value under the cursor", true),
+    binding(Context::Edit, "r", "rename the key", true),
+    binding(Context::Edit, "d", "delete the node", true),
+    binding(Context::Edit, "a / A", "add a node after the cursor / at the end of the container", true),
+    binding(Context::Edit, "Ctrl-Z", "undo the last edit", true),
+    binding(Context::Edit, "Ctrl-S", "save the file", true),
+    binding(Context::Edit, "i / Esc", "leave the edit mode", false),
+    binding(Context::Pipeline, "↑/↓", "select a stage", true),
+    binding(Context::Diff, "↑/↓ PgUp/PgDn", "move the cursor", true),
+    binding(Context::Diff, "n / N", "next / previous change", true),
+    binding(Context::Diff, "Ctrl-P", "export as JSON Patch", true),
+    binding(Context::Compare, "↑/↓ PgUp/PgDn", "move the cursor", true),
+    binding(Context::Compare, "n / N", "next / previous difference", true),
+    binding(Context::Popup, "Enter", "confirm", true),
+    binding(Context::Popup, "Tab / Shift-Tab", "next / previous field", false),
+    binding(Context::Popup, "Esc", "close", true),
+];
+
+// Hints of the focused context, followed by the global ones
+pub fn hints(context: Context) -> Vec<&'static Binding> {
+    let mut hints: Vec<&Binding> = BINDINGS
+        .iter()
+        .filter(|binding| binding.hint && binding.context == context)
+        .collect();
+    if context != Context::Popup {
+        hints.extend(
+            BINDINGS
+                .iter()
+                .filter(|binding| binding.hint && binding.context == Context::Global)
+        );
+    }
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Labels of the Ctrl, Alt, function and Tab keys matched in app.rs
+    fn dispatched() -> Vec<String> {
+        let source = include_str!("app.rs");
+        let mut lines = source.lines();
+        let mut keys = Vec::new();
+        while let Some(line) = lines.next() {
+            let Some(code) = line.trim().strip_prefix("code: event::KeyCode::") else {
+                continue;
+            };
+            let code = code.trim_end_matches(',');
+            let modifiers = lines.next().unwrap_or("").trim();
+            let key = if let Some(number) = code.strip_prefix("F(") {
+                format!("F{}", number.trim_end_matches(')'))
+            } else if code == "Tab" && modifiers.ends_with("NONE,") {
+                "Tab".to_string()
+            } else if code == "BackTab" {
+                "Shift-Tab".to_string()
+            } else if let Some(character) = code.strip_prefix("Char('").and_then(|c| c.strip_suffix("')")) {
+                let character = character.to_uppercase();
+                match modifiers {
+                    "modifiers: event::KeyModifiers::CONTROL," => format!("Ctrl-{}", character),
+                    "modifiers: event::KeyModifiers::ALT," => format!("Alt-{}", character),
+                    _ => continue,
+                }
+            } else {
+                continue;
+            };
+            keys.push(key);
+        }
+        keys
+    }
+
+    fn listed(context: Option<Context>) -> Vec<&'static str> {
+        BINDINGS
+            .iter()
+            .filter(|binding| context.is_none_or(|context| binding.context == context))
+            .flat_map(|binding| binding.keys.split(|c: char| c == '/' || c.is_whitespace()))
+            .filter(|key| !key.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn dispatched_keys_listed() {
+        let listed = listed(None);
+        let dispatched = dispatched();
+        assert!(dispatched.contains(&"Shift-Tab".to_string()));
+        for key in &dispatched {
+            assert!(listed.contains(&key.as_str()), "{} is not in the key bindings", key);
+        }
+    }
+
+    #[test]
+    fn listed_global_keys_dispatched() {
+        let dispatched = dispatched();
+        for key in listed(Some(Context::Global)) {
+            let modified = key.starts_with("Ctrl-") || key.starts_with("Alt-");
+            let checked = key.starts_with('F') && key[1..].parse::<u8>().is_ok()
+                || key.contains("Tab")
+                || modified && key.len() == 5 && key.ends_with(|c: char| c.is_ascii_alphabetic());
+            if checked {
+                assert!(dispatched.iter().any(|d| d == key), "{} is not dispatched", key);
+            }
+        }
+    }
+}
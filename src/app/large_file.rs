@@ -0,0 +1,230 @@
+// Input too large to be read in a String and queried on every keystroke.
+// The file is memory mapped and scanned once, like `jq --stream` would,
+// to find where each value starts and ends: the elements when the file is
+// a single array, the top-level values otherwise. Only a sample of them is
+// kept, the live query runs on it and the whole file is given to jq on demand.
+// The scan does not validate the values, jq reports the invalid ones.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::app::config::_Large;
+
+// Progress is reported every PROGRESS_STEP bytes
+const PROGRESS_STEP: usize = 64 * 1024 * 1024;
+
+pub struct LargeFile {
+    mmap: Mmap,
+    // The file is a single array, the values are its elements
+    array: bool,
+    random: bool,
+    count: usize,
+    // Byte ranges of the sampled values, in file order
+    sample: Vec<(usize, usize)>,
+}
+
+// Keeps the first values, or a uniform random sample of them (reservoir sampling)
+struct Sampler {
+    size: usize,
+    random: bool,
+    rng: fastrand::Rng,
+    count: usize,
+    values: Vec<(usize, usize)>,
+}
+
+impl Sampler {
+    fn add(&mut self, value: (usize, usize)) {
+        if self.values.len() < self.size {
+            self.values.push(value);
+        } else if self.random {
+            let slot = self.rng.usize(..=self.count);
+            if slot < self.size {
+                self.values[slot] = value;
+            }
+        }
+        self.count += 1;
+    }
+}
+
+// Current top-level value (or array element) of a scan
+struct Value {
+    start: Option<usize>,
+    // A number, a boolean or null, which ends at the first delimiter
+    scalar: bool,
+}
+
+impl Value {
+    fn begin(&mut self, position: usize, scalar: bool, sampler: &mut Sampler) {
+        if self.scalar {
+            self.end(position, sampler);
+        }
+        self.start = Some(position);
+        self.scalar = scalar;
+    }
+
+    fn end(&mut self, position: usize, sampler: &mut Sampler) {
+        if let Some(start) = self.start.take() {
+            sampler.add((start, position));
+        }
+        self.scalar = false;
+    }
+}
+
+fn invalid(message: &str, position: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{} at byte {}", message, position))
+}
+
+// Position of the quote closing the string starting at `position`
+fn string_end(data: &[u8], mut position: usize) -> Option<usize> {
+    loop {
+        position += data.get(position..)?.iter().position(|b| *b == b'"' || *b == b'\\')?;
+        if data[position] == b'"' {
+            return Some(position);
+        }
+        // Skip the escaped character
+        position += 2;
+    }
+}
+
+// Feed the values found at `level` (0 for the top-level values, 1 for the
+// elements of a top-level array) to the sampler. Returns false if `level`
+// is 1 but the file is not a single array.
+fn scan(data: &[u8], level: usize, sampler: &mut Sampler, progress: &mut dyn FnMut(usize)) -> io::Result<bool> {
+    let mut depth = 0;
+    let mut value = Value { start: None, scalar: false };
+    let mut seen_array = false;
+    let mut next_progress = 0;
+    let mut position = 0;
+    while position < data.len() {
+        if position >= next_progress {
+            progress(position);
+            next_progress = position + PROGRESS_STEP;
+        }
+        let byte = data[position];
+        if level == 1 && depth == 0 && !byte.is_ascii_whitespace() {
+            if byte != b'[' || seen_array {
+                return Ok(false);
+            }
+            seen_array = true;
+            depth = 1;
+            position += 1;
+            continue;
+        }
+        match byte {
+            b'"' => {
+                if depth == level {
+                    value.begin(position, false, sampler);
+                }
+                position = string_end(data, position + 1)
+                    .ok_or_else(|| invalid("Unterminated string", position))?;
+                if depth == level {
+                    value.end(position + 1, sampler);
+                }
+            },
+            b'{' | b'[' => {
+                if depth == level {
+                    value.begin(position, false, sampler);
+                }
+                depth += 1;
+            },
+            b'}' | b']' => {
+                if depth == level && value.scalar {
+                    value.end(position, sampler);
+                }
+                if depth == 0 {
+                    return Err(invalid("Unexpected closing bracket", position));
+                }
+                depth -= 1;
+                if depth == level {
+                    value.end(position + 1, sampler);
+                }
+            },
+            b',' | b':' | b' ' | b'\t' | b'\n' | b'\r' => {
+                if depth == level && value.scalar {
+                    value.end(position, sampler);
+                }
+            },
+            _ => {
+                if depth == level && value.start.is_none() {
+                    value.begin(position, true, sampler);
+                }
+            },
+        }
+        position += 1;
+    }
+    if depth > 0 {
+        return Err(invalid("Unexpected end of file", data.len()));
+    }
+    value.end(data.len(), sampler);
+    progress(data.len());
+    Ok(true)
+}
+
+impl LargeFile {
+    // `progress` is called with the number of bytes scanned so far
+    pub fn open(path: &Path, config: &_Large, progress: &mut dyn FnMut(usize, usize)) -> io::Result<LargeFile> {
+        let file = File::open(path)?;
+        // The file must not be truncated while it is mapped, like any
+        // input being read by jq
+        let mmap = unsafe { Mmap::map(&file)? };
+        let data: &[u8] = &mmap;
+        let total = data.len();
+
+        let new_sampler = || Sampler {
+            size: config.sample_size,
+            random: config.random_sample,
+            rng: fastrand::Rng::new(),
+            count: 0,
+            values: Vec::new(),
+        };
+        let mut sampler = new_sampler();
+        let mut array = true;
+        if !scan(data, 1, &mut sampler, &mut |done| progress(done, total))? {
+            // Several top-level values (ie: JSON lines), scan again
+            array = false;
+            sampler = new_sampler();
+            scan(data, 0, &mut sampler, &mut |done| progress(done, total))?;
+        }
+        sampler.values.sort_unstable();
+
+        Ok(LargeFile {
+            mmap,
+            array,
+            random: config.random_sample,
+            count: sampler.count,
+            sample: sampler.values,
+        })
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    // The sampled values, as an array if the file is one
+    pub fn sample(&self) -> String {
+        let values: Vec<String> = self.sample
+            .iter()
+            .map(|(start, end)| String::from_utf8_lossy(&self.mmap[*start..*end]).into_owned())
+            .collect();
+        if self.array {
+            format!("[{}]", values.join(","))
+        } else {
+            values.join("\n")
+        }
+    }
+
+    // ie: "random sample: 1000 of 25000 elements"
+    pub fn description(&self) -> String {
+        let unit = if self.array { "elements" } else { "values" };
+        if self.sample.len() == self.count {
+            format!("all {} {}", self.count, unit)
+        } else if self.random {
+            format!("random sample: {} of {} {}", self.sample.len(), self.count, unit)
+        } else {
+            format!("sample: first {} of {} {}", self.sample.len(), self.count, unit)
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// A library of named jq queries, stored as a JSON file.
+// The file is meant to be shared (ie: committed in a git repository),
+// so it only contains plain data and is written with a stable layout.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueryEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub query: String,
+}
+
+impl QueryEntry {
+    // Text used when fuzzy searching the library
+    pub fn haystack(&self) -> String {
+        format!("{} {} {}", self.name, self.description, self.tags.join(" "))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct LibraryFile {
+    #[serde(default)]
+    queries: Vec<QueryEntry>,
+}
+
+pub struct Library {
+    path: PathBuf,
+    entries: Vec<QueryEntry>,
+}
+
+impl Library {
+    // Default location of the library when no path is given
+    // ie: $XDG_CONFIG_HOME/jq_live_query/library.json
+    pub fn default_path() -> PathBuf {
+        let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => match std::env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(".config"),
+                None => PathBuf::from("."),
+            },
+        };
+        config_dir.join("jq_live_query").join("library.json")
+    }
+
+    pub fn load(path: &Path) -> Result<Library, io::Error> {
+        let entries = if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            let file: LibraryFile = serde_json::from_str(&content).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid query library {}: {}", path.display(), error),
+                )
+            })?;
+            file.queries
+        } else {
+            // A missing library is an empty one, it will be created on first save
+            Vec::new()
+        };
+        Ok(Library {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), io::Error> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let file = LibraryFile {
+            queries: self.entries.clone(),
+        };
+        let mut content = serde_json::to_string_pretty(&file)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        content.push('\n');
+        std::fs::write(&self.path, content)
+    }
+
+    pub fn entries(&self) -> &[QueryEntry] {
+        &self.entries
+    }
+
+    pub fn find(&self, name: &str) -> Option<&QueryEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    // Insert the entry, replacing any existing entry with the same name
+    pub fn upsert(&mut self, entry: QueryEntry) {
+        match self.entries.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+}
+
+// Score how well `pattern` matches `text` as a case insensitive subsequence.
+// Returns None if the pattern does not match at all.
+// Consecutive matches and matches at the start of a word score higher.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(|c| c.to_lowercase()).collect();
+    let mut score: i64 = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for p in pattern.chars().flat_map(|c| c.to_lowercase()) {
+        if p.is_whitespace() {
+            continue;
+        }
+        let found = text[position..].iter().position(|&c| c == p)? + position;
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Penalize gaps between matched characters
+        score -= (found - position) as i64 / 4;
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
@@ -1,6 +1,21 @@
 pub mod app;
 pub mod config;
+pub mod diff;
+pub mod document;
+pub mod edit;
+pub mod engine;
+pub mod jmespath;
+pub mod jsonpath;
+pub mod keymap;
+pub mod large_file;
+pub mod library;
+pub mod schema;
+pub mod shape;
+pub mod stats;
+pub mod tokenizer;
 pub mod widgets;
 
 pub use app::App;
 pub use config::Config;
+pub use engine::Engine;
+pub use library::Library;
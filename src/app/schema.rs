@@ -0,0 +1,161 @@
+// JSON Schema validation of the input (--schema) and of the result
+// (--output-schema). Every value of a stream is validated, each violation
+// keeps the jq path of the offending node to highlight and select it.
+
+use std::io;
+use std::path::Path;
+
+use jsonschema::paths::PathChunk;
+use jsonschema::JSONSchema;
+use serde_json::Value;
+
+use crate::app::widgets::json::jq_path;
+
+// Violations listed per validation, the count is shown as "1000+" beyond
+pub const MAX_VIOLATIONS: usize = 1000;
+
+pub struct Violation {
+    // Index of the value in the stream
+    pub value: usize,
+    // jq style path of the offending node in that value (ie: ["a",0])
+    pub path: Vec<Value>,
+    // The path as a jq expression (ie: `.items[2].id`), prefixed with
+    // the value number in a stream
+    pub location: String,
+    pub message: String,
+}
+
+pub struct Schema {
+    // File name, shown in the titles
+    pub name: String,
+    compiled: JSONSchema,
+}
+
+impl Schema {
+    pub fn load(path: &Path) -> io::Result<Schema> {
+        let invalid = |error: String| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid schema {}: {}", path.display(), error)
+        );
+        let schema: Value = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|error| invalid(error.to_string()))?;
+        let compiled = JSONSchema::compile(&schema).map_err(|error| invalid(error.to_string()))?;
+        Ok(Schema {
+            name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned()
+            ),
+            compiled,
+        })
+    }
+
+    // Validate each value of `json`, None if it is not made of JSON values
+    pub fn validate(&self, json: &str) -> Option<Vec<Violation>> {
+        let mut violations = Vec::new();
+        let mut values = 0;
+        let mut stream = serde_json::Deserializer::from_str(json).into_iter::<Value>();
+        while let Some(value) = stream.next() {
+            let index = values;
+            let value = value.ok()?;
+            values += 1;
+            if let Err(errors) = self.compiled.validate(&value) {
+                for error in errors.take(MAX_VIOLATIONS - violations.len()) {
+                    violations.push(Violation {
+                        value: index,
+                        path: error.instance_path
+                            .iter()
+                            .filter_map(|chunk| match chunk {
+                                PathChunk::Property(name) => Some(Value::String(name.to_string())),
+                                PathChunk::Index(i) => Some(Value::from(*i)),
+                                PathChunk::Keyword(_) => None,
+                            })
+                            .collect(),
+                        location: String::new(),
+                        message: error.to_string(),
+                    });
+                }
+            }
+            if violations.len() >= MAX_VIOLATIONS {
+                // The locations name the value if more follow
+                values += stream.next().map_or(0, |_| 1);
+                break;
+            }
+        }
+        for violation in violations.iter_mut() {
+            let path = if violation.path.is_empty() { ".".to_string() } else { jq_path(&violation.path) };
+            violation.location = if values > 1 {
+                format!("value {}: {}", violation.value + 1, path)
+            } else {
+                path
+            };
+        }
+        Some(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(name: &str, schema: &str) -> Schema {
+        let path = std::env::temp_dir().join(format!("jq_live_query-{}-{}.json", std::process::id(), name));
+        std::fs::write(&path, schema).unwrap();
+        let schema = Schema::load(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        schema
+    }
+
+    const ITEMS: &str = r#"{
+        "type": "object",
+        "properties": {
+            "items": {"type": "array", "items": {"type": "object", "properties": {"id": {"type": "integer"}}}},
+            "a b": {"type": "string"}
+        },
+        "required": ["items"]
+    }"#;
+
+    fn locations(violations: &[Violation]) -> Vec<&str> {
+        violations.iter().map(|violation| violation.location.as_str()).collect()
+    }
+
+    #[test]
+    fn jq_paths_of_violations() {
+        let schema = schema("paths", ITEMS);
+        let violations = schema.validate(r#"{"items": [{"id": 1}, {"id": "x"}, 3], "a b": 1}"#).unwrap();
+        assert_eq!(locations(&violations), vec![".items[1].id", ".items[2]", r#"."a b""#]);
+        assert_eq!(violations[0].path, vec![Value::from("items"), Value::from(1), Value::from("id")]);
+        assert!(violations.iter().all(|violation| violation.value == 0));
+        // The root itself
+        let violations = schema.validate("{}").unwrap();
+        assert_eq!(locations(&violations), vec!["."]);
+        assert!(violations[0].path.is_empty());
+        assert!(schema.validate(r#"{"items": []}"#).unwrap().is_empty());
+        assert!(schema.validate("{").is_none());
+    }
+
+    #[test]
+    fn values_of_stream() {
+        let schema = schema("stream", ITEMS);
+        let violations = schema.validate(r#"{"items": []} {"items": [{"id": 0.5}]} 1"#).unwrap();
+        assert_eq!(locations(&violations), vec!["value 2: .items[0].id", "value 3: ."]);
+        assert_eq!(violations.iter().map(|violation| violation.value).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn violations_cut_off() {
+        let schema = schema("cut-off", r#"{"items": {"type": "string"}}"#);
+        let many = serde_json::to_string(&(0..MAX_VIOLATIONS + 10).collect::<Vec<usize>>()).unwrap();
+        let violations = schema.validate(&many).unwrap();
+        assert_eq!(violations.len(), MAX_VIOLATIONS);
+        assert_eq!(violations.last().unwrap().location, format!(".[{}]", MAX_VIOLATIONS - 1));
+        // Across the values of a stream, which are still named
+        let half = serde_json::to_string(&(0..MAX_VIOLATIONS / 2 + 1).collect::<Vec<usize>>()).unwrap();
+        let violations = schema.validate(&format!("{} {} {}", half, half, half)).unwrap();
+        assert_eq!(violations.len(), MAX_VIOLATIONS);
+        assert_eq!(violations.last().unwrap().value, 1);
+        assert!(violations[0].location.starts_with("value 1: "));
+        let violations = schema.validate(&format!("{} [1]", many)).unwrap();
+        assert_eq!(violations.len(), MAX_VIOLATIONS);
+        assert_eq!(violations[0].location, "value 1: .[0]");
+    }
+}
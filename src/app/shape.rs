@@ -0,0 +1,265 @@
+// Shape of a document, inferred from its values like `[paths] | unique`
+// would list them: every key path (the elements of an array share the
+// path `.a[]`), with the types seen there, whether the key is missing from
+// some objects, the lengths of the arrays and a few example values.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::app::document::{Document, Key, Kind};
+use crate::app::widgets::json::jq_path;
+
+// Distinct example values kept per path, and their length
+const EXAMPLES: usize = 3;
+const EXAMPLE_LENGTH: usize = 24;
+
+pub const TYPE_NAMES: [&str; 6] = ["null", "boolean", "number", "string", "array", "object"];
+
+pub struct ShapeNode {
+    // jq path of the node (ie: `.items[].name`)
+    pub path: String,
+    // Key in the parent object, `[]` for the elements of an array
+    pub label: String,
+    pub element: bool,
+    pub depth: usize,
+    // Values seen at this path, by type (in TYPE_NAMES order)
+    pub types: [usize; 6],
+    // Objects seen at the parent path, the key is optional if it is
+    // missing from some of them
+    pub parent_objects: usize,
+    // Shortest and longest array
+    pub lengths: Option<(usize, usize)>,
+    pub examples: Vec<String>,
+    pub children: Vec<usize>,
+    // Index of the children by path
+    index: HashMap<String, usize>,
+}
+
+impl ShapeNode {
+    pub fn count(&self) -> usize {
+        self.types.iter().sum()
+    }
+
+    pub fn optional(&self) -> bool {
+        !self.element && self.count() < self.parent_objects
+    }
+}
+
+// Nodes in depth first order, the root first
+pub struct Shape {
+    pub nodes: Vec<ShapeNode>,
+}
+
+fn type_index(kind: &Kind) -> usize {
+    match kind {
+        Kind::Null => 0,
+        Kind::Bool(_) => 1,
+        Kind::Number(_) => 2,
+        Kind::String(_) => 3,
+        Kind::Array => 4,
+        Kind::Object => 5,
+    }
+}
+
+fn example(kind: &Kind) -> Option<String> {
+    let text = match kind {
+        Kind::Null => "null".to_string(),
+        Kind::Bool(b) => b.to_string(),
+        Kind::Number(n) => n.to_string(),
+        Kind::String(s) => Value::String(s.to_string()).to_string(),
+        Kind::Array | Kind::Object => return None,
+    };
+    if text.chars().count() > EXAMPLE_LENGTH {
+        Some(format!("{}…", text.chars().take(EXAMPLE_LENGTH - 1).collect::<String>()))
+    } else {
+        Some(text)
+    }
+}
+
+impl Shape {
+    pub fn infer(document: &Document) -> Shape {
+        let mut shape = Shape { nodes: Vec::new() };
+        shape.nodes.push(ShapeNode {
+            path: ".".to_string(),
+            label: ".".to_string(),
+            element: false,
+            depth: 0,
+            types: [0; 6],
+            parent_objects: 0,
+            lengths: None,
+            examples: Vec::new(),
+            children: Vec::new(),
+            index: HashMap::new(),
+        });
+        shape.add(document, 0, 0);
+        shape.sort();
+        shape
+    }
+
+    // Child of `parent` at `key`, or at `[]` if `key` is None
+    fn child(&mut self, parent: usize, key: Option<&str>) -> usize {
+        let parent_path = &self.nodes[parent].path;
+        let prefix = if parent_path == "." { "" } else { parent_path };
+        let path = match key {
+            Some(key) => format!("{}{}", prefix, jq_path(&[Value::String(key.to_string())])),
+            None => format!("{}[]", if prefix.is_empty() { "." } else { prefix }),
+        };
+        if let Some(child) = self.nodes[parent].index.get(&path) {
+            return *child;
+        }
+        let child = self.nodes.len();
+        self.nodes[parent].index.insert(path.clone(), child);
+        self.nodes.push(ShapeNode {
+            path,
+            label: key.unwrap_or("[]").to_string(),
+            element: key.is_none(),
+            depth: self.nodes[parent].depth + 1,
+            types: [0; 6],
+            parent_objects: 0,
+            lengths: None,
+            examples: Vec::new(),
+            children: Vec::new(),
+            index: HashMap::new(),
+        });
+        self.nodes[parent].children.push(child);
+        child
+    }
+
+    // Add the value `node` of the document to the shape node `shape_node`
+    fn add(&mut self, document: &Document, node: usize, shape_node: usize) {
+        let kind = &document.node(node).kind;
+        let entry = &mut self.nodes[shape_node];
+        entry.types[type_index(kind)] += 1;
+        if entry.examples.len() < EXAMPLES {
+            if let Some(example) = example(kind) {
+                if !entry.examples.contains(&example) {
+                    entry.examples.push(example);
+                }
+            }
+        }
+        match kind {
+            Kind::Array => {
+                let length = document.node(node).children;
+                entry.lengths = Some(match entry.lengths {
+                    Some((min, max)) => (min.min(length), max.max(length)),
+                    None => (length, length),
+                });
+                if length > 0 {
+                    let elements = self.child(shape_node, None);
+                    for child in document.children(node) {
+                        self.add(document, child, elements);
+                    }
+                }
+            },
+            Kind::Object => {
+                for child in document.children(node) {
+                    if let Key::Name(name) = &document.node(child).key {
+                        let child_shape = self.child(shape_node, Some(name));
+                        self.add(document, child, child_shape);
+                    }
+                }
+                // A key seen less often than the objects is optional
+                let objects = self.nodes[shape_node].types[type_index(&Kind::Object)];
+                let children = self.nodes[shape_node].children.clone();
+                for child in children {
+                    if !self.nodes[child].element {
+                        self.nodes[child].parent_objects = objects;
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    // Put the nodes in depth first order
+    fn sort(&mut self) {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            stack.extend(self.nodes[node].children.iter().rev());
+        }
+        let mut position = vec![0; self.nodes.len()];
+        for (new, old) in order.iter().enumerate() {
+            position[*old] = new;
+        }
+        let mut nodes: Vec<Option<ShapeNode>> = std::mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        self.nodes = order
+            .iter()
+            .map(|old| {
+                let mut node = nodes[*old].take().unwrap();
+                for child in node.children.iter_mut() {
+                    *child = position[*child];
+                }
+                node.index.clear();
+                node
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infer(json: &str) -> Shape {
+        Shape::infer(&Document::parse(json).unwrap())
+    }
+
+    fn node<'a>(shape: &'a Shape, path: &str) -> &'a ShapeNode {
+        shape.nodes.iter().find(|node| node.path == path).unwrap_or_else(|| panic!("no {}", path))
+    }
+
+    #[test]
+    fn paths_in_depth_first_order() {
+        let shape = infer(r#"{"b": {"c": 1}, "a": [{"d": 2}], "a b": null}"#);
+        let paths: Vec<&str> = shape.nodes.iter().map(|node| node.path.as_str()).collect();
+        assert_eq!(paths, vec![".", ".b", ".b.c", ".a", ".a[]", ".a[].d", r#"."a b""#]);
+        let depths: Vec<usize> = shape.nodes.iter().map(|node| node.depth).collect();
+        assert_eq!(depths, vec![0, 1, 2, 1, 2, 3, 1]);
+        assert_eq!(shape.nodes[0].children, vec![1, 3, 6]);
+        assert_eq!(node(&shape, ".a[]").label, "[]");
+    }
+
+    #[test]
+    fn root_arrays() {
+        let shape = infer(r#"[[1, "x"], [], [{"a": true}]]"#);
+        let paths: Vec<&str> = shape.nodes.iter().map(|node| node.path.as_str()).collect();
+        assert_eq!(paths, vec![".", ".[]", ".[][]", ".[][].a"]);
+        assert_eq!(node(&shape, ".[][]").types, [0, 0, 1, 1, 0, 1]);
+        // An empty root array has no element path
+        assert_eq!(infer("[]").nodes.len(), 1);
+    }
+
+    #[test]
+    fn optional_keys() {
+        let shape = infer(r#"[{"a": 1, "b": null}, {"a": 2}, {"a": 3, "c": {"d": 1}}, {"a": 4, "c": {}}]"#);
+        assert!(!node(&shape, ".[]").optional());
+        assert!(!node(&shape, ".[].a").optional());
+        // A null value is not a missing key
+        assert!(node(&shape, ".[].b").optional());
+        assert_eq!(node(&shape, ".[].b").count(), 1);
+        assert_eq!(node(&shape, ".[].b").parent_objects, 4);
+        assert!(node(&shape, ".[].c").optional());
+        assert!(node(&shape, ".[].c.d").optional());
+        assert_eq!(node(&shape, ".[].c.d").parent_objects, 2);
+        // Not optional when the parent is sometimes not an object
+        let shape = infer(r#"[{"a": 1}, 2, {"a": 3}]"#);
+        assert!(!node(&shape, ".[].a").optional());
+    }
+
+    #[test]
+    fn lengths_types_and_examples() {
+        let shape = infer(r#"{"a": [[1, 2, 3], [], [4]], "s": ["x", "x", "y", "z", "w", "a very long string, longer than the examples"]}"#);
+        assert_eq!(node(&shape, ".a").lengths, Some((3, 3)));
+        assert_eq!(node(&shape, ".a[]").lengths, Some((0, 3)));
+        assert_eq!(node(&shape, ".a[][]").lengths, None);
+        assert_eq!(node(&shape, ".a[][]").types, [0, 0, 4, 0, 0, 0]);
+        assert_eq!(node(&shape, ".s[]").examples, vec![r#""x""#, r#""y""#, r#""z""#]);
+        let long = example(&Kind::String("a very long string, longer than the examples".into())).unwrap();
+        assert_eq!(long.chars().count(), EXAMPLE_LENGTH);
+        assert!(long.ends_with('…'));
+        assert_eq!(example(&Kind::Array), None);
+    }
+}
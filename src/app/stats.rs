@@ -0,0 +1,227 @@
+// Summary statistics of a result, computed on its values: the elements
+// when the result is a single array, the values of the stream otherwise.
+// Numbers get their range, mean and percentiles with a histogram, strings
+// their distinct and most frequent values, objects the fill rate of each key.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+// Bars of the number histogram, and most frequent strings listed
+pub const HISTOGRAM_BINS: usize = 16;
+pub const TOP_VALUES: usize = 10;
+
+pub enum Summary {
+    Numbers {
+        min: f64,
+        max: f64,
+        mean: f64,
+        p50: f64,
+        p95: f64,
+        // Values in each of HISTOGRAM_BINS equal ranges between min and max
+        histogram: Vec<u64>,
+    },
+    Strings {
+        distinct: usize,
+        // Most frequent first, with their count
+        top: Vec<(String, usize)>,
+    },
+    Objects {
+        // Keys in the order they first appear, with the number of
+        // objects where they are set and not null
+        fill: Vec<(String, usize)>,
+    },
+    // Values of different types, or only nulls
+    Mixed,
+}
+
+pub struct Stats {
+    pub count: usize,
+    // Values by type, in TYPE_NAMES order
+    pub types: [usize; 6],
+    pub summary: Summary,
+}
+
+fn type_index(value: &Value) -> usize {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], percent: usize) -> f64 {
+    let rank = (sorted.len() * percent).div_ceil(100);
+    sorted[rank.saturating_sub(1)]
+}
+
+fn numbers(values: &[Value]) -> Summary {
+    let mut sorted: Vec<f64> = values.iter().filter_map(|value| value.as_f64()).collect();
+    if sorted.is_empty() {
+        return Summary::Mixed;
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+    let mut histogram = vec![0; HISTOGRAM_BINS];
+    for value in &sorted {
+        let bin = if max > min {
+            ((value - min) / (max - min) * HISTOGRAM_BINS as f64) as usize
+        } else {
+            0
+        };
+        histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+    }
+    Summary::Numbers {
+        min,
+        max,
+        mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+        p50: percentile(&sorted, 50),
+        p95: percentile(&sorted, 95),
+        histogram,
+    }
+}
+
+fn strings(values: &[Value]) -> Summary {
+    // Counts, and the position of the first occurrence to break ties
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (position, value) in values.iter().enumerate() {
+        if let Value::String(s) = value {
+            counts.entry(s).or_insert((0, position)).0 += 1;
+        }
+    }
+    let mut top: Vec<(&str, (usize, usize))> = counts.into_iter().collect();
+    top.sort_by_key(|(_, (count, first))| (std::cmp::Reverse(*count), *first));
+    Summary::Strings {
+        distinct: top.len(),
+        top: top
+            .into_iter()
+            .take(TOP_VALUES)
+            .map(|(s, (count, _))| (s.to_string(), count))
+            .collect(),
+    }
+}
+
+fn objects(values: &[Value]) -> Summary {
+    let mut fill: Vec<(String, usize)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for value in values {
+        if let Value::Object(object) = value {
+            for (key, value) in object {
+                let i = *index.entry(key).or_insert_with(|| {
+                    fill.push((key.clone(), 0));
+                    fill.len() - 1
+                });
+                if !value.is_null() {
+                    fill[i].1 += 1;
+                }
+            }
+        }
+    }
+    Summary::Objects { fill }
+}
+
+impl Stats {
+    // None if the output is not made of JSON values (ie: raw strings)
+    pub fn new(output: &str) -> Option<Stats> {
+        let mut values = serde_json::Deserializer::from_str(output)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<Value>, _>>()
+            .ok()?;
+        if let [Value::Array(_)] = values.as_slice() {
+            if let Some(Value::Array(elements)) = values.pop() {
+                values = elements;
+            }
+        }
+
+        let mut types = [0; 6];
+        for value in &values {
+            types[type_index(value)] += 1;
+        }
+        let count = values.len();
+        // Nulls are missing values, the others must be of a single type
+        let set = count - types[0];
+        let summary = match types.iter().skip(1).position(|n| *n == set) {
+            _ if set == 0 => Summary::Mixed,
+            Some(1) => numbers(&values),
+            Some(2) => strings(&values),
+            Some(4) => objects(&values),
+            _ => Summary::Mixed,
+        };
+        Some(Stats { count, types, summary })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(output: &str) -> Summary {
+        Stats::new(output).unwrap().summary
+    }
+
+    #[test]
+    fn summary_by_type() {
+        assert!(matches!(summary("[1, 2, null]"), Summary::Numbers { .. }));
+        assert!(matches!(summary(r#""a" "b" null"#), Summary::Strings { .. }));
+        assert!(matches!(summary(r#"[{"a": 1}, null]"#), Summary::Objects { .. }));
+        assert!(matches!(summary("[true, false]"), Summary::Mixed));
+        assert!(matches!(summary("[[1], [2]]"), Summary::Mixed));
+        assert!(matches!(summary(r#"[1, "a"]"#), Summary::Mixed));
+        assert!(matches!(summary("[null, null]"), Summary::Mixed));
+        assert!(matches!(summary("[]"), Summary::Mixed));
+        // Several arrays are values of the stream, not elements
+        assert!(matches!(summary("[1] [2]"), Summary::Mixed));
+        let stats = Stats::new(r#"[1, "a", null, true, [], {}, 2]"#).unwrap();
+        assert_eq!(stats.count, 7);
+        assert_eq!(stats.types, [1, 1, 2, 1, 1, 1]);
+        assert!(Stats::new("raw text").is_none());
+    }
+
+    #[test]
+    fn percentiles() {
+        assert_eq!(percentile(&[7.0], 50), 7.0);
+        assert_eq!(percentile(&[7.0], 95), 7.0);
+        assert_eq!(percentile(&[1.0, 2.0], 50), 1.0);
+        assert_eq!(percentile(&[1.0, 2.0], 95), 2.0);
+        let twenty: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(&twenty, 50), 10.0);
+        assert_eq!(percentile(&twenty, 95), 19.0);
+        assert_eq!(percentile(&twenty, 100), 20.0);
+    }
+
+    #[test]
+    fn numbers_summary() {
+        let Summary::Numbers { min, max, mean, p50, p95, histogram } = summary("[4, 1, 3, 2, null]") else {
+            panic!("not numbers");
+        };
+        assert_eq!((min, max, mean, p50, p95), (1.0, 4.0, 2.5, 2.0, 4.0));
+        // The maximum falls in the last bin, not past it
+        assert_eq!(histogram.len(), HISTOGRAM_BINS);
+        assert_eq!(histogram[0], 1);
+        assert_eq!(histogram[HISTOGRAM_BINS - 1], 1);
+        assert_eq!(histogram.iter().sum::<u64>(), 4);
+        // All equal values go to the first bin
+        let Summary::Numbers { histogram, .. } = summary("[5, 5, 5]") else {
+            panic!("not numbers");
+        };
+        assert_eq!(histogram[0], 3);
+    }
+
+    #[test]
+    fn strings_and_objects() {
+        let Summary::Strings { distinct, top } = summary(r#"["b", "a", "b", "c", "a", "b"]"#) else {
+            panic!("not strings");
+        };
+        assert_eq!(distinct, 3);
+        // Ties in the order they first appear
+        assert_eq!(top, vec![("b".to_string(), 3), ("a".to_string(), 2), ("c".to_string(), 1)]);
+        let Summary::Objects { fill } = summary(r#"[{"b": 1, "a": null}, {"a": 2}, {"c": []}]"#) else {
+            panic!("not objects");
+        };
+        assert_eq!(fill, vec![("b".to_string(), 1), ("a".to_string(), 1), ("c".to_string(), 1)]);
+    }
+}
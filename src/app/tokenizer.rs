@@ -0,0 +1,412 @@
+// A small jq tokenizer.
+// It does not validate the program (libjq does that), it only needs to
+// know where tokens start and end, so that the query can be split and
+// rewritten without breaking strings, comments or nested expressions.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenKind {
+    // Keyword or function name, possibly namespaced (ie: k8s::pods)
+    Ident,
+    // .foo
+    Field,
+    // .
+    Dot,
+    // ..
+    Recurse,
+    // $foo or $__loc__
+    Variable,
+    // @csv, @base64d...
+    Format,
+    // String literal, including its interpolations
+    Str,
+    Number,
+    // ( [ {
+    Open,
+    // ) ] }
+    Close,
+    // | , ; : and every other operator
+    Operator,
+    Comment,
+    // Anything we do not understand, ie: an unterminated string
+    Unknown,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+}
+
+// Operators, longest first so that `//=` is not read as `//` then `=`
+const OPERATORS: [&str; 23] = [
+    "?//", "//=", "|=", "+=", "-=", "*=", "/=", "%=", "==", "!=", "<=", ">=", "//",
+    "|", ",", ";", ":", "=", "<", ">", "+", "-", "*",
+];
+const SINGLE_OPERATORS: [char; 3] = ['/', '%', '?'];
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while let Some(token) = next_token(source, position) {
+        position = token.end;
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn next_token(source: &str, from: usize) -> Option<Token> {
+    let rest = &source[from..];
+    let skipped = rest.len() - rest.trim_start().len();
+    let start = from + skipped;
+    let rest = &source[start..];
+    let c = rest.chars().next()?;
+
+    let (kind, length) = if c == '#' {
+        (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+    } else if c == '"' {
+        match scan_string(source, start) {
+            Some(end) => (TokenKind::Str, end - start),
+            None => (TokenKind::Unknown, rest.len()),
+        }
+    } else if rest.starts_with("..") {
+        (TokenKind::Recurse, 2)
+    } else if c == '.' {
+        let name = ident_length(&rest[1..]);
+        if name > 0 {
+            (TokenKind::Field, 1 + name)
+        } else if rest[1..].starts_with('"') {
+            // ."quoted field"
+            match scan_string(source, start + 1) {
+                Some(end) => (TokenKind::Field, end - start),
+                None => (TokenKind::Unknown, rest.len()),
+            }
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            // .5 is a number
+            (TokenKind::Number, number_length(rest))
+        } else {
+            (TokenKind::Dot, 1)
+        }
+    } else if c == '$' {
+        (TokenKind::Variable, 1 + ident_length(&rest[1..]))
+    } else if c == '@' {
+        (TokenKind::Format, 1 + ident_length(&rest[1..]))
+    } else if c.is_ascii_digit() {
+        (TokenKind::Number, number_length(rest))
+    } else if is_ident_start(c) {
+        (TokenKind::Ident, ident_length(rest))
+    } else if matches!(c, '(' | '[' | '{') {
+        (TokenKind::Open, 1)
+    } else if matches!(c, ')' | ']' | '}') {
+        (TokenKind::Close, 1)
+    } else if let Some(operator) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+        (TokenKind::Operator, operator.len())
+    } else if SINGLE_OPERATORS.contains(&c) {
+        (TokenKind::Operator, 1)
+    } else {
+        (TokenKind::Unknown, c.len_utf8())
+    };
+
+    Some(Token {
+        kind,
+        start,
+        end: start + length,
+    })
+}
+
+// Identifier, with optional namespaces (ie: foo::bar)
+fn ident_length(text: &str) -> usize {
+    let mut length = 0;
+    loop {
+        let rest = &text[length..];
+        if !rest.starts_with(is_ident_start) {
+            return length;
+        }
+        length += rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+        if text[length..].starts_with("::") && text[length + 2..].starts_with(is_ident_start) {
+            length += 2;
+        } else {
+            return length;
+        }
+    }
+}
+
+fn number_length(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut length = 0;
+    while length < bytes.len() && (bytes[length].is_ascii_digit() || bytes[length] == b'.') {
+        length += 1;
+    }
+    if length < bytes.len() && (bytes[length] == b'e' || bytes[length] == b'E') {
+        let mut exponent = length + 1;
+        if exponent < bytes.len() && (bytes[exponent] == b'+' || bytes[exponent] == b'-') {
+            exponent += 1;
+        }
+        if exponent < bytes.len() && bytes[exponent].is_ascii_digit() {
+            length = exponent;
+            while length < bytes.len() && bytes[length].is_ascii_digit() {
+                length += 1;
+            }
+        }
+    }
+    length
+}
+
+// Return the index right after the closing quote of the string starting
+// at `start`, following \(...) interpolations which can contain strings
+fn scan_string(source: &str, start: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut position = start + 1;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'"' => return Some(position + 1),
+            b'\\' if bytes.get(position + 1) == Some(&b'(') => {
+                position = scan_interpolation(source, position + 2)?;
+            }
+            b'\\' => position += 2,
+            _ => position += 1,
+        }
+    }
+    None
+}
+
+// Return the index right after the `)` closing an interpolation
+fn scan_interpolation(source: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut position = start;
+    loop {
+        let token = next_token(source, position)?;
+        position = token.end;
+        match token.kind {
+            TokenKind::Unknown => return None,
+            TokenKind::Open => depth += 1,
+            TokenKind::Close if depth == 0 => return Some(position),
+            TokenKind::Close => depth -= 1,
+            _ => {}
+        }
+    }
+}
+
+// Split the leading `module`, `import` and `include` directives from the
+// body of the query, they must stay in front when the body is wrapped
+// (ie: in `path(...)`)
+pub fn split_directives(source: &str) -> (&str, &str) {
+    let mut end = 0;
+    let mut in_directive = false;
+    for token in tokenize(source) {
+        let text = token.text(source);
+        match token.kind {
+            TokenKind::Comment => {}
+            TokenKind::Operator if in_directive && text == ";" => {
+                in_directive = false;
+                end = token.end;
+            }
+            _ if in_directive => {}
+            TokenKind::Ident if matches!(text, "module" | "import" | "include") => {
+                in_directive = true;
+            }
+            _ => break,
+        }
+    }
+    (&source[..end], &source[end..])
+}
+
+// Split the query at its top level pipes, ie: the ones which are not
+// inside brackets, strings, `if ... end` blocks or function definitions.
+// The pipe following a `... as $x` or `label $x` binding belongs to the
+// binding and is not a stage boundary, the leading directives are skipped
+// for the same reason (ie: `import "a" as a;`).
+// Returns the byte offset of each boundary pipe.
+pub fn pipe_boundaries(source: &str) -> Vec<usize> {
+    #[derive(PartialEq)]
+    enum Block {
+        Bracket,
+        If,
+        Def,
+    }
+
+    let mut boundaries = Vec::new();
+    let mut stack: Vec<Block> = Vec::new();
+    let mut binding = false;
+    let mut reduce = false;
+
+    let (directives, body) = split_directives(source);
+    for token in tokenize(body) {
+        let text = token.text(body);
+        match token.kind {
+            TokenKind::Open => stack.push(Block::Bracket),
+            TokenKind::Close => {
+                // Unbalanced `if` or `def` inside brackets are closed with them
+                while let Some(block) = stack.pop() {
+                    if block == Block::Bracket {
+                        break;
+                    }
+                }
+            }
+            TokenKind::Ident => match text {
+                "if" => stack.push(Block::If),
+                "end" if stack.last() == Some(&Block::If) => {
+                    stack.pop();
+                }
+                "def" => stack.push(Block::Def),
+                // The `as` of reduce/foreach is followed by its arguments, not a pipe
+                "reduce" | "foreach" if stack.is_empty() => reduce = true,
+                "as" if stack.is_empty() => {
+                    if reduce {
+                        reduce = false;
+                    } else {
+                        binding = true;
+                    }
+                }
+                "label" if stack.is_empty() => binding = true,
+                _ => {}
+            },
+            TokenKind::Operator if text == ";" && stack.last() == Some(&Block::Def) => {
+                stack.pop();
+            }
+            TokenKind::Operator if text == "|" && stack.is_empty() => {
+                if binding {
+                    binding = false;
+                } else {
+                    boundaries.push(directives.len() + token.start);
+                }
+            }
+            _ => {}
+        }
+    }
+    boundaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(source)
+            .into_iter()
+            .map(|token| (token.kind, token.text(source)))
+            .collect()
+    }
+
+    // The stages of a query, as split by its boundary pipes
+    fn stages(source: &str) -> Vec<&str> {
+        let mut stages = Vec::new();
+        let mut start = 0;
+        for boundary in pipe_boundaries(source) {
+            stages.push(source[start..boundary].trim());
+            start = boundary + 1;
+        }
+        stages.push(source[start..].trim());
+        stages
+    }
+
+    #[test]
+    fn tokenize_kinds() {
+        assert_eq!(
+            kinds(r#".a | .["b"] // $x, @csv "s\(.c)" .5 1e3 .. k8s::pods # end"#),
+            vec![
+                (TokenKind::Field, ".a"),
+                (TokenKind::Operator, "|"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Open, "["),
+                (TokenKind::Str, r#""b""#),
+                (TokenKind::Close, "]"),
+                (TokenKind::Operator, "//"),
+                (TokenKind::Variable, "$x"),
+                (TokenKind::Operator, ","),
+                (TokenKind::Format, "@csv"),
+                (TokenKind::Str, r#""s\(.c)""#),
+                (TokenKind::Number, ".5"),
+                (TokenKind::Number, "1e3"),
+                (TokenKind::Recurse, ".."),
+                (TokenKind::Ident, "k8s::pods"),
+                (TokenKind::Comment, "# end"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_operators_longest_first() {
+        assert_eq!(
+            kinds(".a //= 1 |= ?// ?"),
+            vec![
+                (TokenKind::Field, ".a"),
+                (TokenKind::Operator, "//="),
+                (TokenKind::Number, "1"),
+                (TokenKind::Operator, "|="),
+                (TokenKind::Operator, "?//"),
+                (TokenKind::Operator, "?"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_strings() {
+        // A pipe inside an interpolation stays in the string
+        assert_eq!(kinds(r#""a \(.b | .c) \" d""#), vec![(TokenKind::Str, r#""a \(.b | .c) \" d""#)]);
+        assert_eq!(kinds(r#"."quoted key""#), vec![(TokenKind::Field, r#"."quoted key""#)]);
+        assert_eq!(kinds(r#""unterminated"#)[0].0, TokenKind::Unknown);
+    }
+
+    #[test]
+    fn split_directives_of_queries() {
+        assert_eq!(
+            split_directives(r#"import "k8s" as k8s; include "x"; .a"#),
+            (r#"import "k8s" as k8s; include "x";"#, " .a")
+        );
+        assert_eq!(split_directives("# comment\nmodule {};.a"), ("# comment\nmodule {};", ".a"));
+        assert_eq!(split_directives(".a | .b"), ("", ".a | .b"));
+    }
+
+    #[test]
+    fn pipe_boundaries_at_top_level() {
+        assert_eq!(stages(".a | .b | length"), vec![".a", ".b", "length"]);
+        assert_eq!(stages(".a | (.b | .c) | [.[] | .d]"), vec![".a", "(.b | .c)", "[.[] | .d]"]);
+        assert_eq!(stages(r#".a | "\(.b | .c)""#), vec![".a", r#""\(.b | .c)""#]);
+        assert_eq!(stages(".a # x | y\n| .b"), vec![".a # x | y", ".b"]);
+        assert_eq!(stages("."), vec!["."]);
+    }
+
+    #[test]
+    fn pipe_boundaries_of_blocks() {
+        assert_eq!(
+            stages("if .a then .b | .c else .d end | .e"),
+            vec!["if .a then .b | .c else .d end", ".e"]
+        );
+        assert_eq!(
+            stages("def f: .a | .b; f | .c"),
+            vec!["def f: .a | .b; f", ".c"]
+        );
+    }
+
+    #[test]
+    fn pipe_boundaries_of_bindings() {
+        assert_eq!(stages(".a as $x | $x | .b"), vec![".a as $x | $x", ".b"]);
+        assert_eq!(stages("label $out | .a | .b"), vec!["label $out | .a", ".b"]);
+        assert_eq!(
+            stages("reduce .[] as $x (0; . + $x) | . * 2"),
+            vec!["reduce .[] as $x (0; . + $x)", ". * 2"]
+        );
+    }
+
+    #[test]
+    fn pipe_boundaries_after_directives() {
+        let query = r#"import "k8s" as k8s; .items | map(.x) | length"#;
+        assert_eq!(pipe_boundaries(query), vec![28, 38]);
+        assert_eq!(stages(query), vec![r#"import "k8s" as k8s; .items"#, "map(.x)", "length"]);
+    }
+}
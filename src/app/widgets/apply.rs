@@ -0,0 +1,126 @@
+// This widget is a popup confirming that the result is written to a file,
+// with the diff between the current file and the result below the
+// destination. The content is kept as confirmed, a later run of the query
+// doesn't change it.
+// Up/Down/PgUp/PgDn and n/N move in the diff, Enter writes, Esc cancels.
+
+use ratatui::{
+    widgets::{Block, Borders, Clear, Paragraph},
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    style::Style,
+    backend::Backend,
+};
+
+use crossterm::event;
+
+use std::path::PathBuf;
+
+use crate::app::widgets::{centered_rect, Diff, Drawable};
+use crate::app::Config;
+
+pub enum ApplyEvent {
+    None,
+    Cancel,
+    Confirm,
+}
+
+pub struct ApplyView<'a> {
+    config: &'a Config,
+    path: PathBuf,
+    content: String,
+    // The destination exists and is replaced
+    replace: bool,
+    diff: Diff<'a>,
+}
+
+impl<'a> ApplyView<'a> {
+    pub fn new(path: PathBuf, content: String, config: &'a Config) -> Result<ApplyView<'a>, std::io::Error> {
+        let mut diff = Diff::new(config);
+        let replace = path.exists();
+        if replace {
+            diff.set_values(&std::fs::read_to_string(&path)?, &content);
+        } else {
+            diff.set_added(&content);
+        }
+        diff.set_title(format!("Diff: {} → JQ Output", path.display()));
+        diff.set_selected(true);
+        Ok(ApplyView { config, path, content, replace, diff })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    // The bytes to write, as shown in the diff
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> ApplyEvent {
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Esc,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => ApplyEvent::Cancel,
+            event::KeyEvent {
+                code: event::KeyCode::Enter,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => ApplyEvent::Confirm,
+            _ => {
+                self.diff.handle_event(event);
+                ApplyEvent::None
+            },
+        }
+    }
+}
+
+impl Drawable for ApplyView<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+
+        let popup = centered_rect(80, 80, area);
+        f.render_widget(Clear, popup);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(3),
+                Constraint::Min(0),
+            ])
+            .split(popup);
+
+        let action = if self.replace {
+            "Replace"
+        } else {
+            "Create"
+        };
+        let paragraph = Paragraph::new(Line::from(vec![
+                Span::styled(format!("{} ", action), Style::default().fg(fg_color).bg(bg_color)),
+                Span::styled(
+                    self.path.display().to_string(),
+                    Style::default().fg(self.config.json.json_key).bg(bg_color)
+                ),
+                Span::styled(" with the result?", Style::default().fg(fg_color).bg(bg_color)),
+            ]))
+            .block(Block::default()
+                .title(Span::styled(
+                    "Apply the query · Enter writes, Esc cancels",
+                    Style::default().fg(self.config.color.selected_foreground).bg(bg_color)
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            )
+            .style(Style::default().fg(fg_color).bg(bg_color));
+        f.render_widget(paragraph, layout[0]);
+        self.diff.draw(f, layout[1])
+    }
+}
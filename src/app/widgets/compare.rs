@@ -0,0 +1,361 @@
+// This widget compares the query results of two input files side by side.
+// Both sides are aligned line by line, unchanged subtrees are collapsed
+// and differences are coloured. n/N jump to the next/previous difference.
+
+use ratatui::{
+    widgets::{
+        Block,
+        Borders,
+        Paragraph,
+        block::{
+            Title,
+            title::Position
+        }
+    },
+    text::{Line, Span, Text},
+    style::{Modifier, Style},
+    backend::Backend,
+    layout::Rect,
+    prelude::Alignment,
+};
+
+use crossterm::event;
+
+use std::cell::Cell;
+
+use serde_json::Value;
+
+use crate::app::diff::{diff, same};
+use crate::app::Config;
+
+// Lines kept visible above and below the cursor, like the Json widget
+const SCROLL_OFF: usize = 3;
+
+pub struct Compare<'a> {
+    config: &'a Config,
+    // The second input file
+    path: String,
+    left: Vec<Line<'a>>,
+    right: Vec<Line<'a>>,
+    // Lines where a difference starts
+    changes: Vec<usize>,
+    change_count: usize,
+    cursor: usize,
+    // First line shown, the view follows the cursor
+    offset: Cell<usize>,
+    // Lines shown by the last draw, the size of a page
+    height: Cell<usize>,
+    selected: bool,
+}
+
+impl<'a> Compare<'a> {
+    pub fn new(path: String, config: &'a Config) -> Compare<'a> {
+        Compare {
+            config,
+            path,
+            left: Vec::new(),
+            right: Vec::new(),
+            changes: Vec::new(),
+            change_count: 0,
+            cursor: 0,
+            offset: Cell::new(0),
+            height: Cell::new(1),
+            selected: false,
+        }
+    }
+
+    pub fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
+
+    pub fn selected(&self) -> bool {
+        self.selected
+    }
+
+    // Compare the results of the query on both files
+    pub fn set_results(&mut self, left: &str, right: &str) {
+        self.left.clear();
+        self.right.clear();
+        self.changes.clear();
+        self.change_count = 0;
+        self.cursor = 0;
+        self.offset.set(0);
+
+        let values = (
+            serde_json::from_str::<Value>(left),
+            serde_json::from_str::<Value>(right),
+        );
+        match values {
+            (Ok(left), Ok(right)) => {
+                self.render("", Some(&left), Some(&right), 0);
+                self.change_count = diff(&left, &right).len();
+            },
+            (left_value, right_value) => {
+                // Show the raw output (ie: an error) of both sides
+                for (is_value, raw, lines) in [
+                    (left_value.is_ok(), left, &mut self.left),
+                    (right_value.is_ok(), right, &mut self.right),
+                ] {
+                    let style = Style::default()
+                        .fg(
+                            if is_value {self.config.color.foreground}
+                            else {self.config.color.invalid_foreground}
+                        )
+                        .bg(self.config.color.background);
+                    lines.extend(raw.lines().map(|line| {
+                        Line::from(Span::styled(line.to_string(), style))
+                    }));
+                }
+                let count = std::cmp::max(self.left.len(), self.right.len());
+                self.left.resize(count, Line::from(""));
+                self.right.resize(count, Line::from(""));
+            },
+        }
+    }
+
+    fn push_line(&mut self, left: Option<(&str, String, Style)>, right: Option<(&str, String, Style)>, indent: usize, is_change: bool) {
+        if is_change {
+            self.changes.push(self.left.len());
+        }
+        let key_style = Style::default()
+            .fg(self.config.json.json_key)
+            .bg(self.config.color.background);
+        for (side, lines) in [(left, &mut self.left), (right, &mut self.right)] {
+            lines.push(match side {
+                Some((key, text, style)) => Line::from(vec![
+                    Span::styled(format!("{:indent$}", "", indent = indent), style),
+                    Span::styled(key.to_string(), if is_change {style} else {key_style}),
+                    Span::styled(text, style),
+                ]),
+                None => Line::from(""),
+            });
+        }
+    }
+
+    fn render(&mut self, key: &str, a: Option<&Value>, b: Option<&Value>, indent: usize) {
+        let style_default = Style::default()
+            .fg(self.config.color.foreground)
+            .bg(self.config.color.background);
+        let style_added = style_default.fg(self.config.color.diff_added_foreground);
+        let style_removed = style_default.fg(self.config.color.diff_removed_foreground);
+        let style_modified = style_default.fg(self.config.color.diff_modified_foreground);
+        let indent_increment = self.config.json.indent;
+
+        match (a, b) {
+            (Some(a), Some(b)) if same(a, b) => {
+                let text = match a {
+                    Value::Object(o) if !o.is_empty() => format!("{{…}} ({} keys)", o.len()),
+                    Value::Array(v) if !v.is_empty() => format!("[…] ({} items)", v.len()),
+                    other => other.to_string(),
+                };
+                let style = style_default.add_modifier(Modifier::DIM);
+                self.push_line(Some((key, text.clone(), style)), Some((key, text, style)), indent, false);
+            },
+            (Some(Value::Object(a)), Some(Value::Object(b))) => {
+                self.push_line(
+                    Some((key, "{".to_string(), style_default)),
+                    Some((key, "{".to_string(), style_default)),
+                    indent,
+                    false
+                );
+                for (k, a_value) in a {
+                    let child_key = format!("{}: ", Value::String(k.clone()));
+                    self.render(&child_key, Some(a_value), b.get(k), indent + indent_increment);
+                }
+                for (k, b_value) in b {
+                    if !a.contains_key(k) {
+                        let child_key = format!("{}: ", Value::String(k.clone()));
+                        self.render(&child_key, None, Some(b_value), indent + indent_increment);
+                    }
+                }
+                self.push_line(
+                    Some(("", "}".to_string(), style_default)),
+                    Some(("", "}".to_string(), style_default)),
+                    indent,
+                    false
+                );
+            },
+            (Some(Value::Array(a)), Some(Value::Array(b))) => {
+                self.push_line(
+                    Some((key, "[".to_string(), style_default)),
+                    Some((key, "[".to_string(), style_default)),
+                    indent,
+                    false
+                );
+                for i in 0..std::cmp::max(a.len(), b.len()) {
+                    self.render("", a.get(i), b.get(i), indent + indent_increment);
+                }
+                self.push_line(
+                    Some(("", "]".to_string(), style_default)),
+                    Some(("", "]".to_string(), style_default)),
+                    indent,
+                    false
+                );
+            },
+            (Some(a), Some(b)) => {
+                self.push_line(
+                    Some((key, a.to_string(), style_modified)),
+                    Some((key, b.to_string(), style_modified)),
+                    indent,
+                    true
+                );
+            },
+            (Some(a), None) => {
+                self.push_line(Some((key, a.to_string(), style_removed)), None, indent, true);
+            },
+            (None, Some(b)) => {
+                self.push_line(None, Some((key, b.to_string(), style_added)), indent, true);
+            },
+            (None, None) => {},
+        }
+    }
+
+    // First line shown, with the cursor kept SCROLL_OFF lines away from the edges
+    fn window(&self, height: usize) -> usize {
+        let margin = std::cmp::min(SCROLL_OFF, height.saturating_sub(1) / 2);
+        let mut first = self.offset.get();
+        if self.cursor < first + margin {
+            first = self.cursor.saturating_sub(margin);
+        }
+        if self.cursor + margin >= first + height {
+            first = (self.cursor + margin + 1).saturating_sub(height);
+        }
+        first = std::cmp::min(first, self.left.len().saturating_sub(height));
+        self.offset.set(first);
+        self.height.set(height);
+        first
+    }
+
+    fn jump_to_change(&mut self, forward: bool) {
+        let next = if forward {
+            self.changes.iter().find(|line| **line > self.cursor).or(self.changes.first())
+        } else {
+            self.changes.iter().rev().find(|line| **line < self.cursor).or(self.changes.last())
+        };
+        if let Some(line) = next {
+            self.cursor = *line;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &event::KeyEvent) {
+        let last_line = self.left.len().saturating_sub(1);
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = self.cursor.saturating_sub(1);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = std::cmp::min(self.cursor + 1, last_line);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageUp,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = self.cursor.saturating_sub(self.height.get());
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageDown,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = std::cmp::min(self.cursor + self.height.get(), last_line);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Char('n'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.jump_to_change(true);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Char('N'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.jump_to_change(false);
+            },
+            _ => {}
+        }
+    }
+
+    // Draw both sides, `left_title` names the first input file
+    pub fn draw_pair<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            left_area: Rect,
+            right_area: Rect,
+            left_title: &str,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+        let selected_fg_color = self.config.color.selected_foreground;
+        let title_style = Style::default()
+            .fg(if self.selected {selected_fg_color} else {fg_color})
+            .bg(bg_color);
+        let cursor_style = Style::default()
+            .fg(
+                if self.selected {
+                    self.config.color.json_cursor_foreground
+                } else {
+                    fg_color
+                }
+            )
+            .bg(bg_color);
+
+        // Both sides scroll together, with the same chunked window as the Json widget
+        let height = std::cmp::max(left_area.height.saturating_sub(2) as usize, 1);
+        let first_line = self.window(height);
+        let last_line = std::cmp::min(first_line + height, self.left.len());
+
+        let changes_info = format!("{} differences", self.change_count);
+        let cursor_info = format!("{} / {}", self.cursor + 1, self.left.len());
+
+        for (lines, area, title) in [
+            (&self.left, left_area, left_title),
+            (&self.right, right_area, self.path.as_str()),
+        ] {
+            let mut content = lines[first_line..last_line].to_vec();
+            for (i, line) in content.iter_mut().enumerate() {
+                line.spans.insert(
+                    0,
+                    Span::styled(
+                        if first_line + i == self.cursor {"▷ "} else {"  "},
+                        cursor_style
+                    )
+                );
+            }
+            let paragraph = Paragraph::new(Text::from(content))
+                .block(Block::default()
+                    .title(Span::styled(title, title_style))
+                    .title(
+                        Title::from(Span::styled(changes_info.as_str(), title_style))
+                            .alignment(Alignment::Right)
+                    )
+                    .title(
+                        Title::from(Span::styled(cursor_info.as_str(), title_style))
+                            .alignment(Alignment::Right)
+                            .position(Position::Bottom)
+                    )
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(fg_color).bg(bg_color))
+                )
+                .style(Style::default().fg(fg_color).bg(bg_color));
+            f.render_widget(paragraph, area);
+        }
+        Ok(())
+    }
+}
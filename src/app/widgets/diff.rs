@@ -0,0 +1,461 @@
+// This widget displays the structural diff between the base document
+// and the query result.
+// Unchanged subtrees are collapsed, added, removed and modified nodes
+// are coloured. n/N jump to the next/previous change.
+
+use ratatui::{
+    widgets::{
+        Block,
+        Borders,
+        Paragraph,
+        block::{
+            Title,
+            title::Position
+        }
+    },
+    text::{Line, Span, Text},
+    style::{Modifier, Style},
+    backend::Backend,
+    prelude::Alignment,
+};
+
+use crossterm::event;
+
+use std::cell::Cell;
+
+use serde_json::Value;
+
+use crate::app::diff::{diff, json_patch, same, Change};
+use crate::app::widgets::Drawable;
+use crate::app::Config;
+
+// Lines kept visible above and below the cursor, like the Json widget
+const SCROLL_OFF: usize = 3;
+
+pub struct Diff<'a> {
+    config: &'a Config,
+    title: String,
+    lines: Vec<Line<'a>>,
+    // Lines where a change starts
+    changes: Vec<usize>,
+    patch: Option<Value>,
+    // The parsed base document, None if it is not a single JSON value.
+    // Kept between the results: it only changes when the input is edited.
+    base: Option<Value>,
+    // Line of the cursor, see cursor()
+    cursor: usize,
+    // First line shown, the view follows the cursor
+    offset: Cell<usize>,
+    // Lines shown by the last draw, the size of a page
+    height: Cell<usize>,
+    selected: bool,
+}
+
+impl<'a> Diff<'a> {
+    pub fn new(config: &'a Config) -> Diff<'a> {
+        Diff {
+            config,
+            title: "Diff: JSON File → JQ Output".to_string(),
+            lines: Vec::new(),
+            changes: Vec::new(),
+            patch: None,
+            base: None,
+            cursor: 0,
+            offset: Cell::new(0),
+            height: Cell::new(1),
+            selected: false,
+        }
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    pub fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
+
+    pub fn selected(&self) -> bool {
+        self.selected
+    }
+
+    // The changes as a JSON Patch document, None if the diff is not available
+    pub fn patch(&self) -> Option<&Value> {
+        self.patch.as_ref()
+    }
+
+    pub fn set_values(&mut self, base: &str, filtered: &str) {
+        self.set_base(base);
+        self.set_filtered(filtered);
+    }
+
+    // The base is parsed once here, set_filtered() diffs each result with it
+    pub fn set_base(&mut self, base: &str) {
+        self.base = serde_json::from_str::<Value>(base).ok();
+    }
+
+    // Diff a new result with the base. The cursor stays on the same line,
+    // a shorter diff (ie: an error while typing the query) only clamps it.
+    pub fn set_filtered(&mut self, filtered: &str) {
+        self.lines.clear();
+        self.changes.clear();
+        self.patch = None;
+
+        let base = self.base.take();
+        match (&base, serde_json::from_str::<Value>(filtered)) {
+            (Some(base), Ok(filtered)) => {
+                self.render("", Some(base), Some(&filtered), 0);
+                self.patch = Some(json_patch(&diff(base, &filtered)));
+            },
+            (None, _) => {
+                self.message("The input is not a single JSON value");
+            },
+            (_, Err(_)) => {
+                self.message("The result is not a single JSON value");
+            },
+        }
+        self.base = base;
+    }
+
+    // Diff of a value with nothing, it is added as a whole
+    pub fn set_added(&mut self, filtered: &str) {
+        self.lines.clear();
+        self.changes.clear();
+        self.patch = None;
+        self.cursor = 0;
+        self.offset.set(0);
+
+        match serde_json::from_str::<Value>(filtered) {
+            Ok(filtered) => {
+                self.render("", None, Some(&filtered), 0);
+                self.patch = Some(json_patch(&[Change::Add { path: Vec::new(), value: filtered }]));
+            },
+            Err(_) => {
+                self.message("The result is not a single JSON value");
+            },
+        }
+    }
+
+    fn message(&mut self, message: &str) {
+        self.lines.push(Line::from(Span::styled(
+            message.to_string(),
+            Style::default()
+                .fg(self.config.color.invalid_foreground)
+                .bg(self.config.color.background)
+        )));
+    }
+
+    fn push_line(&mut self, sign: &str, indent: usize, key: &str, text: String, style: Style, is_change: bool) {
+        if is_change {
+            self.changes.push(self.lines.len());
+        }
+        let key_style = if sign == " " {
+            Style::default()
+                .fg(self.config.json.json_key)
+                .bg(self.config.color.background)
+        } else {
+            style
+        };
+        self.lines.push(Line::from(vec![
+            Span::styled(format!("{} {:indent$}", sign, "", indent = indent), style),
+            Span::styled(key.to_string(), key_style),
+            Span::styled(text, style),
+        ]));
+    }
+
+    fn render(&mut self, key: &str, a: Option<&Value>, b: Option<&Value>, indent: usize) {
+        let style_default = Style::default()
+            .fg(self.config.color.foreground)
+            .bg(self.config.color.background);
+        let style_added = style_default.fg(self.config.color.diff_added_foreground);
+        let style_removed = style_default.fg(self.config.color.diff_removed_foreground);
+        let style_modified = style_default.fg(self.config.color.diff_modified_foreground);
+        let indent_increment = self.config.json.indent;
+
+        match (a, b) {
+            (Some(a), Some(b)) if same(a, b) => {
+                // Unchanged, containers are collapsed
+                let text = match a {
+                    Value::Object(o) if !o.is_empty() => format!("{{…}} ({} keys)", o.len()),
+                    Value::Array(v) if !v.is_empty() => format!("[…] ({} items)", v.len()),
+                    other => other.to_string(),
+                };
+                self.push_line(" ", indent, key, text, style_default.add_modifier(Modifier::DIM), false);
+            },
+            (Some(Value::Object(a)), Some(Value::Object(b))) => {
+                self.push_line(" ", indent, key, "{".to_string(), style_default, false);
+                for (k, a_value) in a {
+                    let child_key = format!("{}: ", Value::String(k.clone()));
+                    self.render(&child_key, Some(a_value), b.get(k), indent + indent_increment);
+                }
+                for (k, b_value) in b {
+                    if !a.contains_key(k) {
+                        let child_key = format!("{}: ", Value::String(k.clone()));
+                        self.render(&child_key, None, Some(b_value), indent + indent_increment);
+                    }
+                }
+                self.push_line(" ", indent, "", "}".to_string(), style_default, false);
+            },
+            (Some(Value::Array(a)), Some(Value::Array(b))) => {
+                self.push_line(" ", indent, key, "[".to_string(), style_default, false);
+                for i in 0..std::cmp::max(a.len(), b.len()) {
+                    self.render("", a.get(i), b.get(i), indent + indent_increment);
+                }
+                self.push_line(" ", indent, "", "]".to_string(), style_default, false);
+            },
+            (Some(a), Some(b)) => {
+                self.push_line("~", indent, key, format!("{} → {}", a, b), style_modified, true);
+            },
+            (Some(a), None) => {
+                self.push_line("-", indent, key, a.to_string(), style_removed, true);
+            },
+            (None, Some(b)) => {
+                self.push_line("+", indent, key, b.to_string(), style_added, true);
+            },
+            (None, None) => {},
+        }
+    }
+
+    // The cursor line, within the lines of the current diff
+    fn cursor(&self) -> usize {
+        std::cmp::min(self.cursor, self.lines.len().saturating_sub(1))
+    }
+
+    // First line shown, with the cursor kept SCROLL_OFF lines away from the edges
+    fn window(&self, height: usize) -> usize {
+        let cursor = self.cursor();
+        let margin = std::cmp::min(SCROLL_OFF, height.saturating_sub(1) / 2);
+        let mut first = self.offset.get();
+        if cursor < first + margin {
+            first = cursor.saturating_sub(margin);
+        }
+        if cursor + margin >= first + height {
+            first = (cursor + margin + 1).saturating_sub(height);
+        }
+        first = std::cmp::min(first, self.lines.len().saturating_sub(height));
+        self.offset.set(first);
+        self.height.set(height);
+        first
+    }
+
+    fn jump_to_change(&mut self, forward: bool) {
+        let cursor = self.cursor();
+        let next = if forward {
+            self.changes.iter().find(|line| **line > cursor).or(self.changes.first())
+        } else {
+            self.changes.iter().rev().find(|line| **line < cursor).or(self.changes.last())
+        };
+        if let Some(line) = next {
+            self.cursor = *line;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &event::KeyEvent) {
+        let last_line = self.lines.len().saturating_sub(1);
+        let cursor = self.cursor();
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = cursor.saturating_sub(1);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = std::cmp::min(cursor + 1, last_line);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageUp,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = cursor.saturating_sub(self.height.get());
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageDown,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.cursor = std::cmp::min(cursor + self.height.get(), last_line);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Char('n'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.jump_to_change(true);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Char('N'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.jump_to_change(false);
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Drawable for Diff<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+        let selected_fg_color = self.config.color.selected_foreground;
+        let title_style = Style::default()
+            .fg(if self.selected {selected_fg_color} else {fg_color})
+            .bg(bg_color);
+        let cursor_style = Style::default()
+            .fg(
+                if self.selected {
+                    self.config.color.json_cursor_foreground
+                } else {
+                    fg_color
+                }
+            )
+            .bg(bg_color);
+
+        let height = std::cmp::max(area.height.saturating_sub(2) as usize, 1);
+        let first_line = self.window(height);
+        let last_line = std::cmp::min(first_line + height, self.lines.len());
+        let mut content = self.lines[first_line..last_line].to_vec();
+        for (i, line) in content.iter_mut().enumerate() {
+            line.spans.insert(
+                0,
+                Span::styled(
+                    if first_line + i == self.cursor() {"▷ "} else {"  "},
+                    cursor_style
+                )
+            );
+        }
+
+        let change_count = self.patch
+            .as_ref()
+            .and_then(|patch| patch.as_array())
+            .map_or(0, |patch| patch.len());
+        let changes_info = format!("{} changes", change_count);
+        let cursor_info = format!("{} / {}", self.cursor() + 1, self.lines.len());
+
+        let paragraph = Paragraph::new(Text::from(content))
+            .block(Block::default()
+                .title(Span::styled(self.title.as_str(), title_style))
+                .title(
+                    Title::from(Span::styled(changes_info.as_str(), title_style))
+                        .alignment(Alignment::Right)
+                )
+                .title(
+                    Title::from(Span::styled(cursor_info.as_str(), title_style))
+                        .alignment(Alignment::Right)
+                        .position(Position::Bottom)
+                )
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            )
+            .style(Style::default().fg(fg_color).bg(bg_color));
+        f.render_widget(paragraph, area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: event::KeyCode) -> event::KeyEvent {
+        event::KeyEvent::new(code, event::KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn window_of_few_lines() {
+        let config = Config::new();
+        let mut diff = Diff::new(&config);
+        for (base, filtered) in [("1", "1"), ("1", "2"), ("[1]", "[1, 2]"), ("1", "[")] {
+            diff.set_values(base, filtered);
+            for height in [1, 2, 5, 20] {
+                for code in [event::KeyCode::PageDown, event::KeyCode::Down, event::KeyCode::PageUp] {
+                    let first = diff.window(height);
+                    assert!(first <= diff.cursor && diff.cursor < first + height);
+                    diff.handle_event(&key(code));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn window_keeps_lines_around_cursor() {
+        let config = Config::new();
+        let mut diff = Diff::new(&config);
+        let base = serde_json::to_string(&(0..100).collect::<Vec<u32>>()).unwrap();
+        diff.set_values(&base, &base.replace("[0,", "[1,"));
+        assert_eq!(diff.window(20), 0);
+        for _ in 0..17 {
+            diff.handle_event(&key(event::KeyCode::Down));
+        }
+        assert_eq!(diff.window(20), 1);
+        // A page is the height of the last draw
+        diff.handle_event(&key(event::KeyCode::PageDown));
+        assert_eq!(diff.cursor, 37);
+        assert_eq!(diff.window(20), 21);
+        diff.handle_event(&key(event::KeyCode::PageUp));
+        assert_eq!(diff.window(20), 14);
+    }
+
+    #[test]
+    fn cursor_kept_on_new_results() {
+        let config = Config::new();
+        let mut diff = Diff::new(&config);
+        let base = serde_json::to_string(&(0..100).collect::<Vec<u32>>()).unwrap();
+        diff.set_base(&base);
+        diff.set_filtered(&base.replace("[0,", "[1,"));
+        for _ in 0..40 {
+            diff.handle_event(&key(event::KeyCode::Down));
+        }
+        diff.window(20);
+        // Typing the query gives new results, diffed with the same base
+        diff.set_filtered(&base.replace(",99]", ",98]"));
+        assert_eq!(diff.cursor(), 40);
+        assert_eq!(diff.window(20), 24);
+        assert_eq!(diff.changes, vec![100]);
+        diff.set_filtered("[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+        assert_eq!(diff.lines.len(), 102);
+        assert_eq!(diff.cursor(), 40);
+        // A shorter diff shows the cursor on its last line, an error while
+        // typing the query doesn't lose its position
+        diff.set_filtered(r#"{"a": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]}"#);
+        assert_eq!(diff.lines.len(), 1);
+        assert_eq!(diff.cursor(), 0);
+        assert_eq!(diff.window(20), 0);
+        diff.set_filtered("[");
+        assert_eq!(diff.cursor(), 0);
+        let result = base.replace(",99]", ",98]");
+        diff.set_filtered(&result);
+        assert_eq!(diff.cursor(), 40);
+        // Moving from the clamped line
+        diff.set_filtered("1");
+        diff.handle_event(&key(event::KeyCode::Down));
+        diff.set_filtered(&result);
+        assert_eq!(diff.cursor(), 0);
+        // A new base is parsed once
+        diff.set_base("[1]");
+        diff.set_filtered("[1]");
+        assert!(diff.changes.is_empty());
+        diff.set_base("{");
+        diff.set_filtered("[1]");
+        assert_eq!(diff.lines.len(), 1);
+        assert!(diff.patch().is_none());
+    }
+}
@@ -0,0 +1,153 @@
+// This widget is a popup listing every key binding, grouped by context.
+// Up/Down/PgUp/PgDn scroll, Esc, q, ? or F1 close it.
+
+use ratatui::{
+    widgets::{Block, Borders, Clear, Paragraph},
+    text::{Line, Span},
+    style::{Modifier, Style},
+    backend::Backend,
+};
+
+use crossterm::event;
+
+use crate::app::keymap::{Context, BINDINGS};
+use crate::app::widgets::{centered_rect, Drawable};
+use crate::app::Config;
+
+const CONTEXTS: [Context; 9] = [
+    Context::Global,
+    Context::Input,
+    Context::Json,
+    Context::Table,
+    Context::Edit,
+    Context::Pipeline,
+    Context::Diff,
+    Context::Compare,
+    Context::Popup,
+];
+
+pub struct Help<'a> {
+    config: &'a Config,
+    scroll: usize,
+}
+
+impl<'a> Help<'a> {
+    pub fn new(config: &'a Config) -> Help<'a> {
+        Help {
+            config,
+            scroll: 0,
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'a>> {
+        let bg_color = self.config.color.background;
+        let keys_width = BINDINGS
+            .iter()
+            .map(|binding| binding.keys.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for context in CONTEXTS {
+            if !lines.is_empty() {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(Span::styled(
+                context.title(),
+                Style::default()
+                    .fg(self.config.color.selected_foreground)
+                    .bg(bg_color)
+                    .add_modifier(Modifier::BOLD)
+            )));
+            for binding in BINDINGS.iter().filter(|binding| binding.context == context) {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {:<width$}  ", binding.keys, width = keys_width),
+                        Style::default().fg(self.config.json.json_key).bg(bg_color)
+                    ),
+                    Span::styled(
+                        binding.action,
+                        Style::default().fg(self.config.color.foreground).bg(bg_color)
+                    ),
+                ]));
+            }
+        }
+        lines
+    }
+
+    // Returns true when the popup must be closed
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> bool {
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Esc | event::KeyCode::F(1) | event::KeyCode::Char('q') | event::KeyCode::Char('?'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                return true;
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = self.scroll.saturating_sub(1);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = std::cmp::min(self.scroll + 1, self.lines().len().saturating_sub(1));
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageUp,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = self.scroll.saturating_sub(10);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageDown,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = std::cmp::min(self.scroll + 10, self.lines().len().saturating_sub(1));
+            },
+            _ => {}
+        }
+        false
+    }
+}
+
+impl Drawable for Help<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+
+        let popup = centered_rect(70, 80, area);
+        f.render_widget(Clear, popup);
+
+        let paragraph = Paragraph::new(self.lines())
+            .block(Block::default()
+                .title(Span::styled(
+                    "Key bindings",
+                    Style::default().fg(self.config.color.selected_foreground).bg(bg_color)
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            )
+            .style(Style::default().fg(fg_color).bg(bg_color))
+            .scroll((self.scroll as u16, 0));
+        f.render_widget(paragraph, popup);
+        Ok(())
+    }
+}
@@ -2,8 +2,9 @@ use std::fmt::Error;
 
 use ratatui::{
     text::{self, Span, Line},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, block::Title},
     style::Style, backend::Backend,
+    prelude::Alignment,
 };
 
 use crossterm::event;
@@ -14,10 +15,14 @@ use crate::app::app::Selected;
 
 pub struct Input<'a> {
     prompt: &'a str,
+    // Query language, shown after the prompt
+    mode: &'static str,
+    right_title: Option<String>,
     value: String,
     selected: bool,
     config: &'a Config,
     is_valid: bool,
+    // Byte index in `value`, always on a char boundary
     cursor_position: usize,
 }
 
@@ -29,6 +34,8 @@ impl<'a> Input<'a>{
         }
         Input::<'a> {
             prompt,
+            mode: "jq",
+            right_title: None,
             value: input,
             selected: false,
             config,
@@ -41,6 +48,40 @@ impl<'a> Input<'a>{
         &self.value
     }
 
+    pub fn set_value(&mut self, value: &str) {
+        self.value = value.to_string();
+        self.cursor_position = self.value.len();
+    }
+
+    // Insert text at the cursor
+    pub fn insert(&mut self, text: &str) {
+        self.value.insert_str(self.cursor_position, text);
+        self.cursor_position += text.len();
+    }
+
+    // Byte indexes of the chars before and after the cursor
+    fn previous_position(&self) -> usize {
+        self.value[..self.cursor_position]
+            .chars()
+            .next_back()
+            .map_or(0, |c| self.cursor_position - c.len_utf8())
+    }
+
+    fn next_position(&self) -> usize {
+        self.value[self.cursor_position..]
+            .chars()
+            .next()
+            .map_or(self.value.len(), |c| self.cursor_position + c.len_utf8())
+    }
+
+    pub fn set_mode(&mut self, mode: &'static str) {
+        self.mode = mode;
+    }
+
+    pub fn set_right_title(&mut self, right_title: Option<String>) {
+        self.right_title = right_title;
+    }
+
     pub fn set_selected(&mut self, selected: &Selected) {
         match selected {
             Selected::Input => {
@@ -59,6 +100,11 @@ impl<'a> Input<'a>{
     pub fn render_content(&self) -> Result<Line, Error> {
         // Render the content of the input
         // ie: Adding the cursor
+        // Programs loaded from a file can span several lines,
+        // newlines are shown as a visible marker to keep a single line
+        fn visible(text: &str) -> String {
+            text.replace('\n', "↵")
+        }
         let mut content: Vec<Span> = Vec::new();
         let text_style = Style::default()
             .fg(self.config.color.foreground)
@@ -80,7 +126,7 @@ impl<'a> Input<'a>{
             } else if self.cursor_position == self.value.len() {
                 content.push(
                     Span::styled(
-                        &self.value[..],
+                        visible(&self.value[..]),
                         text_style                
                     )
                 );
@@ -91,22 +137,23 @@ impl<'a> Input<'a>{
                     )
                 );
             } else {
+                let next_position = self.next_position();
                 content.push(
                     Span::styled(
-                        &self.value[..self.cursor_position],
+                        visible(&self.value[..self.cursor_position]),
                         text_style                
                     )
                 );
                 content.push(
                     Span::styled(
-                        &self.value[self.cursor_position..self.cursor_position+1],
+                        visible(&self.value[self.cursor_position..next_position]),
                         cursor_style                
                     )
                 );
-                if self.cursor_position < self.value.len() {
+                if next_position < self.value.len() {
                     content.push(
                         Span::styled(
-                            &self.value[self.cursor_position+1..],
+                            visible(&self.value[next_position..]),
                             text_style                
                         )
                     );
@@ -116,7 +163,7 @@ impl<'a> Input<'a>{
             // We don't want to show the cursor if the input is not selected
             content.push(
                 Span::styled(
-                    &self.value[..],
+                    visible(&self.value[..]),
                     text_style                
                 )
             );
@@ -137,8 +184,8 @@ impl<'a> Input<'a>{
                     state: _,
                 } => {
                     if self.cursor_position > 0 {
-                        self.value.remove(self.cursor_position-1);
-                        self.cursor_position -= 1;
+                        self.cursor_position = self.previous_position();
+                        self.value.remove(self.cursor_position);
                     }
                 }
                 event::KeyEvent{ // Handle delete
@@ -157,9 +204,7 @@ impl<'a> Input<'a>{
                     kind: _,
                     state: _,
                 } => {
-                    if self.cursor_position > 0 {
-                        self.cursor_position -= 1;
-                    }
+                    self.cursor_position = self.previous_position();
                 }
                 event::KeyEvent{ // Handle right arrow
                     code: event::KeyCode::Right,
@@ -167,9 +212,7 @@ impl<'a> Input<'a>{
                     kind: _,
                     state: _,
                 } => {
-                    if self.cursor_position < self.value.len() {
-                        self.cursor_position += 1;
-                    }
+                    self.cursor_position = self.next_position();
                 }
                 event::KeyEvent{ // Handle home
                     code: event::KeyCode::Home,
@@ -213,7 +256,7 @@ impl<'a> Input<'a>{
                     state: _,
                 } => {
                     self.value.insert(self.cursor_position, *c);
-                    self.cursor_position += 1;
+                    self.cursor_position += c.len_utf8();
                 }
                 _ => {}
             }
@@ -235,15 +278,31 @@ impl Drawable for Input<'_> {
         let valild_fg_color = self.config.color.valid_foreground;
         let invalid_fg_color = self.config.color.invalid_foreground;
 
+        let right_title: String = match &self.right_title {
+            Some(right_title) => right_title.clone(),
+            None => String::new()
+        };
+
         let block = Block::default()
             .title(
                 text::Span::styled(
-                    self.prompt,
+                    format!("{} [{}]", self.prompt, self.mode),
                     Style::default()
                         .fg(if self.selected {selected_fg_color} else {fg_color})
                         .bg(bg_color)
                 )
             )
+            .title(
+                Title::from(
+                    text::Span::styled(
+                        right_title.as_str(),
+                        Style::default()
+                            .fg(if self.selected {selected_fg_color} else {fg_color})
+                            .bg(bg_color)
+                    )
+                )
+                .alignment(Alignment::Right)
+            )
             .borders(Borders::ALL)
             .border_style(
                 Style::default()
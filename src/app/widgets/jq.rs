@@ -1,46 +1,223 @@
 use ratatui::{
-    layout::{Layout, Direction, Constraint},
+    layout::{Layout, Direction, Constraint, Rect},
     backend::Backend,
 };
 
 use crossterm::event;
 
-use crate::app::widgets::{Drawable, Input, Json};
-use crate::app::Config;
+use crate::app::widgets::{Compare, Diff, Drawable, Input, Json, Pipeline, PickerItem, Timings};
+use crate::app::{Config, Engine};
+use crate::app::config::OutputMode;
+use crate::app::document::Document;
+use crate::app::edit::{self, Action, Splice};
+use crate::app::engine::{Language, Parsed};
 use crate::app::app::Selected;
+use crate::app::keymap::Context;
+use crate::app::jsonpath;
+use crate::app::large_file::LargeFile;
+use crate::app::schema::{Schema, Violation};
+use crate::app::shape::Shape;
+use crate::app::stats::Stats;
+use crate::app::tokenizer::split_directives;
+
+use serde_json::Value;
+
+use std::cell::Cell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use jq_rs;
 pub struct Jq<'a> {
     pub json_base: Json<'a>,
     pub json_filtered: Json<'a>,
+    // Shown instead of json_base while debugging the pipeline
+    pipeline: Option<Pipeline<'a>>,
+    // Shown instead of both panes in diff mode
+    diff: Option<Diff<'a>>,
+    // Second input file, compared side by side with the first one
+    compare: Option<Compare<'a>>,
+    // The inputs, parsed once for every query
+    base_input: Parsed,
+    compare_input: Option<Parsed>,
+    pub timings: Timings<'a>,
+    // Output of the query shown in the result pane, kept to print it again
+    // in another mode
+    result: Result<String, String>,
+    output_mode: OutputMode,
+    // JSON Schemas of the input and of the result, with their last violations
+    input_schema: Option<Schema>,
+    output_schema: Option<Schema>,
+    input_violations: Vec<Violation>,
+    output_violations: Vec<Violation>,
+    json_file_path: String,
+    // Edit mode of the base document, the edits to undo with the path of the
+    // node they were made on, and how many of them are saved to the file
+    // (None once an undone saved edit is replaced)
+    editing: bool,
+    edits: Vec<(Splice, Vec<Value>)>,
+    saved_edits: Option<usize>,
+    // Set when the input is too large, the base document is then a sample of it
+    large: Option<LargeFile>,
+    config: &'a Config,
+    engine: Engine,
     need_to_clear: bool,
+    // Pane layout, starts from the config and is changed at runtime
+    direction: Direction,
+    result_first: bool,
+    ratio: u16,
+    base_hidden: bool,
+    zoomed: bool,
+    // Where the result and base panes were last drawn, to route mouse events
+    result_area: Cell<Option<Rect>>,
+    base_area: Cell<Option<Rect>>,
 }
 
-impl<'a> Jq<'a> {
-    pub fn new(json_file_path: String, config: &'a Config) -> Jq<'a> {
-        let json_file_path_clone = json_file_path.clone();
-        match std::fs::read_to_string(json_file_path) {
-            Ok(json) => {
-                Jq {
-                    json_base: Json::new(
-                        json,
-                        "JSON File".to_string(),
-                        Some(json_file_path_clone),
-                        config
-                    ),
-                    json_filtered: Json::new(
-                        String::new(),
-                        "JQ Output".to_string(),
-                        None,
-                        config
-                    ),
-                    need_to_clear: false,
-                }
+fn contains(area: Option<Rect>, column: u16, row: u16) -> bool {
+    area.is_some_and(|area| {
+        column >= area.x && column < area.x + area.width
+            && row >= area.y && row < area.y + area.height
+    })
+}
+
+// The output of the engine (one compact value per line) as jq would print
+// it: pretty JSON, or the strings unquoted with -r and -j
+fn format_output(output: &str, mode: OutputMode) -> String {
+    let mut text = String::new();
+    for line in output.lines() {
+        let string = match mode {
+            OutputMode::Json => None,
+            OutputMode::Raw | OutputMode::Join => serde_json::from_str::<String>(line).ok(),
+        };
+        match string {
+            Some(string) => text.push_str(&string),
+            None => match serde_json::from_str::<Value>(line) {
+                Ok(value) => text.push_str(&serde_json::to_string_pretty(&value).unwrap_or_default()),
+                Err(_) => text.push_str(line),
             },
-            Err(_) => {
-                panic!("Invalid JSON file")
-            }
         }
+        if mode != OutputMode::Join {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+fn output_title(mode: OutputMode) -> String {
+    match mode {
+        OutputMode::Json => "JQ Output".to_string(),
+        OutputMode::Raw => "JQ Output (raw)".to_string(),
+        OutputMode::Join => "JQ Output (join)".to_string(),
+    }
+}
+
+// Write through a temporary file renamed over `path`, so that it is never
+// left half written. The previous file is kept next to it, as `<path>.bak`
+// or, when that backup exists, `<path>.bak.1`, `<path>.bak.2`...
+// A symbolic link is followed, the file it points to is replaced.
+fn write_atomically(path: &Path, content: &str) -> Result<Option<PathBuf>, std::io::Error> {
+    let path = if path.exists() {
+        std::fs::canonicalize(path)?
+    } else {
+        path.to_path_buf()
+    };
+    let path = path.as_path();
+    let name = path.file_name().ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{} is not a file path", path.display())
+    ))?;
+    let backup = if path.exists() {
+        let backup = (0..)
+            .map(|i| match i {
+                0 => path.with_file_name(format!("{}.bak", name.to_string_lossy())),
+                i => path.with_file_name(format!("{}.bak.{}", name.to_string_lossy(), i)),
+            })
+            .find(|backup| !backup.exists())
+            .unwrap_or_default();
+        std::fs::copy(path, &backup)?;
+        Some(backup)
+    } else {
+        None
+    };
+    let temp = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    let written = std::fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| match std::fs::metadata(path) {
+            // Keep the mode of the replaced file
+            Ok(metadata) => std::fs::set_permissions(&temp, metadata.permissions()),
+            Err(_) => Ok(()),
+        })
+        .and_then(|_| std::fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written.map(|_| backup)
+}
+
+const MIN_RATIO: u16 = 10;
+const MAX_RATIO: u16 = 90;
+
+impl<'a> Jq<'a> {
+    pub fn new(json_file_path: String, engine: Engine, config: &'a Config) -> Result<Jq<'a>, std::io::Error> {
+        let size = std::fs::metadata(&json_file_path)?.len();
+        let (json, right_title, large) = if size >= config.large.threshold {
+            let large = LargeFile::open(Path::new(&json_file_path), &config.large, &mut |done, total| {
+                eprint!("\rIndexing {}: {}%", json_file_path, done * 100 / total.max(1));
+            })?;
+            eprintln!();
+            let right_title = format!("{} · {}", json_file_path, large.description());
+            (large.sample(), right_title, Some(large))
+        } else {
+            (std::fs::read_to_string(&json_file_path)?, json_file_path.clone(), None)
+        };
+        let mut timings = Timings::new(config);
+        let start = Instant::now();
+        let base_input = engine.parse(&json);
+        timings.input_parse = start.elapsed();
+        let input_schema = config.schema.input.as_deref().map(Schema::load).transpose()?;
+        let output_schema = config.schema.output.as_deref().map(Schema::load).transpose()?;
+        let input_violations = input_schema.as_ref().and_then(|schema| schema.validate(&json));
+        let mut json_base = Json::new(json, "JSON File".to_string(), Some(right_title), config);
+        json_base.set_validation(input_violations.as_deref());
+        let input_violations = input_violations.unwrap_or_default();
+        Ok(Jq {
+            json_base,
+            json_filtered: Json::new(
+                String::new(),
+                output_title(config.output_mode),
+                None,
+                config
+            ),
+            pipeline: None,
+            diff: None,
+            compare: None,
+            base_input,
+            compare_input: None,
+            timings,
+            result: Ok(String::new()),
+            output_mode: config.output_mode,
+            input_schema,
+            output_schema,
+            input_violations,
+            output_violations: Vec::new(),
+            json_file_path,
+            editing: false,
+            edits: Vec::new(),
+            saved_edits: Some(0),
+            large,
+            config,
+            engine,
+            need_to_clear: false,
+            direction: config.layout.direction,
+            result_first: config.layout.result_first,
+            ratio: config.layout.ratio.clamp(MIN_RATIO, MAX_RATIO),
+            base_hidden: false,
+            zoomed: false,
+            result_area: Cell::new(None),
+            base_area: Cell::new(None),
+        })
     }
 
     pub fn consume_clear(&mut self) -> bool {
@@ -49,17 +226,168 @@ impl<'a> Jq<'a> {
         need_to_clear
     }
 
+    // Run the query on a second input file too and compare both results
+    pub fn compare_with(&mut self, path: String) -> Result<(), std::io::Error> {
+        let json = std::fs::read_to_string(&path)?;
+        self.compare_input = Some(self.engine.parse(&json));
+        self.compare = Some(Compare::new(path, self.config));
+        Ok(())
+    }
+
+    // Move the split between both panes by `delta` percent
+    pub fn resize(&mut self, delta: i16) {
+        self.ratio = (self.ratio as i16 + delta).clamp(MIN_RATIO as i16, MAX_RATIO as i16) as u16;
+    }
+
+    pub fn toggle_direction(&mut self) {
+        self.direction = match self.direction {
+            Direction::Horizontal => Direction::Vertical,
+            Direction::Vertical => Direction::Horizontal,
+        };
+    }
+
+    pub fn swap_panes(&mut self) {
+        self.result_first = !self.result_first;
+    }
+
+    pub fn toggle_base(&mut self) {
+        self.base_hidden = !self.base_hidden;
+    }
+
+    pub fn base_hidden(&self) -> bool {
+        // Both files are always shown in compare mode
+        self.base_hidden && self.compare.is_none()
+    }
+
+    pub fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed;
+    }
+
+    // Handle a click, returns the pane which must be focused if one was hit
+    pub fn click(&mut self, column: u16, row: u16) -> Option<Selected> {
+        let (result_area, base_area) = (self.result_area.get(), self.base_area.get());
+        if contains(result_area, column, row) {
+            if self.diff.is_none() && self.compare.is_none() {
+                self.json_filtered.click(result_area.unwrap(), column, row);
+            }
+            Some(Selected::JsonFiltered)
+        } else if contains(base_area, column, row) {
+            if self.pipeline.is_none() && self.compare.is_none() {
+                self.json_base.click(base_area.unwrap(), column, row);
+            }
+            Some(Selected::JsonBase)
+        } else {
+            None
+        }
+    }
+
+    // Scroll the pane under the mouse, whether it is focused or not
+    pub fn scroll(&mut self, column: u16, row: u16, down: bool) {
+        let key = event::KeyEvent::new(
+            if down {event::KeyCode::Down} else {event::KeyCode::Up},
+            event::KeyModifiers::NONE
+        );
+        let in_result = contains(self.result_area.get(), column, row);
+        let in_base = contains(self.base_area.get(), column, row);
+        if !in_result && !in_base {
+            return;
+        }
+        if let Some(diff) = &mut self.diff {
+            diff.handle_event(&key);
+        } else if let Some(compare) = &mut self.compare {
+            compare.handle_event(&key);
+        } else if in_result {
+            self.json_filtered.scroll(down);
+        } else if let Some(pipeline) = &mut self.pipeline {
+            if pipeline.handle_event(&key) {
+                self.show_stage();
+            }
+        } else {
+            self.json_base.scroll(down);
+        }
+    }
+
+    // Path of the node under the cursor of the focused JSON pane. The paths
+    // of the base pane mean nothing to a query run on --stream events.
+    pub fn cursor_path(&self, selected: &Selected) -> Option<String> {
+        if self.diff.is_some() || self.compare.is_some() {
+            return None;
+        }
+        match selected {
+            Selected::JsonFiltered => self.json_filtered.cursor_path(),
+            Selected::JsonBase if self.pipeline.is_none() && !self.engine.streaming() => self.json_base.cursor_path(),
+            _ => None,
+        }
+    }
+
+    // Node under the cursor of the focused JSON pane, with its path
+    pub fn cursor_value(&self, selected: &Selected) -> Option<(String, Value)> {
+        if self.diff.is_some() || self.compare.is_some() {
+            return None;
+        }
+        match selected {
+            Selected::JsonFiltered => self.json_filtered.cursor_value(),
+            Selected::JsonBase if self.pipeline.is_none() => self.json_base.cursor_value(),
+            _ => None,
+        }
+    }
+
+    // Shape of the input document, an error if it is not a single JSON value
+    pub fn input_shape(&self) -> Result<Shape, String> {
+        // Its paths can't be used in a query run on --stream events
+        if self.engine.streaming() {
+            return Err("The query runs on --stream events, not on the input document".to_string());
+        }
+        self.json_base.document()
+            .map(Shape::infer)
+            .ok_or_else(|| "The input is not a single JSON value".to_string())
+    }
+
+    // Which key bindings apply to the focused pane
+    pub fn context(&self, selected: &Selected) -> Context {
+        match selected {
+            Selected::Input => Context::Input,
+            _ if self.diff.is_some() => Context::Diff,
+            _ if self.compare.is_some() => Context::Compare,
+            _ if self.pipeline.as_ref().is_some_and(|pipeline| pipeline.selected()) => Context::Pipeline,
+            Selected::JsonBase if self.editing => Context::Edit,
+            Selected::JsonFiltered if self.json_filtered.table_active() => Context::Table,
+            Selected::JsonBase if self.json_base.table_active() => Context::Table,
+            _ => Context::Json,
+        }
+    }
+
     pub fn set_selected(&mut self, selected: &Selected) {
+        if let Some(diff) = &mut self.diff {
+            diff.set_selected(matches!(selected, Selected::JsonBase | Selected::JsonFiltered));
+        }
+        if let Some(compare) = &mut self.compare {
+            compare.set_selected(matches!(selected, Selected::JsonBase | Selected::JsonFiltered));
+        }
         match selected {
             Selected::JsonBase => {
-                self.json_base.set_selected(true);
+                match &mut self.pipeline {
+                    Some(pipeline) => {
+                        pipeline.set_selected(true);
+                        self.json_base.set_selected(false);
+                    },
+                    None => {
+                        self.json_base.set_selected(true);
+                    }
+                }
                 self.json_filtered.set_selected(false);
             },
             Selected::JsonFiltered => {
+                if let Some(pipeline) = &mut self.pipeline {
+                    pipeline.set_selected(false);
+                }
                 self.json_base.set_selected(false);
                 self.json_filtered.set_selected(true);
             },
             _ => {
+                if let Some(pipeline) = &mut self.pipeline {
+                    pipeline.set_selected(false);
+                }
                 self.json_base.set_selected(false);
                 self.json_filtered.set_selected(false);
             }
@@ -67,6 +395,26 @@ impl<'a> Jq<'a> {
     }
 
     pub fn handle_event(&mut self, event: &event::KeyEvent) -> () {
+        if let Some(diff) = &mut self.diff {
+            if diff.selected() {
+                diff.handle_event(event);
+                return;
+            }
+        }
+        if let Some(compare) = &mut self.compare {
+            if compare.selected() {
+                compare.handle_event(event);
+                return;
+            }
+        }
+        if let Some(pipeline) = &mut self.pipeline {
+            if pipeline.selected() {
+                if pipeline.handle_event(event) {
+                    self.show_stage();
+                }
+                return;
+            }
+        }
         if self.json_base.selected() {
             self.json_base.handle_event(event);
         } else if self.json_filtered.selected() {
@@ -74,20 +422,409 @@ impl<'a> Jq<'a> {
         }
     }
 
+    pub fn editing(&self) -> bool {
+        self.editing
+    }
+
+    // Enter or leave the edit mode of the base document
+    pub fn toggle_edit(&mut self) -> Result<(), String> {
+        if !self.editing {
+            if self.large.is_some() {
+                return Err("The base pane is a sample of a large input, it can't be edited".to_string());
+            }
+            if self.pipeline.is_some() || self.diff.is_some() || self.compare.is_some() {
+                return Err("Close the pipeline debugger, the diff or the comparison first".to_string());
+            }
+            if self.json_base.document().is_none() {
+                return Err("Only an input made of a single JSON value can be edited".to_string());
+            }
+        }
+        self.editing = !self.editing;
+        self.json_base.set_title(if self.editing { "JSON File [edit]" } else { "JSON File" }.to_string());
+        Ok(())
+    }
+
+    fn edit_node(&self) -> Result<(&Document, usize), String> {
+        match (self.json_base.document(), self.json_base.cursor_index()) {
+            (Some(document), Some(node)) => Ok((document, node)),
+            _ => Err("No node under the cursor".to_string()),
+        }
+    }
+
+    // The fields to fill to edit the node under the cursor, with their initial values
+    pub fn edit_fields(&self, action: Action) -> Result<Vec<(&'static str, String)>, String> {
+        let (document, node) = self.edit_node()?;
+        edit::fields(self.json_base.json(), document, node, action)
+    }
+
+    // Edit the node under the cursor of the base pane, then run the query again
+    pub fn edit(&mut self, action: Action, values: &[String], input: &mut Input) -> Result<(), String> {
+        let (document, node) = self.edit_node()?;
+        let (splice, path) = edit::apply(self.json_base.json(), document, node, action, values)?;
+        let cursor = document.path(node);
+        let mut json = self.json_base.json().to_string();
+        splice.apply(&mut json);
+        if self.saved_edits.is_some_and(|saved| saved > self.edits.len()) {
+            self.saved_edits = None;
+        }
+        self.edits.push((splice, cursor));
+        self.set_base(json, &path, input);
+        Ok(())
+    }
+
+    // Undo the last edit, false if there is none
+    pub fn undo_edit(&mut self, input: &mut Input) -> bool {
+        let (splice, cursor) = match self.edits.pop() {
+            Some(edit) => edit,
+            None => return false,
+        };
+        let mut json = self.json_base.json().to_string();
+        splice.reverse().apply(&mut json);
+        self.set_base(json, &cursor, input);
+        true
+    }
+
+    // Write the edited base document back to the input file, returns the
+    // backup of the previous content
+    pub fn save_input(&mut self) -> Result<Option<PathBuf>, std::io::Error> {
+        let backup = write_atomically(Path::new(&self.json_file_path), self.json_base.json())?;
+        self.saved_edits = Some(self.edits.len());
+        self.update_base_title();
+        Ok(backup)
+    }
+
+    fn update_base_title(&mut self) {
+        let title = if self.saved_edits != Some(self.edits.len()) {
+            format!("{} [modified]", self.json_file_path)
+        } else {
+            self.json_file_path.clone()
+        };
+        self.json_base.set_right_title(Some(title));
+    }
+
+    // Replace the base document, keeping the cursor on the node at `cursor`
+    fn set_base(&mut self, json: String, cursor: &[Value], input: &mut Input) {
+        let start = Instant::now();
+        self.base_input = self.engine.parse(&json);
+        self.timings.input_parse = start.elapsed();
+        let violations = self.input_schema.as_ref().and_then(|schema| schema.validate(&json));
+        if let Some(diff) = &mut self.diff {
+            diff.set_base(&json);
+        }
+        self.json_base.set_json(json);
+        self.json_base.set_validation(violations.as_deref());
+        self.input_violations = violations.unwrap_or_default();
+        self.update_base_title();
+        self.apply_filter(input);
+        self.json_base.select_path(cursor);
+    }
+
+    // Check if one of the jq modules was edited, the filter must then be applied again
+    pub fn modules_changed(&mut self) -> bool {
+        if !self.engine.modules_changed() {
+            return false;
+        }
+        self.base_input.forget();
+        if let Some(compare_input) = &self.compare_input {
+            compare_input.forget();
+        }
+        true
+    }
+
+    pub fn language(&self) -> Language {
+        self.engine.language()
+    }
+
+    // Run the query in another language, the pipeline debugger only splits jq queries
+    pub fn set_language(&mut self, language: Language, input: &mut Input, selected: &Selected) {
+        self.engine.set_language(language);
+        if language != Language::Jq && self.pipeline.is_some() {
+            self.pipeline = None;
+            self.set_selected(selected);
+            self.need_to_clear = true;
+        }
+        self.apply_filter(input);
+    }
+
+    // Show or hide the pipeline stages in place of the base document
+    pub fn toggle_pipeline(&mut self, input: &mut Input, selected: &Selected) {
+        self.pipeline = match self.pipeline {
+            Some(_) => None,
+            None => Some(Pipeline::new(self.config)),
+        };
+        self.set_selected(selected);
+        self.need_to_clear = true;
+        self.apply_filter(input);
+    }
+
+    // Show or hide the diff between the base document and the result
+    pub fn toggle_diff(&mut self, input: &mut Input, selected: &Selected) {
+        self.diff = match self.diff {
+            Some(_) => None,
+            None => {
+                let mut diff = Diff::new(self.config);
+                diff.set_base(self.json_base.json());
+                Some(diff)
+            },
+        };
+        self.set_selected(selected);
+        self.need_to_clear = true;
+        self.apply_filter(input);
+    }
+
+    pub fn diff_active(&self) -> bool {
+        self.diff.is_some()
+    }
+
+    // Write the diff as a JSON Patch (RFC 6902) document
+    pub fn export_patch(&self, path: &std::path::Path) -> Result<(), std::io::Error> {
+        let patch = match self.diff.as_ref().and_then(|diff| diff.patch()) {
+            Some(patch) => patch,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "No diff to export"
+                ));
+            }
+        };
+        let mut content = serde_json::to_string_pretty(patch)?;
+        content.push('\n');
+        std::fs::write(path, content)
+    }
+
+    fn set_result(&mut self, result: Result<String, String>) {
+        match (&result, self.output_mode) {
+            (Ok(output), OutputMode::Json) => self.json_filtered.set_json(output.clone()),
+            (Ok(output), mode) => self.json_filtered.set_text(format_output(output, mode)),
+            (Err(error), _) => {
+                self.need_to_clear = true;
+                self.json_filtered.set_json(format!("Error: {}", error));
+            },
+        }
+        if let Some(schema) = &self.output_schema {
+            let violations = result.as_ref().ok().and_then(|output| schema.validate(output));
+            self.json_filtered.set_validation(violations.as_deref());
+            self.output_violations = violations.unwrap_or_default();
+        }
+        self.result = result;
+    }
+
+    // Schema violations of the input, or of the result, as picker items
+    pub fn violations(&self, input: bool) -> Result<(String, Vec<PickerItem>), String> {
+        let (schema, violations, option) = if input {
+            (&self.input_schema, &self.input_violations, "--schema")
+        } else {
+            (&self.output_schema, &self.output_violations, "--output-schema")
+        };
+        let schema = schema.as_ref().ok_or_else(|| format!("No schema, set one with {}", option))?;
+        let items = violations
+            .iter()
+            .map(|violation| PickerItem {
+                label: violation.location.clone(),
+                detail: violation.message.clone(),
+                haystack: format!("{} {}", violation.location, violation.message),
+            })
+            .collect();
+        Ok((format!("Violations of {}", schema.name), items))
+    }
+
+    // Move the cursor of the input or result pane to the node of a violation
+    pub fn select_violation(&mut self, input: bool, index: usize) {
+        let (json, violations) = if input {
+            (&mut self.json_base, &self.input_violations)
+        } else {
+            (&mut self.json_filtered, &self.output_violations)
+        };
+        // The panes only show the first value of a stream
+        if let Some(violation) = violations.get(index).filter(|violation| violation.value == 0) {
+            json.select_path(&violation.path);
+        }
+    }
+
+    // Toggle between `mode` and the JSON output
+    pub fn toggle_output_mode(&mut self, mode: OutputMode) {
+        self.output_mode = if self.output_mode == mode { OutputMode::Json } else { mode };
+        self.json_filtered.set_title(output_title(self.output_mode));
+        self.set_result(self.result.clone());
+    }
+
+    // The result as printed in the current output mode, what gets copied or saved
+    pub fn formatted_result(&self) -> Result<String, String> {
+        match &self.result {
+            Ok(output) => Ok(format_output(output, self.output_mode)),
+            Err(_) => Err("The query failed, there is no result".to_string()),
+        }
+    }
+
+    pub fn result_stats(&self) -> Result<Stats, String> {
+        match &self.result {
+            Ok(output) => Stats::new(output).ok_or_else(|| "The result is not made of JSON values".to_string()),
+            Err(_) => Err("The query failed, there is no result".to_string()),
+        }
+    }
+
+    pub fn export_result(&self, path: &std::path::Path) -> Result<(), std::io::Error> {
+        let text = self.formatted_result()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        std::fs::write(path, text)
+    }
+
+    pub fn input_path(&self) -> &str {
+        &self.json_file_path
+    }
+
+    // The result as the content of a file: a single JSON value, printed like the input
+    pub fn result_file(&self) -> Result<String, String> {
+        if self.large.is_some() {
+            return Err("The query runs on a sample of the input, its result can't replace a file".to_string());
+        }
+        if self.pipeline.is_some() || self.compare.is_some() {
+            return Err("Close the pipeline debugger or the comparison first".to_string());
+        }
+        let output = self.result.as_ref().map_err(|_| "The query failed, there is no result".to_string())?;
+        let values = serde_json::Deserializer::from_str(output)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<Value>, _>>()
+            .map_err(|_| "The result is not made of JSON values".to_string())?;
+        match values.as_slice() {
+            [value] => Ok(format!("{}\n", edit::format_like(self.json_base.json(), value))),
+            [] => Err("The result is empty".to_string()),
+            _ => Err(format!("The result is {} values, a file can only hold one", values.len())),
+        }
+    }
+
+    // Write a result, as confirmed from result_file(), to a file. Replacing
+    // the input file loads it in the base pane. Returns the backup of the
+    // replaced file.
+    pub fn apply_to_file(&mut self, path: &Path, content: &str, input: &mut Input) -> Result<Option<PathBuf>, String> {
+        let backup = write_atomically(path, content).map_err(|error| format!("Error: {}", error))?;
+        let is_input = std::fs::canonicalize(path)
+            .ok()
+            .is_some_and(|path| std::fs::canonicalize(&self.json_file_path).is_ok_and(|input| input == path));
+        if is_input {
+            // The edits were made on the previous content
+            self.edits.clear();
+            self.saved_edits = Some(0);
+            let cursor = self.json_base.cursor_index()
+                .and_then(|node| self.json_base.document().map(|document| document.path(node)))
+                .unwrap_or_default();
+            self.set_base(content.to_string(), &cursor, input);
+        }
+        Ok(backup)
+    }
+
+    // Display the result of the selected pipeline stage in the output pane
+    fn show_stage(&mut self) {
+        let (result, position) = match &self.pipeline {
+            Some(pipeline) => match pipeline.current() {
+                Some(stage) => (stage.result.clone(), pipeline.position()),
+                None => (Ok(String::new()), (0, 0)),
+            },
+            None => return,
+        };
+        self.set_result(result);
+        self.json_filtered.set_right_title(Some(format!("stage {}/{}", position.0, position.1)));
+    }
+
+    // Paths of the nodes selected by the query, computed with `path(query)`.
+    // Only path expressions (ie: `.items[] | select(...)`) permit it,
+    // for anything else (ie: `.a + 1`) nothing is selected.
+    // JSONPath queries are translated to jq first, JMESPath ones select nothing.
+    // With --stream the query sees [path, leaf] events, not the document of
+    // the base pane, so nothing is selected either.
+    fn selected_paths(&mut self, query: &str) -> Vec<Value> {
+        if self.engine.streaming() {
+            return Vec::new();
+        }
+        let query = match self.engine.language() {
+            Language::Jq => query.to_string(),
+            Language::JsonPath => match jsonpath::to_jq(query) {
+                Ok(query) => query,
+                Err(_) => return Vec::new(),
+            },
+            Language::JmesPath => return Vec::new(),
+        };
+        let (directives, body) = split_directives(&query);
+        if body.trim().is_empty() {
+            return Vec::new();
+        }
+        // Newlines keep a trailing comment from swallowing the parenthesis
+        let wrapped = format!("{}path(\n{}\n)", directives, body);
+        self.timings.paths_memoized = self.base_input.is_memoized(Language::Jq, &wrapped);
+        match self.engine.run_as(Language::Jq, &wrapped, &self.base_input) {
+            Ok(output) => output
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Run the query on the whole input instead of the sample, `progress`
+    // gets the bytes parsed and the file size. Returns how long it took.
+    pub fn run_on_whole_file(&mut self, input: &mut Input, progress: &mut dyn FnMut(usize, usize)) -> Result<Duration, String> {
+        let large = match &self.large {
+            Some(large) => large,
+            None => return Err("The whole file is already queried".to_string()),
+        };
+        let start = Instant::now();
+        let result = self.engine.run_bytes(input.value(), large.data(), progress);
+        let elapsed = start.elapsed();
+        input.set_valid(result.is_ok());
+        self.set_result(result);
+        self.json_filtered.set_right_title(Some("whole file".to_string()));
+        Ok(elapsed)
+    }
+
     pub fn apply_filter (&mut self, input: &mut Input) -> () {
-        self.json_filtered.set_json(
-            match jq_rs::run(input.value(), self.json_base.json()) {
-                Ok(result) => {
-                    input.set_valid(true);
-                    result
-                },
-                Err(error) => {
-                    input.set_valid(false);
-                    self.need_to_clear = true;
-                    format!("Error: {}", error)
-                }
+        let start = Instant::now();
+        match &mut self.pipeline {
+            Some(pipeline) => {
+                let engine = &self.engine;
+                let base = &self.base_input;
+                // Memoized only if every stage is
+                let mut memoized = true;
+                pipeline.update(input.value(), |prefix| {
+                    memoized &= base.is_memoized(engine.language(), prefix);
+                    engine.run(prefix, base)
+                });
+                self.timings.query_memoized = memoized;
+                self.timings.query = start.elapsed();
+                // The last stage is the whole query
+                input.set_valid(pipeline.current().is_none_or(|stage| stage.result.is_ok()));
+                let start = Instant::now();
+                self.show_stage();
+                self.timings.result_parse = start.elapsed();
+            },
+            None => {
+                self.timings.query_memoized = self.base_input.is_memoized(self.engine.language(), input.value());
+                let result = self.engine.run(input.value(), &self.base_input);
+                self.timings.query = start.elapsed();
+                input.set_valid(result.is_ok());
+                let start = Instant::now();
+                self.set_result(result);
+                self.timings.result_parse = start.elapsed();
+                self.json_filtered.set_right_title(None);
             }
-        );
+        }
+        let start = Instant::now();
+        let paths = self.selected_paths(input.value());
+        self.timings.paths = start.elapsed();
+        self.timings.memoized = self.base_input.memoized();
+        self.json_base.set_highlights(&paths);
+        if let Some(diff) = &mut self.diff {
+            diff.set_filtered(self.result.as_deref().unwrap_or_default());
+        }
+        if let (Some(compare), Some(compare_input)) = (&mut self.compare, &self.compare_input) {
+            // Always compare the whole query, even while showing a pipeline stage
+            let [left, right] = [&self.base_input, compare_input].map(|data| {
+                match self.engine.run(input.value(), data) {
+                    Ok(result) => result,
+                    Err(error) => format!("Error: {}", error),
+                }
+            });
+            compare.set_results(&left, &right);
+        }
     }
 }
 
@@ -98,16 +835,190 @@ impl Drawable for Jq<'_> {
         area: ratatui::layout::Rect,
     ) -> Result<(), std::fmt::Error>{
 
+        if let Some(diff) = &self.diff {
+            self.result_area.set(Some(area));
+            self.base_area.set(None);
+            return diff.draw(f, area);
+        }
+
+        // Side by side panes are unreadable on narrow terminals
+        let direction = if area.width < self.config.layout.stack_below_width {
+            Direction::Vertical
+        } else {
+            self.direction
+        };
         let layout = Layout::default()
-            .direction(Direction::Horizontal)
+            .direction(direction)
             .constraints(vec![
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
+                Constraint::Percentage(self.ratio),
+                Constraint::Percentage(100 - self.ratio),
             ])
             .split(area);
 
-        self.json_filtered.draw(f, layout[0])?;
-        self.json_base.draw(f, layout[1])?;
+        if let Some(compare) = &self.compare {
+            self.result_area.set(Some(layout[0]));
+            self.base_area.set(Some(layout[1]));
+            return compare.draw_pair(f, layout[0], layout[1], &self.json_file_path);
+        }
+
+        let (result_area, base_area) = if self.result_first {
+            (layout[0], layout[1])
+        } else {
+            (layout[1], layout[0])
+        };
+        let base_selected = match &self.pipeline {
+            Some(pipeline) => pipeline.selected(),
+            None => self.json_base.selected(),
+        };
+        // A zoomed pane takes the whole area, the result pane when the input is focused
+        let (result_area, base_area): (Option<Rect>, Option<Rect>) = match (self.zoomed, self.base_hidden) {
+            (true, _) if base_selected => (None, Some(area)),
+            (true, _) | (false, true) => (Some(area), None),
+            (false, false) => (Some(result_area), Some(base_area)),
+        };
+        self.result_area.set(result_area);
+        self.base_area.set(base_area);
+
+        if let Some(result_area) = result_area {
+            self.json_filtered.draw(f, result_area)?;
+        }
+        if let Some(base_area) = base_area {
+            match &self.pipeline {
+                Some(pipeline) => pipeline.draw(f, base_area)?,
+                None => self.json_base.draw(f, base_area)?,
+            }
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A new empty directory for a test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jq_live_query-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn write_new_file() {
+        let dir = temp_dir("new");
+        let path = dir.join("new.json");
+        assert_eq!(write_atomically(&path, "[1]\n").unwrap(), None);
+        assert_eq!(read(&path), "[1]\n");
+        // No backup nor temporary file left
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn replace_with_backups() {
+        let dir = temp_dir("replace");
+        let path = dir.join("data.json");
+        std::fs::write(&path, "1\n").unwrap();
+        assert_eq!(write_atomically(&path, "2\n").unwrap(), Some(dir.join("data.json.bak")));
+        assert_eq!(read(&path), "2\n");
+        assert_eq!(read(&dir.join("data.json.bak")), "1\n");
+        // The first backup is kept
+        assert_eq!(write_atomically(&path, "3\n").unwrap(), Some(dir.join("data.json.bak.1")));
+        assert_eq!(write_atomically(&path, "4\n").unwrap(), Some(dir.join("data.json.bak.2")));
+        assert_eq!(read(&path), "4\n");
+        assert_eq!(read(&dir.join("data.json.bak")), "1\n");
+        assert_eq!(read(&dir.join("data.json.bak.1")), "2\n");
+        assert_eq!(read(&dir.join("data.json.bak.2")), "3\n");
+        assert!(!dir.join(".data.json.tmp").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_kept() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("permissions");
+        let path = dir.join("data.json");
+        std::fs::write(&path, "1\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        write_atomically(&path, "2\n").unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_followed() {
+        let dir = temp_dir("symlink");
+        std::fs::create_dir(dir.join("target")).unwrap();
+        let target = dir.join("target").join("data.json");
+        let link = dir.join("link.json");
+        std::fs::write(&target, "1\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let backup = write_atomically(&link, "2\n").unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(read(&target), "2\n");
+        assert_eq!(backup, Some(std::fs::canonicalize(dir.join("target")).unwrap().join("data.json.bak")));
+        assert!(!dir.join("link.json.bak").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // The result of `query` on the input as a file
+    fn result_file(input: &str, query: &str) -> Result<String, String> {
+        let dir = temp_dir("result");
+        let path = dir.join("input.json");
+        std::fs::write(&path, input).unwrap();
+        let config = Config::new();
+        let engine = Engine::new(Vec::new(), dir.clone());
+        let mut jq = Jq::new(path.display().to_string(), engine, &config).unwrap();
+        let mut input = Input::new("", "", &config);
+        input.set_value(query);
+        jq.apply_filter(&mut input);
+        let result = jq.result_file();
+        std::fs::remove_dir_all(dir).unwrap();
+        result
+    }
+
+    #[test]
+    fn result_as_file() {
+        assert_eq!(result_file("{\n  \"a\": [1, 2]\n}\n", ".").unwrap(), "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n");
+        assert_eq!(result_file(r#"{"a": [1, 2]}"#, ".a").unwrap(), "[1,2]\n");
+        assert_eq!(result_file(r#"{"a": [1, 2]}"#, ".a | map(. * 10)").unwrap(), "[10,20]\n");
+        assert_eq!(result_file(r#"{"a": [1, 2]}"#, "error(\"x\")"), Err("The query failed, there is no result".to_string()));
+        assert_eq!(result_file(r#"{"a": [1, 2]}"#, "empty"), Err("The result is empty".to_string()));
+        assert_eq!(
+            result_file(r#"{"a": [1, 2]}"#, ".a[]"),
+            Err("The result is 2 values, a file can only hold one".to_string())
+        );
+        assert!(result_file(r#"{"a": [1, 2]}"#, ".a |").is_err());
+    }
+
+    #[test]
+    fn nothing_selected_in_stream_mode() {
+        let dir = temp_dir("stream");
+        let path = dir.join("input.json");
+        std::fs::write(&path, r#"{"a": [1, 2]}"#).unwrap();
+        let config = Config::new();
+        for streaming in [false, true] {
+            let mut engine = Engine::new(Vec::new(), dir.clone());
+            engine.set_streaming(streaming);
+            let mut jq = Jq::new(path.display().to_string(), engine, &config).unwrap();
+            let paths = jq.selected_paths(".a[]");
+            let cursor = jq.cursor_path(&Selected::JsonBase);
+            if streaming {
+                assert!(paths.is_empty());
+                assert_eq!(cursor, None);
+                assert!(jq.input_shape().is_err());
+            } else {
+                assert_eq!(paths, vec![serde_json::json!(["a", 0]), serde_json::json!(["a", 1])]);
+                assert!(cursor.is_some());
+                assert!(jq.input_shape().is_ok());
+            }
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,28 +1,39 @@
 // This widget displays a JSON object in a tree view.
 // In can handle keyboard events to expand and collapse nodes.
 // It can also handle mouse events to select nodes.
+// The document is kept as a flat tree with an index of its lines,
+// only the lines being drawn are styled.
 
 use ratatui::{
     widgets::{
         Block,
         Borders,
         Paragraph,
+        Scrollbar,
+        ScrollbarOrientation,
+        ScrollbarState,
         block::{
             Title,
             title::Position
         }
     },
     text::{Line, Span, Text},
-    style::Style, backend::Backend,
+    style::{Modifier, Style}, backend::Backend,
+    layout::{Margin, Rect},
     prelude::Alignment
     
 };
 
 use crossterm::event;
 
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
 use std::string::String;
 
-use crate::app::widgets::Drawable;
+use crate::app::document::{DocLine, Document, Key, Kind, Part};
+use crate::app::schema::{Violation, MAX_VIOLATIONS};
+use crate::app::widgets::{Drawable, TableView};
 use crate::app::Config;
 use serde_json::{self, Value};
 
@@ -30,11 +41,207 @@ pub struct Json<'a> {
     selected: bool,
     config: &'a Config,
     raw: String,
-    json: Vec<Line<'a>>,
+    content: Content,
     json_lines_count: usize,
     title: String,
     right_title: Option<String>,
     cursor: usize,
+    // First line shown, it follows the cursor when drawing
+    offset: Cell<usize>,
+    // Number of lines shown, known once drawn
+    height: Cell<usize>,
+    // Line number typed after `:`
+    goto: Option<String>,
+    // Line shown on each row, known once drawn (a wrapped line takes several rows)
+    row_lines: RefCell<Vec<usize>>,
+    // Soft wrap, otherwise long lines are scrolled horizontally
+    wrap: bool,
+    hscroll: usize,
+    // Width of the longest line, in characters
+    max_width: usize,
+    // Shorten long strings in the middle
+    elide: bool,
+    // Show the non-printable characters as \u escapes
+    escape_non_printable: bool,
+    // `z` was typed, waiting for h/l
+    pending_z: bool,
+    // Line range of each node of the document
+    ranges: Vec<(usize, usize)>,
+    // Line ranges of the nodes selected by the query, sorted and merged
+    highlighted: Vec<(usize, usize)>,
+    // First line of each selected node, sorted
+    highlight_starts: Vec<usize>,
+    highlighted_count: usize,
+    // Paths of the selected nodes, kept to highlight them again after a fold
+    highlight_paths: Vec<Value>,
+    // Paths of the nodes failing the schema, and the first line of each
+    violation_paths: Vec<Vec<Value>>,
+    violation_lines: Vec<usize>,
+    // Schema validation shown in the title (ie: "✗ 3 errors"), and whether it passed
+    validation: Option<(String, bool)>,
+    // Path keys of the collapsed nodes, kept when the content changes
+    folded: HashSet<String>,
+    // Last documents shown, going back to a previous result does not parse it again
+    documents: VecDeque<(String, Rc<Document>)>,
+    // Show an array of objects as a table (t toggles it)
+    table_mode: bool,
+    // Built while the table mode is on, None if the content is not an array of objects
+    table: Option<TableView<'a>>,
+}
+
+// What the widget displays
+enum Content {
+    Document { document: Rc<Document>, lines: Vec<DocLine> },
+    // Anything which is not a single JSON value (ie: errors)
+    Text { lines: Vec<String>, is_error: bool },
+}
+
+// Lines scrolled by a mouse wheel step
+const SCROLL_LINES: usize = 3;
+// Lines kept visible above and below the cursor
+const SCROLL_OFF: usize = 3;
+// Columns scrolled by Left/Right
+const HSCROLL_COLUMNS: usize = 4;
+// Columns used by the cursor, fold and highlight markers
+const GUTTER_WIDTH: u16 = 3;
+// Columns between tab stops in text content
+const TAB_WIDTH: usize = 8;
+// Number of documents kept, and the size of the largest one
+const DOCUMENT_CACHE_SIZE: usize = 8;
+const DOCUMENT_CACHE_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+// Key used to index nodes by their path, the compact JSON of the path array
+pub fn path_key(path: &[Value]) -> String {
+    Value::Array(path.to_vec()).to_string()
+}
+
+// jq expression of a path (ie: ["a",0,"b c"] is `.a[0]."b c"`)
+pub fn jq_path(path: &[Value]) -> String {
+    let is_identifier = |key: &str| {
+        key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let mut expression = String::new();
+    for element in path {
+        match element {
+            Value::String(key) if is_identifier(key) => {
+                expression.push('.');
+                expression.push_str(key);
+            },
+            Value::String(_) => {
+                expression.push('.');
+                expression.push_str(&element.to_string());
+            },
+            other => {
+                if expression.is_empty() {
+                    expression.push('.');
+                }
+                expression.push_str(&format!("[{}]", other));
+            },
+        }
+    }
+    if expression.is_empty() {
+        expression.push('.');
+    }
+    expression
+}
+
+// Invisible characters which can only be told apart escaped
+// (ie: C1 controls, zero width spaces, bidi controls, BOM)
+fn is_non_printable(c: char) -> bool {
+    matches!(
+        c,
+        '\u{80}'..='\u{9f}'
+            | '\u{ad}'
+            | '\u{200b}'..='\u{200f}'
+            | '\u{2028}'..='\u{202e}'
+            | '\u{2060}'..='\u{206f}'
+            | '\u{feff}'
+            | '\u{fff9}'..='\u{fffb}'
+    )
+}
+
+// JSON escape of a character, None if it is shown as is
+pub fn escape_char(c: char, non_printable: bool) -> Option<String> {
+    let escape = match c {
+        '"' => "\\\"",
+        '\\' => "\\\\",
+        '\n' => "\\n",
+        '\t' => "\\t",
+        '\r' => "\\r",
+        '\u{8}' => "\\b",
+        '\u{c}' => "\\f",
+        '\u{0}'..='\u{1f}' | '\u{7f}' => return Some(format!("\\u{:04x}", c as u32)),
+        // All of them are in the basic plane, no surrogate pair needed
+        c if non_printable && is_non_printable(c) => return Some(format!("\\u{:04x}", c as u32)),
+        _ => return None,
+    };
+    Some(escape.to_string())
+}
+
+// Width of a string once escaped, without the quotes
+fn escaped_width(s: &str, non_printable: bool) -> usize {
+    s.chars()
+        .map(|c| escape_char(c, non_printable).map_or(1, |escape| escape.len()))
+        .sum()
+}
+
+// Drop the first `columns` characters of a line
+fn skip_columns<'a>(line: &Line<'a>, columns: usize) -> Line<'a> {
+    let mut skipped = 0;
+    let mut spans = Vec::new();
+    for span in &line.spans {
+        let length = span.content.chars().count();
+        if skipped + length <= columns {
+            skipped += length;
+            continue;
+        }
+        let content: String = span.content.chars().skip(columns - skipped).collect();
+        skipped = columns;
+        spans.push(Span::styled(content, span.style));
+    }
+    Line::from(spans)
+}
+
+// Split a line in rows of `width` characters,
+// continuation rows are indented like the line
+fn wrap_line<'a>(line: &Line<'a>, width: usize) -> Vec<Line<'a>> {
+    let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+    let length = text.chars().count();
+    if length <= width || width == 0 {
+        return vec![line.clone()];
+    }
+    // Keep at least half of the width for the content
+    let indent = std::cmp::min(
+        text.chars().take_while(|c| *c == ' ').count(),
+        width / 2
+    );
+    let mut rows = Vec::new();
+    let mut start = 0;
+    while start < length {
+        let (prefix, row_width) = if start == 0 {(0, width)} else {(indent, width - indent)};
+        let mut row = skip_columns(line, start);
+        // Truncate the spans to the row width
+        let mut remaining = row_width;
+        row.spans.retain_mut(|span| {
+            if remaining == 0 {
+                return false;
+            }
+            let span_length = span.content.chars().count();
+            if span_length > remaining {
+                span.content = span.content.chars().take(remaining).collect::<String>().into();
+            }
+            remaining -= std::cmp::min(span_length, remaining);
+            true
+        });
+        if prefix > 0 {
+            let style = line.spans.first().map_or(Style::default(), |span| span.style);
+            row.spans.insert(0, Span::styled(" ".repeat(prefix), style));
+        }
+        rows.push(row);
+        start += row_width;
+    }
+    rows
 }
 
 
@@ -48,13 +255,30 @@ impl<'a> Json<'a> {
             title,
             right_title,
             cursor: 0,
-            json: vec!(Line::from(Span::styled(
-                "Loading...",
-                Style::default()
-                    .fg(config.color.foreground)
-                    .bg(config.color.background)
-            ))),
+            offset: Cell::new(0),
+            height: Cell::new(1),
+            goto: None,
+            row_lines: RefCell::new(Vec::new()),
+            wrap: config.json.soft_wrap,
+            hscroll: 0,
+            max_width: 0,
+            elide: config.json.elide_strings,
+            escape_non_printable: config.json.escape_non_printable,
+            pending_z: false,
+            content: Content::Text { lines: vec!["Loading...".to_string()], is_error: false },
             json_lines_count: 0,
+            ranges: Vec::new(),
+            highlighted: Vec::new(),
+            highlight_starts: Vec::new(),
+            highlighted_count: 0,
+            highlight_paths: Vec::new(),
+            violation_paths: Vec::new(),
+            violation_lines: Vec::new(),
+            validation: None,
+            folded: HashSet::new(),
+            documents: VecDeque::new(),
+            table_mode: false,
+            table: None,
         };
         json.set_json(json_payload);
         json
@@ -68,189 +292,584 @@ impl<'a> Json<'a> {
         self.selected
     }
 
-    fn recursive_parser<'b> (&self, spans: &'b mut Vec<Vec<Span>>,value: Value, indent: usize) {
-        // When we get newline we push a new Vec<Span> to spans
-        // We work only on the last Vec<Span> of spans
+    // A `:N` line number or a `z` command is being typed
+    pub fn pending_input(&self) -> bool {
+        self.goto.is_some() || self.pending_z
+    }
 
-        let style_key = Style::default()
-            .fg(self.config.json.json_key)
-            .bg(self.config.color.background);
-        let style_string = Style::default()
-            .fg(self.config.json.json_string)
-            .bg(self.config.color.background);
-        let style_number = Style::default()
-            .fg(self.config.json.json_number)
-            .bg(self.config.color.background);
-        let style_boolean = Style::default()
-            .fg(self.config.json.json_boolean)
-            .bg(self.config.color.background);
-        let style_null = Style::default()
-            .fg(self.config.json.json_null)
-            .bg(self.config.color.background);
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    pub fn set_right_title(&mut self, right_title: Option<String>) {
+        self.right_title = right_title;
+    }
+
+    // Style one line of the document
+    fn line(&self, index: usize) -> Line<'a> {
         let style_default = Style::default()
             .fg(self.config.color.foreground)
             .bg(self.config.color.background);
-        let indent_increment = self.config.json.indent;
-
-        match value {
-            Value::Null => {
-                spans.last_mut().unwrap().push(Span::styled(
-                    "null",
-                    style_null
-                ));
-            },
-            Value::Bool(b) => {
-                spans.last_mut().unwrap().push(Span::styled(
-                    b.to_string(),
-                    style_boolean
-                ));                
-            },
-            Value::Number(n) => {
-                spans.last_mut().unwrap().push(Span::styled(
-                    n.to_string(),
-                    style_number
-                ));
-            },
-            Value::String(s) => {
-                spans.last_mut().unwrap().push(Span::styled(
-                    format!("\"{}\"", s),
-                    style_string
+        let (document, doc_line) = match &self.content {
+            Content::Document { document, lines } => (document, lines[index]),
+            Content::Text { lines, is_error } => {
+                return Line::from(Span::styled(
+                    lines[index].clone(),
+                    style_default.fg(
+                        if *is_error {self.config.color.invalid_foreground}
+                        else {self.config.color.valid_foreground}
+                    )
                 ));
+            }
+        };
+        let node = document.node(doc_line.node);
+
+        let mut spans = vec![Span::styled(
+            " ".repeat(node.depth as usize * self.config.json.indent),
+            style_default
+        )];
+        if let (Key::Name(name), false) = (&node.key, doc_line.part == Part::Close) {
+            let key_style = style_default.fg(self.config.json.json_key);
+            spans.push(Span::styled("\"", key_style));
+            self.push_escaped(&mut spans, name, key_style);
+            spans.push(Span::styled("\": ", key_style));
+        }
+        match (&node.kind, doc_line.part) {
+            (Kind::Array, Part::Open) => spans.push(Span::styled("[", style_default)),
+            (Kind::Object, Part::Open) => spans.push(Span::styled("{", style_default)),
+            (Kind::Array, Part::Close) => spans.push(Span::styled("]", style_default)),
+            (Kind::Object, Part::Close) => spans.push(Span::styled("}", style_default)),
+            (Kind::Array, _) if node.children == 0 => spans.push(Span::styled("[]", style_default)),
+            (Kind::Object, _) if node.children == 0 => spans.push(Span::styled("{}", style_default)),
+            // Folded
+            (Kind::Array, _) => spans.push(Span::styled(
+                format!("[…] ({} items)", node.children),
+                style_default.add_modifier(Modifier::DIM)
+            )),
+            (Kind::Object, _) => spans.push(Span::styled(
+                format!("{{…}} ({} keys)", node.children),
+                style_default.add_modifier(Modifier::DIM)
+            )),
+            (Kind::Null, _) => spans.push(Span::styled("null", style_default.fg(self.config.json.json_null))),
+            (Kind::Bool(b), _) => spans.push(Span::styled(b.to_string(), style_default.fg(self.config.json.json_boolean))),
+            (Kind::Number(n), _) => spans.push(Span::styled(n.to_string(), style_default.fg(self.config.json.json_number))),
+            (Kind::String(s), _) => {
+                self.push_string(&mut spans, s, style_default.fg(self.config.json.json_string));
             },
-            Value::Array(a) => {
-                let mut last_span = spans.last_mut().unwrap();
-                //Push [
-                last_span.push(Span::styled(
-                    "[",
-                    style_default
-                ));
-                for (i, v) in a.iter().enumerate() {
-                    spans.push(Vec::new());
-                    //Push indent
-                    spans.last_mut().unwrap().push(Span::styled(
-                        format!("{:indent$}", "", indent = indent + indent_increment),
-                        style_default
-                    ));
-                    self.recursive_parser(spans, v.clone(), indent + indent_increment);
-                    if i < a.len() - 1 {
-                        spans.last_mut().unwrap().push(Span::styled(
-                            ",",
-                            style_default
-                        ));
+        }
+        if !node.last && doc_line.part != Part::Open {
+            spans.push(Span::styled(",", style_default));
+        }
+        Line::from(spans)
+    }
+
+    // Both ends of a string to show, ie: "eyJhbG…Q0In0",
+    // the tail is None when the whole string is shown
+    fn elided<'s>(&self, s: &'s str) -> (&'s str, Option<&'s str>) {
+        let length = s.chars().count();
+        let max_length = self.config.json.elide_length;
+        if !self.elide || length <= max_length {
+            return (s, None);
+        }
+        let head = max_length / 2;
+        let tail = max_length.saturating_sub(head + 1);
+        let byte_index = |chars: usize| s.char_indices().nth(chars).map_or(s.len(), |(index, _)| index);
+        (&s[..byte_index(head)], Some(&s[byte_index(length - tail)..]))
+    }
+
+    // Push the spans of escaped text, the escapes in their own colour
+    fn push_escaped(&self, spans: &mut Vec<Span<'a>>, text: &str, style: Style) {
+        let mut plain = String::new();
+        for c in text.chars() {
+            match escape_char(c, self.escape_non_printable) {
+                Some(escape) => {
+                    if !plain.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut plain), style));
+                    }
+                    spans.push(Span::styled(escape, style.fg(self.config.json.json_escape)));
+                },
+                None => plain.push(c),
+            }
+        }
+        if !plain.is_empty() {
+            spans.push(Span::styled(plain, style));
+        }
+    }
+
+    // Push the spans of a quoted string, possibly elided
+    fn push_string(&self, spans: &mut Vec<Span<'a>>, s: &str, style: Style) {
+        let (head, tail) = self.elided(s);
+        spans.push(Span::styled("\"", style));
+        self.push_escaped(spans, head, style);
+        if let Some(tail) = tail {
+            spans.push(Span::styled("…", style));
+            self.push_escaped(spans, tail, style);
+        }
+        spans.push(Span::styled("\"", style));
+    }
+
+    fn string_width(&self, s: &str) -> usize {
+        let (head, tail) = self.elided(s);
+        let width = escaped_width(head, self.escape_non_printable) + 2;
+        match tail {
+            Some(tail) => width + 1 + escaped_width(tail, self.escape_non_printable),
+            None => width,
+        }
+    }
+
+    // Width of a line in characters, without styling it
+    fn line_width(&self, document: &Document, doc_line: DocLine) -> usize {
+        let node = document.node(doc_line.node);
+        let mut width = node.depth as usize * self.config.json.indent;
+        if let (Key::Name(name), false) = (&node.key, doc_line.part == Part::Close) {
+            width += escaped_width(name, self.escape_non_printable) + 4;
+        }
+        width += match (&node.kind, doc_line.part) {
+            (Kind::Array | Kind::Object, Part::Open | Part::Close) => 1,
+            (Kind::Array | Kind::Object, _) if node.children == 0 => 2,
+            (Kind::Array, _) => format!("[…] ({} items)", node.children).chars().count(),
+            (Kind::Object, _) => format!("{{…}} ({} keys)", node.children).chars().count(),
+            (Kind::Null, _) => 4,
+            (Kind::Bool(b), _) => if *b {4} else {5},
+            (Kind::Number(n), _) => n.len(),
+            (Kind::String(s), _) => self.string_width(s),
+        };
+        if !node.last && doc_line.part != Part::Open {
+            width += 1;
+        }
+        width
+    }
+
+    pub fn json(&self) -> &str {
+        &self.raw
+    }
+
+    // The document shown, None if the content is not a single JSON value
+    pub fn document(&self) -> Option<&Document> {
+        match &self.content {
+            Content::Document { document, lines: _ } => Some(document),
+            Content::Text { lines: _, is_error: _ } => None,
+        }
+    }
+
+    pub fn set_json(&mut self, json: String) {
+        let cached = self.documents
+            .iter()
+            .find(|(raw, _)| *raw == json)
+            .map(|(_, document)| Rc::clone(document));
+        self.raw = json;
+        let document = match cached {
+            Some(document) => Ok(document),
+            None => Document::parse(&self.raw).map(|document| {
+                let document = Rc::new(document);
+                if self.raw.len() <= DOCUMENT_CACHE_MAX_BYTES {
+                    if self.documents.len() == DOCUMENT_CACHE_SIZE {
+                        self.documents.pop_front();
                     }
+                    self.documents.push_back((self.raw.clone(), Rc::clone(&document)));
                 }
-                spans.push(Vec::new());
-                last_span = spans.last_mut().unwrap();
-                //Push indent
-                last_span.push(Span::styled(
-                    format!("{:indent$}", "", indent = indent),
-                    style_default
-                ));
-                //Push ]
-                last_span.push(Span::styled(
-                    "]",
-                    style_default
-                ));
-            },
-            Value::Object(o) => {
-                let mut last_span = spans.last_mut().unwrap();
-                //Push {
-                last_span.push(Span::styled(
-                    "{",
-                    style_default
-                ));
+                document
+            }),
+        };
+        self.content = match document {
+            Ok(document) => Content::Document { document, lines: Vec::new() },
+            Err(_) => {
+                // Error messages can span several lines (ie: jq compile errors)
+                let mut lines: Vec<String> = self.raw.lines().map(|line| line.to_string()).collect();
+                if lines.is_empty() {
+                    lines.push(String::new());
+                }
+                Content::Text { lines, is_error: self.raw.starts_with("Error: ") }
+            }
+        };
+        self.highlight_paths.clear();
+        self.violation_paths.clear();
+        self.render_lines();
+        self.build_table();
+        self.cursor = 0; // Reset cursor position
+        self.offset.set(0);
+        // TO AVOID LITTLE NOT FUNNY BUGS
+        // FUCK ME
+    }
 
-                for (i, (k, v)) in o.iter().enumerate() {
-                    spans.push(Vec::new());
-                    //Push indent
-                    spans.last_mut().unwrap().push(Span::styled(
-                        format!("{:indent$}", "", indent = indent + indent_increment),
-                        style_default
-                    ));
-                    //Push key
-                    spans.last_mut().unwrap().push(Span::styled(
-                        format!("\"{}\": ", k),
-                        style_key
-                    ));
-                    self.recursive_parser(spans, v.clone(), indent + indent_increment);
-                    if i < o.len() - 1 {
-                        spans.last_mut().unwrap().push(Span::styled(
-                            ",",
-                            style_default
-                        ));
+    // Show plain text (ie: raw output), even if it is valid JSON
+    pub fn set_text(&mut self, text: String) {
+        // Tabs (ie: @tsv) take no room once drawn, expand them
+        let mut lines: Vec<String> = text
+            .lines()
+            .map(|line| {
+                let mut expanded = String::new();
+                for c in line.chars() {
+                    if c == '\t' {
+                        let width = expanded.chars().count();
+                        expanded.push_str(&" ".repeat(TAB_WIDTH - width % TAB_WIDTH));
+                    } else {
+                        expanded.push(c);
                     }
                 }
-                spans.push(Vec::new());
-                last_span = spans.last_mut().unwrap();
-                //Push indent
-                last_span.push(Span::styled(
-                    format!("{:indent$}", "", indent = indent),
-                    style_default
-                ));
-                //Push }
-                last_span.push(Span::styled(
-                    "}",
-                    style_default
-                ));
+                expanded
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        self.raw = text;
+        self.content = Content::Text { lines, is_error: false };
+        self.table = None;
+        self.highlight_paths.clear();
+        self.violation_paths.clear();
+        self.render_lines();
+        self.cursor = 0;
+        self.offset.set(0);
+    }
 
+    // Index the lines of the content, with the folded nodes collapsed
+    fn render_lines(&mut self) {
+        let (lines_count, max_width) = match &mut self.content {
+            Content::Document { document, lines } => {
+                let folded = self.folded
+                    .iter()
+                    .filter_map(|key| serde_json::from_str::<Vec<Value>>(key).ok())
+                    .filter_map(|path| document.find(&path))
+                    .collect();
+                let (document_lines, ranges) = document.lines(&folded);
+                *lines = document_lines;
+                self.ranges = ranges;
+                (lines.len(), 0)
+            },
+            Content::Text { lines, is_error: _ } => {
+                self.ranges.clear();
+                (lines.len(), lines.iter().map(|line| line.chars().count()).max().unwrap_or(0))
+            },
+        };
+        self.json_lines_count = lines_count;
+        self.max_width = match &self.content {
+            Content::Document { document, lines } => lines
+                .iter()
+                .map(|line| self.line_width(document, *line))
+                .max()
+                .unwrap_or(0),
+            Content::Text { lines: _, is_error: _ } => max_width,
+        };
+        self.hscroll = std::cmp::min(self.hscroll, self.max_width.saturating_sub(1));
+        self.highlighted.clear();
+        self.highlight_starts.clear();
+        self.highlighted_count = 0;
+    }
+
+    // Highlight the nodes at the given paths (as output by jq's `path(...)`)
+    pub fn set_highlights(&mut self, paths: &[Value]) {
+        self.highlight_paths = paths.to_vec();
+        self.apply_highlights();
+    }
+
+    // Validation result of the content, None without a schema. The nodes
+    // of the first value failing the schema are marked.
+    pub fn set_validation(&mut self, violations: Option<&[Violation]>) {
+        self.validation = violations.map(|violations| match violations.len() {
+            0 => ("✓ valid".to_string(), true),
+            1 => ("✗ 1 error".to_string(), false),
+            MAX_VIOLATIONS => (format!("✗ {}+ errors", MAX_VIOLATIONS), false),
+            count => (format!("✗ {} errors", count), false),
+        });
+        self.violation_paths = violations
+            .unwrap_or_default()
+            .iter()
+            .filter(|violation| violation.value == 0)
+            .map(|violation| violation.path.clone())
+            .collect();
+        self.apply_highlights();
+    }
+
+    // Move the cursor to the node at `path`
+    pub fn select_path(&mut self, path: &[Value]) {
+        let node = match &self.content {
+            Content::Document { document, lines: _ } => document.find(path),
+            Content::Text { lines: _, is_error: _ } => None,
+        };
+        if let Some(node) = node {
+            if let (true, Some(table)) = (self.table_mode, &mut self.table) {
+                table.select_node(node);
+            } else {
+                self.move_cursor_to(self.ranges[node].0);
+            }
+        }
+    }
 
+    fn apply_highlights(&mut self) {
+        self.highlighted.clear();
+        self.highlight_starts.clear();
+        self.highlighted_count = 0;
+        self.violation_lines.clear();
+        let document = match &self.content {
+            Content::Document { document, lines: _ } => document,
+            Content::Text { lines: _, is_error: _ } => return,
+        };
+        self.violation_lines = self.violation_paths
+            .iter()
+            .filter_map(|path| document.find(path))
+            .map(|node| self.ranges[node].0)
+            .collect();
+        self.violation_lines.sort_unstable();
+        self.violation_lines.dedup();
+        let mut ranges = Vec::new();
+        for path in &self.highlight_paths {
+            let path = match path {
+                Value::Array(path) => path,
+                _ => continue,
+            };
+            // A node hidden in a folded one gets the line of the folded node
+            if let Some(node) = document.find(path) {
+                ranges.push(self.ranges[node]);
+                self.highlighted_count += 1;
+            }
+        }
+        ranges.sort_unstable();
+        self.highlight_starts = ranges.iter().map(|(start, _)| *start).collect();
+        self.highlight_starts.dedup();
+        for (start, end) in ranges {
+            match self.highlighted.last_mut() {
+                Some(last) if start <= last.1 + 1 => {
+                    last.1 = std::cmp::max(last.1, end);
+                },
+                _ => self.highlighted.push((start, end)),
             }
+        }
+    }
+
+    fn is_highlighted(&self, line: usize) -> bool {
+        let i = self.highlighted.partition_point(|(_, end)| *end < line);
+        self.highlighted.get(i).is_some_and(|(start, _)| *start <= line)
+    }
+
+    // Move the cursor to the start of the next (or previous) highlighted node
+    fn jump_to_highlight(&mut self, forward: bool) {
+        let starts = &self.highlight_starts;
+        let next = if forward {
+            starts.iter().find(|line| **line > self.cursor).or(starts.first())
+        } else {
+            starts.iter().rev().find(|line| **line < self.cursor).or(starts.last())
         };
+        if let Some(line) = next {
+            self.cursor = *line;
+        }
     }
 
-    fn pretty_json(&mut self) -> Option<Vec<Line<'a>>> {
-        match serde_json::from_str(&self.raw) {
-            Ok(serde_json_value) => {
-                
-                let mut spans: Vec<Vec<Span>> = Vec::new();
-                spans.push(Vec::new());
-                self.recursive_parser(&mut spans, serde_json_value, 0);
-                let mut lines: Vec<Line> = Vec::new();
-                for span in spans {
-                    lines.push(Line::from(span));
+    // Node which can be folded or unfolded on `line`, and whether it is folded
+    fn foldable(&self, line: usize) -> Option<(usize, bool)> {
+        let (document, doc_line) = match &self.content {
+            Content::Document { document, lines } => (document, lines.get(line)?),
+            Content::Text { lines: _, is_error: _ } => return None,
+        };
+        let node = document.node(doc_line.node);
+        match doc_line.part {
+            Part::Open => Some((doc_line.node, false)),
+            Part::Whole if node.is_container() && node.children > 0 => Some((doc_line.node, true)),
+            _ => None,
+        }
+    }
+
+    // Collapse or expand the node starting on `line`
+    fn toggle_fold(&mut self, line: usize) {
+        let key = match (&self.content, self.foldable(line)) {
+            (Content::Document { document, lines: _ }, Some((node, _))) => path_key(&document.path(node)),
+            _ => return,
+        };
+        if !self.folded.remove(&key) {
+            self.folded.insert(key);
+        }
+        self.render_lines();
+        self.apply_highlights();
+        // Lines above the node are unchanged
+        self.cursor = line;
+    }
+
+    // The node starting on the cursor line, or the cell under the cursor of the table
+    fn cursor_node(&self) -> Option<(&Document, usize)> {
+        match &self.content {
+            Content::Document { document, lines: _ } if self.table_mode => {
+                Some((document, self.table.as_ref()?.cursor_node()?))
+            },
+            Content::Document { document, lines } => {
+                let doc_line = lines.get(self.cursor)?;
+                match doc_line.part {
+                    Part::Close => None,
+                    _ => Some((document, doc_line.node)),
                 }
-                Some(lines)
             },
-            Err(_) => {
-                return None
+            Content::Text { lines: _, is_error: _ } => None,
+        }
+    }
+
+    // Index in the document of the node starting on the cursor line
+    pub fn cursor_index(&self) -> Option<usize> {
+        self.cursor_node().map(|(_, node)| node)
+    }
+
+    // Path of the node starting on the cursor line, as a jq expression
+    pub fn cursor_path(&self) -> Option<String> {
+        self.cursor_node().map(|(document, node)| jq_path(&document.path(node)))
+    }
+
+    // The node starting on the cursor line, with its path as a jq expression
+    pub fn cursor_value(&self) -> Option<(String, Value)> {
+        self.cursor_node().map(|(document, node)| {
+            (jq_path(&document.path(node)), document.to_value(node))
+        })
+    }
+
+    // Build the table of the content, keeping the hidden columns and the sort
+    fn build_table(&mut self) {
+        self.table = match &self.content {
+            Content::Document { document, lines: _ } if self.table_mode => {
+                TableView::new(Rc::clone(document), self.config, self.table.as_ref())
+            },
+            _ => None,
+        };
+    }
+
+    // Switch between the tree and the table, keeping the same node under the cursor
+    fn toggle_table(&mut self) {
+        let node = self.cursor_node().map(|(_, node)| node);
+        self.table_mode = !self.table_mode;
+        if self.table_mode {
+            self.build_table();
+            if let (Some(table), Some(node)) = (&mut self.table, node) {
+                table.select_node(node);
             }
+        } else if let Some((start, _)) = node.and_then(|node| self.ranges.get(node)) {
+            self.move_cursor_to(*start);
         }
     }
 
-    pub fn json(&self) -> &str {
-        &self.raw
+    pub fn table_active(&self) -> bool {
+        self.table_mode && self.table.is_some()
     }
 
-    pub fn set_json(&mut self, json: String) {
-        let json_text: Vec<Line>;
-        self.raw = json;
-        self.json = match self.pretty_json() {
-            Some(pretty_json) => pretty_json,
-            None => {
-                let is_error = {
-                    // Check if the string starts with "Error: "
-                    let error_prefix = "Error: ";
-                    self.raw.starts_with(error_prefix)
-                };
-                json_text = vec!(Line::from(Span::styled(
-                        self.raw.clone(),
-                        Style::default()
-                            .fg(
-                                if is_error {self.config.color.invalid_foreground}
-                                else {self.config.color.valid_foreground}
-                            )
-                            .bg(self.config.color.background)
-                    )));
-                json_text
+    // The sort of the table as a jq expression, the table goes back to the
+    // document order since the query now sorts it
+    pub fn take_table_sort(&mut self) -> Option<String> {
+        let table = self.table.as_mut()?;
+        let expression = table.sort_expression()?;
+        table.clear_sort();
+        Some(expression)
+    }
+
+    fn scroll_horizontally(&mut self, right: bool, columns: usize) {
+        self.hscroll = if right {
+            std::cmp::min(self.hscroll + columns, self.max_width.saturating_sub(1))
+        } else {
+            self.hscroll.saturating_sub(columns)
+        };
+    }
+
+    // Lines shown in a pane of `max_lines` lines, and the cursor position in it.
+    // The view only scrolls to keep SCROLL_OFF lines around the cursor.
+    fn window(&self, max_lines: usize) -> (usize, usize, usize) {
+        let json_lines = self.json_lines_count;
+        let margin = std::cmp::min(SCROLL_OFF, max_lines.saturating_sub(1) / 2);
+        let mut first_line = self.offset.get();
+        if self.cursor < first_line + margin {
+            first_line = self.cursor.saturating_sub(margin);
+        }
+        if self.cursor + margin >= first_line + max_lines {
+            first_line = (self.cursor + margin + 1).saturating_sub(max_lines);
+        }
+        first_line = std::cmp::min(first_line, json_lines.saturating_sub(max_lines));
+        self.offset.set(first_line);
+        self.height.set(max_lines);
+        (first_line, std::cmp::min(first_line + max_lines, json_lines), self.cursor - first_line)
+    }
+
+    fn move_cursor_to(&mut self, line: usize) {
+        self.cursor = std::cmp::min(line, self.json_lines_count.saturating_sub(1));
+    }
+
+    // Keys typed after `:`, digits then Enter to jump, Esc to cancel
+    fn handle_goto_event(&mut self, event: &event::KeyEvent) {
+        let goto = match &mut self.goto {
+            Some(goto) => goto,
+            None => return,
+        };
+        match event.code {
+            event::KeyCode::Char(c) if c.is_ascii_digit() => {
+                goto.push(c);
+            },
+            event::KeyCode::Backspace if goto.is_empty() => {
+                self.goto = None;
+            },
+            event::KeyCode::Backspace => {
+                goto.pop();
+            },
+            event::KeyCode::Enter => {
+                // Lines are numbered from 1 in the title
+                if let Ok(line) = goto.parse::<usize>() {
+                    self.move_cursor_to(line.saturating_sub(1));
+                }
+                self.goto = None;
+            },
+            event::KeyCode::Esc => {
+                self.goto = None;
             },
+            _ => {}
+        }
+    }
+
+    // Handle a click at the given terminal cell, `area` is where the widget was drawn.
+    // A click on a line moves the cursor there, on a fold marker it toggles the fold.
+    pub fn click(&mut self, area: Rect, column: u16, row: u16) {
+        if let (true, Some(table)) = (self.table_mode, &mut self.table) {
+            table.click(area, row);
+            return;
+        }
+        if row <= area.y || row + 1 >= area.y + area.height {
+            return;
+        }
+        let line = match self.row_lines.borrow().get((row - area.y - 1) as usize) {
+            Some(line) => *line,
+            None => return,
         };
-        self.json_lines_count = self.json.len();
-        self.cursor = 0; // Reset cursor position
-        // TO AVOID LITTLE NOT FUNNY BUGS
-        // FUCK ME
+        self.cursor = line;
+        // The border, then the cursor column, then the fold marker
+        if column == area.x + 2 {
+            self.toggle_fold(line);
+        }
+    }
+
+    pub fn scroll(&mut self, down: bool) {
+        if let (true, Some(table)) = (self.table_mode, &mut self.table) {
+            table.scroll(down);
+            return;
+        }
+        if down {
+            self.move_cursor_to(self.cursor + SCROLL_LINES);
+        } else {
+            self.move_cursor_to(self.cursor.saturating_sub(SCROLL_LINES));
+        }
+    }
+
+    // Lines shown from `first_line` as rows of `width` characters, with the
+    // line index of each row. Without wrapping the lines are scrolled horizontally.
+    fn rows(&self, first_line: usize, max_rows: usize, width: usize) -> Vec<(usize, Line<'a>)> {
+        let mut rows = Vec::new();
+        for i in first_line..self.json_lines_count {
+            let line = self.line(i);
+            if self.wrap {
+                for row in wrap_line(&line, width) {
+                    rows.push((i, row));
+                }
+            } else {
+                rows.push((i, skip_columns(&line, self.hscroll)));
+            }
+            if rows.len() >= max_rows {
+                break;
+            }
+        }
+        rows.truncate(max_rows);
+        rows
     }
 
-    fn process_json_content(&self, max_lines: usize) -> Text<'a> {
+    fn process_json_content(&self, max_lines: usize, width: usize) -> Text<'a> {
         let cursor_style = Style::default()
             .fg(
                 if self.selected {
@@ -260,46 +879,107 @@ impl<'a> Json<'a> {
                 }
             )
             .bg(self.config.color.background);
-        
-        // If the cursor is on first line of the chunk
-        // we need to add a line before the chunk
-        // Else if the cursor is on the last line of the chunk
-        // we need to add a line after the chunk
-        // If cursor is at first line of content we don't need to add a line before
-        // Same if cursor is at last line of content we don't need to add a line after
-
-        let json_lines = self.json_lines_count;
 
-        let (first_line, last_line, cursor_pos) = match (self.cursor / max_lines, self.cursor % max_lines) {
-            (0, context_cursor) => {
-                (0, std::cmp::min(max_lines, json_lines), context_cursor)
+        let (mut first_line, _, _) = self.window(max_lines);
+        if self.wrap {
+            // Wrapped lines take several rows, scroll until the cursor line fits
+            let row_count = |line: usize| wrap_line(&self.line(line), width).len();
+            let mut rows_to_cursor: usize = (first_line..=self.cursor).map(row_count).sum();
+            while rows_to_cursor > max_lines && first_line < self.cursor {
+                rows_to_cursor -= row_count(first_line);
+                first_line += 1;
             }
-            (chunk, context) if chunk == json_lines => {
-                ((json_lines - max_lines), (json_lines), context)
-            }
-            (chunk, context) => {
-                ((chunk * max_lines), std::cmp::min((chunk + 1) * max_lines, json_lines), context)
-            }
-        };
+            self.offset.set(first_line);
+        }
+
+        let highlight_marker_style = Style::default()
+            .fg(self.config.color.highlight_marker)
+            .bg(self.config.color.background);
+        let violation_marker_style = Style::default()
+            .fg(self.config.color.invalid_foreground)
+            .bg(self.config.color.background);
+
+        let rows = self.rows(first_line, max_lines, width);
+        *self.row_lines.borrow_mut() = rows.iter().map(|(line, _)| *line).collect();
 
-        let mut content = self.json[first_line..last_line].to_vec();
-        for (i, line) in content.iter_mut().enumerate() {
+        let mut content = Vec::new();
+        let mut previous_line = None;
+        for (line_index, mut line) in rows {
+            let is_continuation = previous_line == Some(line_index);
+            previous_line = Some(line_index);
+            let is_highlighted = self.is_highlighted(line_index);
+            if is_highlighted {
+                for span in line.spans.iter_mut() {
+                    span.style = span.style.bg(self.config.color.highlight_background);
+                }
+            }
+            let is_violation = !is_continuation && self.violation_lines.binary_search(&line_index).is_ok();
+            line.spans.insert(
+                0,
+                match (is_violation, is_highlighted) {
+                    (true, _) => Span::styled("✗", violation_marker_style),
+                    (false, true) => Span::styled("▌", highlight_marker_style),
+                    (false, false) => Span::styled(" ", highlight_marker_style),
+                }
+            );
+            line.spans.insert(
+                0,
+                Span::styled(
+                    match self.foldable(line_index) {
+                        _ if is_continuation => " ",
+                        Some((_, true)) => "▸",
+                        Some((_, false)) => "▾",
+                        None => " ",
+                    },
+                    Style::default()
+                        .fg(self.config.color.foreground)
+                        .bg(self.config.color.background)
+                )
+            );
             line.spans.insert(
                 0,
                 Span::styled(
-                    if i == cursor_pos {
-                        "▷ "
+                    if line_index == self.cursor && !is_continuation {
+                        "▷"
                     } else {
-                        "  "
+                        " "
                     },
                     cursor_style
                 )
-            )
+            );
+            content.push(line);
         }
         Text::from(content)
     }
 
     pub fn handle_event(&mut self, event: &event::KeyEvent) -> () {
+        if self.goto.is_some() {
+            self.handle_goto_event(event);
+            return;
+        }
+        if self.pending_z {
+            self.pending_z = false;
+            match event.code {
+                event::KeyCode::Char('h') => self.scroll_horizontally(false, 1),
+                event::KeyCode::Char('l') => self.scroll_horizontally(true, 1),
+                _ => {}
+            }
+            return;
+        }
+        if let event::KeyEvent{ // Handle t: toggle the table view
+            code: event::KeyCode::Char('t'),
+            modifiers: event::KeyModifiers::NONE,
+            kind: _,
+            state: _,
+        } = event {
+            self.toggle_table();
+            return;
+        }
+        if let (true, Some(table)) = (self.table_mode, &mut self.table) {
+            table.handle_event(event);
+            return;
+        }
+        let page = self.height.get();
         match event {
             event::KeyEvent { // Handle keyboard up
                 code: event::KeyCode::Up,
@@ -307,9 +987,7 @@ impl<'a> Json<'a> {
                 kind: _,
                 state: _,
             } => {
-                if self.cursor > 0 {
-                    self.cursor -= 1;
-                }
+                self.move_cursor_to(self.cursor.saturating_sub(1));
             },
             event::KeyEvent{ // Handle keyboard page up
                 code: event::KeyCode::PageUp,
@@ -317,11 +995,7 @@ impl<'a> Json<'a> {
                 kind: _,
                 state: _,
             } => {
-                if self.cursor > 10 {
-                    self.cursor -= 10;
-                } else {
-                    self.cursor = 0;
-                }
+                self.move_cursor_to(self.cursor.saturating_sub(page));
             },
             event::KeyEvent { // Handle keyboard down
                 code: event::KeyCode::Down,
@@ -329,9 +1003,7 @@ impl<'a> Json<'a> {
                 kind: _,
                 state: _,
             } => {
-                if self.cursor < self.json_lines_count - 1 {
-                    self.cursor += 1;
-                }
+                self.move_cursor_to(self.cursor + 1);
             },
             event::KeyEvent{ // Handle keyboard page down
                 code: event::KeyCode::PageDown,
@@ -339,11 +1011,125 @@ impl<'a> Json<'a> {
                 kind: _,
                 state: _,
             } => {
-                if self.cursor < self.json_lines_count - 10 {
-                    self.cursor += 10;
-                } else {
-                    self.cursor = self.json_lines_count - 1;
-                }
+                self.move_cursor_to(self.cursor + page);
+            },
+            event::KeyEvent{ // Handle Ctrl-U: half a page up
+                code: event::KeyCode::Char('u'),
+                modifiers: event::KeyModifiers::CONTROL,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor.saturating_sub(std::cmp::max(page / 2, 1)));
+            },
+            event::KeyEvent{ // Handle Ctrl-D: half a page down
+                code: event::KeyCode::Char('d'),
+                modifiers: event::KeyModifiers::CONTROL,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor + std::cmp::max(page / 2, 1));
+            },
+            event::KeyEvent { // Handle keyboard left
+                code: event::KeyCode::Left,
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll_horizontally(false, HSCROLL_COLUMNS);
+            },
+            event::KeyEvent { // Handle keyboard right
+                code: event::KeyCode::Right,
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll_horizontally(true, HSCROLL_COLUMNS);
+            },
+            event::KeyEvent{ // Handle z: zh/zl scroll by one column
+                code: event::KeyCode::Char('z'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.pending_z = true;
+            },
+            event::KeyEvent{ // Handle w: toggle soft wrap
+                code: event::KeyCode::Char('w'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.wrap = !self.wrap;
+            },
+            event::KeyEvent{ // Handle e: toggle eliding long strings
+                code: event::KeyCode::Char('e'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.elide = !self.elide;
+                self.render_lines();
+                self.apply_highlights();
+                self.move_cursor_to(self.cursor);
+            },
+            event::KeyEvent{ // Handle u: show the invisible characters escaped
+                code: event::KeyCode::Char('u'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.escape_non_printable = !self.escape_non_printable;
+                self.render_lines();
+                self.apply_highlights();
+                self.move_cursor_to(self.cursor);
+            },
+            event::KeyEvent{ // Handle g: first line
+                code: event::KeyCode::Char('g'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(0);
+            },
+            event::KeyEvent{ // Handle G: last line
+                code: event::KeyCode::Char('G'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.json_lines_count);
+            },
+            event::KeyEvent{ // Handle ':': go to a line
+                code: event::KeyCode::Char(':'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.goto = Some(String::new());
+            },
+            event::KeyEvent{ // Handle Enter/Space: fold or unfold the node
+                code: event::KeyCode::Enter | event::KeyCode::Char(' '),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.toggle_fold(self.cursor);
+            },
+            event::KeyEvent{ // Handle n: next selected node
+                code: event::KeyCode::Char('n'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.jump_to_highlight(true);
+            },
+            event::KeyEvent{ // Handle N: previous selected node
+                code: event::KeyCode::Char('N'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.jump_to_highlight(false);
             },
             _ => {}
         }
@@ -365,57 +1151,352 @@ impl Drawable for Json<'_> {
             Some(right_title) => right_title.clone(),
             None => String::new()
         };
-        let cursor_info: String = format!("{} / {}", self.cursor + 1, self.json_lines_count);
-        let height = (area.height - 2) as usize;
+        let cursor_info: String = if let Some(goto) = &self.goto {
+            format!("Go to line: {}", goto)
+        } else if let (true, Some(table)) = (self.table_mode, &self.table) {
+            table.info()
+        } else {
+            let mut info = format!("{} / {}", self.cursor + 1, self.json_lines_count);
+            if self.highlighted_count > 0 {
+                info = format!("{} selected · {}", self.highlighted_count, info);
+            }
+            if self.wrap {
+                info.push_str(" · wrap");
+            } else if self.hscroll > 0 {
+                info.push_str(&format!(" · col {}", self.hscroll + 1));
+            }
+            if self.table_mode {
+                info.push_str(" · not an array of objects");
+            }
+            info
+        };
+        let height = std::cmp::max(area.height.saturating_sub(2) as usize, 1);
         
         
-        let content = Paragraph::new(self.process_json_content(height))
-            .block(Block::default()
-                .title(
+        let width = area.width.saturating_sub(2 + GUTTER_WIDTH) as usize;
+        let mut title = vec![
+            Span::styled(
+                self.title.as_str(),
+                Style::default()
+                    .fg(if self.selected {selected_fg_color} else {fg_color})
+                    .bg(bg_color)
+            )
+        ];
+        if let Some((status, valid)) = &self.validation {
+            title.push(Span::styled(" · ", Style::default().fg(fg_color).bg(bg_color)));
+            title.push(Span::styled(
+                status.as_str(),
+                Style::default()
+                    .fg(if *valid {self.config.color.valid_foreground} else {self.config.color.invalid_foreground})
+                    .bg(bg_color)
+            ));
+        }
+        let block = Block::default()
+            .title(Line::from(title))
+            .title(
+                Title::from(
                     Span::styled(
-                        self.title.as_str(),
+                        right_title.as_str(),
                         Style::default()
                             .fg(if self.selected {selected_fg_color} else {fg_color})
                             .bg(bg_color)
                     )
                 )
-                .title(
-                    Title::from(
-                        Span::styled(
-                            right_title.as_str(),
-                            Style::default()
-                                .fg(if self.selected {selected_fg_color} else {fg_color})
-                                .bg(bg_color)
-                        )
-                    )
-                    .alignment(Alignment::Right)
-                )
-                .title(
-                    Title::from(
-                        Span::styled(
-                            cursor_info.as_str(),
-                            Style::default()
-                                .fg(if self.selected {selected_fg_color} else {fg_color})
-                                .bg(bg_color)
-                        )
+                .alignment(Alignment::Right)
+            )
+            .title(
+                Title::from(
+                    Span::styled(
+                        cursor_info.as_str(),
+                        Style::default()
+                            .fg(if self.selected {selected_fg_color} else {fg_color})
+                            .bg(bg_color)
                     )
-                    .alignment(Alignment::Right)
-                    .position(Position::Bottom)
-                )
-                .borders(Borders::ALL)
-                .border_style(
-                    Style::default()
-                        .fg(fg_color)
-                        .bg(bg_color)
                 )
+                .alignment(Alignment::Right)
+                .position(Position::Bottom)
             )
+            .borders(Borders::ALL)
+            .border_style(
+                Style::default()
+                    .fg(fg_color)
+                    .bg(bg_color)
+            );
+        if let (true, Some(table)) = (self.table_mode, &self.table) {
+            table.draw(f, area, block, self.selected);
+            return Ok(());
+        }
+        let content = Paragraph::new(self.process_json_content(height, width))
+            .block(block)
             .style(Style::default()
                 .fg(fg_color)
                 .bg(bg_color)
             );
         
         f.render_widget(content, area);
+
+        // On the right border, only when the content doesn't fit
+        if self.json_lines_count > height {
+            // The scrollbar state is limited to u16
+            let scale = self.json_lines_count / u16::MAX as usize + 1;
+            let mut state = ScrollbarState::default()
+                .content_length((self.json_lines_count / scale) as u16)
+                .viewport_content_length((height / scale) as u16)
+                .position((self.offset.get() / scale) as u16);
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None)
+                    .style(Style::default().fg(fg_color).bg(bg_color)),
+                area.inner(&Margin { vertical: 1, horizontal: 0 }),
+                &mut state
+            );
+        }
         Ok(())
         
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The text of every line of the pane
+    fn rendered(json: &Json) -> String {
+        (0..json.json_lines_count)
+            .map(|index| json.line(index).spans.iter().map(|span| span.content.as_ref()).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // The value parsed back from the rendered text
+    fn round_trip(value: &Value, non_printable: bool) -> (String, Value) {
+        let mut config = Config::new();
+        config.json.escape_non_printable = non_printable;
+        let json = Json::new(value.to_string(), String::new(), None, &config);
+        let text = rendered(&json);
+        let parsed = serde_json::from_str(&text).unwrap_or_else(|error| panic!("{}: {}", error, text));
+        (text, parsed)
+    }
+
+    fn assert_round_trip(value: Value) {
+        for non_printable in [false, true] {
+            let (text, parsed) = round_trip(&value, non_printable);
+            assert_eq!(parsed, value, "rendered as {}", text);
+            // C1 controls are only escaped in the `\u` mode
+            let is_escaped = |c: char| matches!(c, '\u{0}'..='\u{1f}' | '\u{7f}');
+            assert!(!text.lines().any(|line| line.contains(is_escaped)), "unescaped control in {:?}", text);
+        }
+    }
+
+    #[test]
+    fn escape_chars() {
+        assert_eq!(escape_char('\n', false).as_deref(), Some("\\n"));
+        assert_eq!(escape_char('\t', false).as_deref(), Some("\\t"));
+        assert_eq!(escape_char('"', false).as_deref(), Some("\\\""));
+        assert_eq!(escape_char('\\', false).as_deref(), Some("\\\\"));
+        assert_eq!(escape_char('\u{1}', false).as_deref(), Some("\\u0001"));
+        assert_eq!(escape_char('\u{7f}', false).as_deref(), Some("\\u007f"));
+        assert_eq!(escape_char('\u{200b}', false), None);
+        assert_eq!(escape_char('\u{200b}', true).as_deref(), Some("\\u200b"));
+        assert_eq!(escape_char('é', true), None);
+        assert_eq!(escape_char('😀', true), None);
+    }
+
+    #[test]
+    fn round_trip_newlines_and_tabs() {
+        assert_round_trip(serde_json::json!({"a\nb": "line 1\nline 2\r\n", "tab\t": "\tindented\t"}));
+    }
+
+    #[test]
+    fn round_trip_quotes_and_backslashes() {
+        assert_round_trip(serde_json::json!({"\"key\"": "say \"hi\"", "a\\b": "C:\\dir\\", "\\\"": "\\\""}));
+    }
+
+    #[test]
+    fn round_trip_control_characters() {
+        let controls: String = ('\u{0}'..='\u{1f}').chain(['\u{7f}', '\u{8}', '\u{c}']).collect();
+        assert_round_trip(serde_json::json!({controls.clone(): controls}));
+    }
+
+    #[test]
+    fn round_trip_non_bmp_characters() {
+        assert_round_trip(serde_json::json!({"😀": "𝄞 music, 🇫🇷 flag, 𠀀 ideograph", "é": "日本"}));
+    }
+
+    #[test]
+    fn round_trip_non_printable_escapes() {
+        let invisible = "zero\u{200b}width\u{ad}soft\u{feff}bom\u{85}next\u{202e}bidi\u{2028}separator";
+        let value = serde_json::json!({invisible: invisible});
+        assert_round_trip(value.clone());
+        let (text, _) = round_trip(&value, true);
+        for escape in ["\\u200b", "\\u00ad", "\\ufeff", "\\u0085", "\\u202e", "\\u2028"] {
+            assert!(text.contains(escape), "{} not in {}", escape, text);
+        }
+        let (text, _) = round_trip(&value, false);
+        assert!(text.contains('\u{200b}') && !text.contains("\\u200b"));
+    }
+
+    // The pane shows the keys in document order and the numbers as written
+    #[test]
+    fn tricky_document_as_written() {
+        let config = Config::new();
+        let json = Json::new(include_str!("../../../examples/tricky.json").to_string(), String::new(), None, &config);
+        let text = rendered(&json);
+        let keys: Vec<&str> = text.lines()
+            .filter(|line| line.starts_with("  \"") && !line.starts_with("   "))
+            .map(|line| &line[3..line.find("\":").unwrap()])
+            .collect();
+        assert_eq!(keys, [
+            "zebra", "apple", "", "with space", "with \\\"quotes\\\"", "with\\\\backslash",
+            "unicode é ü 日本", "escaped é", "10", "9", "numbers", "duplicated", "duplicated", "nested",
+        ]);
+        for line in [
+            "\"trailing_zero\": 1.10,",
+            "\"many_zeros\": 1.000000,",
+            "\"negative_zero\": -0,",
+            "\"exponent\": 1e+2,",
+            "\"pi\": 3.14159265358979323846264338327950288,",
+            "\"id\": 12345678901234567890,",
+            "\"max_safe\": 9007199254740993,",
+        ] {
+            assert!(text.lines().any(|rendered| rendered.trim() == line), "{} not in {}", line, text);
+        }
+    }
+
+    fn key(code: event::KeyCode) -> event::KeyEvent {
+        event::KeyEvent::new(code, event::KeyModifiers::NONE)
+    }
+
+    // The window stays in the lines whatever their count and the cursor
+    fn assert_window(json: &Json, max_lines: usize) {
+        let (first_line, last_line, cursor) = json.window(max_lines);
+        assert!(first_line <= last_line && last_line <= json.json_lines_count);
+        assert!(last_line - first_line <= max_lines);
+        if json.json_lines_count > 0 {
+            assert_eq!(first_line + cursor, json.cursor);
+            assert!(json.cursor < last_line);
+        }
+    }
+
+    #[test]
+    fn window_of_few_lines() {
+        let config = Config::new();
+        for payload in ["", "1", "[1, 2]", "{\"a\": [1, 2, 3]}"] {
+            let mut json = Json::new(payload.to_string(), String::new(), None, &config);
+            for max_lines in [1, 2, 5, 10, 20] {
+                assert_window(&json, max_lines);
+                for code in [
+                    event::KeyCode::PageDown,
+                    event::KeyCode::Down,
+                    event::KeyCode::Char('G'),
+                    event::KeyCode::PageUp,
+                    event::KeyCode::Up,
+                    event::KeyCode::Char('g'),
+                ] {
+                    json.handle_event(&key(code));
+                    assert_window(&json, max_lines);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn window_keeps_lines_around_cursor() {
+        let config = Config::new();
+        let payload = serde_json::to_string(&(0..100).collect::<Vec<u32>>()).unwrap();
+        let mut json = Json::new(payload, String::new(), None, &config);
+        // 102 lines with the brackets
+        assert_eq!(json.window(20), (0, 20, 0));
+        for _ in 0..16 {
+            json.handle_event(&key(event::KeyCode::Down));
+        }
+        assert_eq!(json.window(20), (0, 20, 16));
+        json.handle_event(&key(event::KeyCode::Down));
+        assert_eq!(json.window(20), (1, 21, 16));
+        // A page is the height of the last draw
+        json.handle_event(&key(event::KeyCode::PageDown));
+        assert_eq!(json.cursor, 37);
+        assert_eq!(json.window(20), (21, 41, 16));
+        json.handle_event(&key(event::KeyCode::PageUp));
+        assert_eq!(json.window(20), (14, 34, 3));
+        json.handle_event(&key(event::KeyCode::Char('G')));
+        assert_eq!(json.window(20), (82, 102, 19));
+    }
+
+    // About `size` bytes of records
+    fn generated(size: usize) -> String {
+        let mut raw = String::with_capacity(size + 256);
+        raw.push('[');
+        let mut i = 0;
+        while raw.len() < size {
+            if i > 0 {
+                raw.push(',');
+            }
+            raw.push_str(&format!(
+                r#"{{"id":{},"name":"item {}","price":{}.{:02},"tags":["a","b"],"nested":{{"ok":true,"note":null}}}}"#,
+                i, i, i % 1000, i % 100
+            ));
+            i += 1;
+        }
+        raw.push(']');
+        raw
+    }
+
+    // Load a generated document, draw its last page and highlight a node
+    // of every record. Run with `cargo test --release -- --ignored bench`,
+    // the memory used is about 25 times the size (ie: 25 GB for 1 GB).
+    fn bench(size: usize) {
+        let raw = generated(size);
+        let config = Config::new();
+
+        let start = std::time::Instant::now();
+        let mut json = Json::new(raw, String::new(), None, &config);
+        let loaded = start.elapsed();
+
+        let start = std::time::Instant::now();
+        json.handle_event(&key(event::KeyCode::Char('G')));
+        let (first_line, last_line, _) = json.window(50);
+        let page: Vec<Line> = (first_line..last_line).map(|index| json.line(index)).collect();
+        let drawn = start.elapsed();
+        assert_eq!(page.len(), 50);
+
+        let records = json.document().unwrap().node(0).children;
+        let paths: Vec<Value> = (0..records).map(|i| serde_json::json!([i, "price"])).collect();
+        let start = std::time::Instant::now();
+        json.set_highlights(&paths);
+        let highlighted = start.elapsed();
+        assert_eq!(json.highlighted_count, records);
+
+        // `"note": null` of the last record
+        for _ in 0..3 {
+            json.handle_event(&key(event::KeyCode::Up));
+        }
+        let start = std::time::Instant::now();
+        let path = json.cursor_node().map(|(document, node)| document.path(node));
+        let selected = start.elapsed();
+        assert_eq!(path, Some(vec![Value::from(records - 1), Value::from("nested"), Value::from("note")]));
+
+        eprintln!(
+            "{} MB, {} lines: loaded in {:?}, last page drawn in {:?}, {} paths highlighted in {:?}, cursor path in {:?}",
+            size >> 20, json.json_lines_count, loaded, drawn, records, highlighted, selected
+        );
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_10_mb() {
+        bench(10 << 20);
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_100_mb() {
+        bench(100 << 20);
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_1_gb() {
+        bench(1 << 30);
+    }
+}
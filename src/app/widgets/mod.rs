@@ -1,14 +1,41 @@
+pub mod apply;
+pub mod compare;
+pub mod diff;
+pub mod help;
 pub mod input;
 pub mod json;
 pub mod jq;
+pub mod picker;
+pub mod pipeline;
+pub mod prompt;
+pub mod shape;
+pub mod stats;
+pub mod status;
+pub mod table;
+pub mod timings;
+pub mod viewer;
 
 use std::fmt::Error;
 
 use ratatui::backend::Backend;
+use ratatui::layout::Rect;
 
+pub use apply::{ApplyEvent, ApplyView};
+pub use compare::Compare;
+pub use diff::Diff;
+pub use help::Help;
 pub use input::Input;
 pub use json::Json;
 pub use jq::Jq;
+pub use picker::{Picker, PickerEvent, PickerItem};
+pub use pipeline::Pipeline;
+pub use prompt::{Prompt, PromptEvent};
+pub use shape::{ShapeEvent, ShapeView};
+pub use stats::StatsView;
+pub use status::StatusBar;
+pub use table::TableView;
+pub use timings::Timings;
+pub use viewer::Viewer;
 
 pub trait Drawable {
     fn draw<B: Backend>(
@@ -17,3 +44,15 @@ pub trait Drawable {
         area: ratatui::layout::Rect,
     ) -> Result<(), Error>;
 }
+
+// Compute a rect centered in `area`, using a percentage of its size
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = area.height * percent_y / 100;
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
@@ -0,0 +1,204 @@
+// This widget is a popup used to pick an item in a list.
+// Typing filters the items with a fuzzy search,
+// Up/Down move the selection, Enter picks and Esc closes the popup.
+
+use ratatui::{
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    layout::{Layout, Direction, Constraint},
+    text::{Line, Span},
+    style::{Modifier, Style},
+    backend::Backend,
+};
+
+use crossterm::event;
+
+use crate::app::widgets::{centered_rect, Drawable};
+use crate::app::library::fuzzy_score;
+use crate::app::Config;
+
+pub struct PickerItem {
+    pub label: String,
+    pub detail: String,
+    pub haystack: String,
+}
+
+pub enum PickerEvent {
+    None,
+    Close,
+    Pick(usize),
+}
+
+pub struct Picker<'a> {
+    title: String,
+    config: &'a Config,
+    items: Vec<PickerItem>,
+    filter: String,
+    // Indexes in `items` matching the filter, best match first
+    matches: Vec<usize>,
+    cursor: usize,
+}
+
+impl<'a> Picker<'a> {
+    pub fn new(title: &str, items: Vec<PickerItem>, config: &'a Config) -> Picker<'a> {
+        let mut picker = Picker {
+            title: title.to_string(),
+            config,
+            items,
+            filter: String::new(),
+            matches: Vec::new(),
+            cursor: 0,
+        };
+        picker.update_matches();
+        picker
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                fuzzy_score(&self.filter, &item.haystack).map(|score| (score, i))
+            })
+            .collect();
+        // Stable sort keeps the library order for equal scores
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.cursor = 0;
+    }
+
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> PickerEvent {
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Esc,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => PickerEvent::Close,
+            event::KeyEvent {
+                code: event::KeyCode::Enter,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => match self.matches.get(self.cursor) {
+                Some(index) => PickerEvent::Pick(*index),
+                None => PickerEvent::None,
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                }
+                PickerEvent::None
+            }
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                if self.cursor + 1 < self.matches.len() {
+                    self.cursor += 1;
+                }
+                PickerEvent::None
+            }
+            event::KeyEvent {
+                code: event::KeyCode::Backspace,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.filter.pop();
+                self.update_matches();
+                PickerEvent::None
+            }
+            event::KeyEvent {
+                code: event::KeyCode::Char(c),
+                modifiers: event::KeyModifiers::NONE | event::KeyModifiers::SHIFT,
+                kind: _,
+                state: _,
+            } => {
+                self.filter.push(*c);
+                self.update_matches();
+                PickerEvent::None
+            }
+            _ => PickerEvent::None,
+        }
+    }
+}
+
+impl Drawable for Picker<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+        let selected_fg_color = self.config.color.selected_foreground;
+
+        let popup = centered_rect(70, 60, area);
+        f.render_widget(Clear, popup);
+
+        let block = Block::default()
+            .title(Span::styled(
+                self.title.as_str(),
+                Style::default().fg(selected_fg_color).bg(bg_color)
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(fg_color).bg(bg_color));
+        let inner = block.inner(popup);
+        f.render_widget(block, popup);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ])
+            .split(inner);
+
+        let filter = Paragraph::new(Line::from(vec![
+            Span::styled("> ", Style::default().fg(selected_fg_color).bg(bg_color)),
+            Span::styled(self.filter.as_str(), Style::default().fg(fg_color).bg(bg_color)),
+            Span::styled(
+                " ",
+                Style::default()
+                    .fg(self.config.color.cursor_foreground)
+                    .bg(self.config.color.cursor_background)
+            ),
+        ]));
+        f.render_widget(filter, layout[0]);
+
+        let items: Vec<ListItem> = self.matches
+            .iter()
+            .map(|index| {
+                let item = &self.items[*index];
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        item.label.as_str(),
+                        Style::default().fg(self.config.json.json_key).bg(bg_color)
+                    ),
+                    Span::styled("  ", Style::default().bg(bg_color)),
+                    Span::styled(
+                        item.detail.as_str(),
+                        Style::default().fg(fg_color).bg(bg_color)
+                    ),
+                ]))
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        if !self.matches.is_empty() {
+            state.select(Some(self.cursor));
+        }
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("▷ ");
+        f.render_stateful_widget(list, layout[1], &mut state);
+        Ok(())
+    }
+}
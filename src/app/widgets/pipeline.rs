@@ -0,0 +1,222 @@
+// This widget lists the stages of the query pipeline with the result
+// of the query evaluated up to each stage.
+// Up/Down select a stage, the Jq widget shows its full result.
+
+use ratatui::{
+    widgets::{Block, Borders, List, ListItem, ListState},
+    text::{Line, Span},
+    style::{Modifier, Style},
+    backend::Backend,
+};
+
+use crossterm::event;
+
+use crate::app::widgets::Drawable;
+use crate::app::tokenizer::pipe_boundaries;
+use crate::app::Config;
+
+pub struct Stage {
+    // Text of this stage only
+    pub text: String,
+    // Output of the query up to and including this stage
+    pub result: Result<String, String>,
+}
+
+impl Stage {
+    fn summary(&self) -> String {
+        match &self.result {
+            Ok(output) => {
+                let mut values = output.lines();
+                match (values.next(), values.count()) {
+                    (None, _) => "(no output)".to_string(),
+                    (Some(first), 0) => first.to_string(),
+                    (Some(first), more) => format!("{} (+{} more)", first, more),
+                }
+            },
+            Err(error) => error.lines().next().unwrap_or("").to_string(),
+        }
+    }
+}
+
+pub struct Pipeline<'a> {
+    config: &'a Config,
+    stages: Vec<Stage>,
+    cursor: usize,
+    selected: bool,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new(config: &'a Config) -> Pipeline<'a> {
+        Pipeline {
+            config,
+            stages: Vec::new(),
+            cursor: 0,
+            selected: false,
+        }
+    }
+
+    pub fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
+
+    pub fn selected(&self) -> bool {
+        self.selected
+    }
+
+    // Split the query into stages and evaluate each prefix with `evaluate`.
+    // The last stage (ie: the whole query) is selected.
+    pub fn update<F>(&mut self, query: &str, mut evaluate: F)
+    where
+        F: FnMut(&str) -> Result<String, String>,
+    {
+        let mut ends = pipe_boundaries(query);
+        ends.push(query.len());
+
+        self.stages.clear();
+        let mut start = 0;
+        for end in ends {
+            let text = query[start..end].trim();
+            // Skip the empty stages, but the last one: the whole query is
+            // evaluated for it, so `.a |` fails instead of showing `.a`
+            if !text.is_empty() || (end == query.len() && !query.trim().is_empty()) {
+                self.stages.push(Stage {
+                    text: text.to_string(),
+                    result: evaluate(&query[..end]),
+                });
+            }
+            start = end + 1;
+        }
+        self.cursor = self.stages.len().saturating_sub(1);
+    }
+
+    pub fn current(&self) -> Option<&Stage> {
+        self.stages.get(self.cursor)
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.cursor + 1, self.stages.len())
+    }
+
+    // Returns true if the selected stage changed
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> bool {
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } if self.cursor > 0 => {
+                self.cursor -= 1;
+                true
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } if self.cursor + 1 < self.stages.len() => {
+                self.cursor += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Drawable for Pipeline<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+        let selected_fg_color = self.config.color.selected_foreground;
+
+        let items: Vec<ListItem> = self.stages
+            .iter()
+            .enumerate()
+            .map(|(i, stage)| {
+                let result_color = match stage.result {
+                    Ok(_) => self.config.color.valid_foreground,
+                    Err(_) => self.config.color.invalid_foreground,
+                };
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{:>2}. ", i + 1),
+                            Style::default().fg(fg_color).bg(bg_color)
+                        ),
+                        Span::styled(
+                            stage.text.as_str(),
+                            Style::default().fg(self.config.json.json_key).bg(bg_color)
+                        ),
+                    ]),
+                    Line::from(Span::styled(
+                        format!("    → {}", stage.summary()),
+                        Style::default().fg(result_color).bg(bg_color)
+                    )),
+                ])
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        if !self.stages.is_empty() {
+            state.select(Some(self.cursor));
+        }
+        let list = List::new(items)
+            .block(Block::default()
+                .title(Span::styled(
+                    "Pipeline stages",
+                    Style::default()
+                        .fg(if self.selected {selected_fg_color} else {fg_color})
+                        .bg(bg_color)
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, area, &mut state);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Text and result of each stage, evaluating a prefix gives its text
+    fn stages(query: &str) -> Vec<(String, Result<String, String>)> {
+        let config = Config::new();
+        let mut pipeline = Pipeline::new(&config);
+        pipeline.update(query, |prefix| {
+            if prefix.trim_end().ends_with('|') {
+                Err("syntax error".to_string())
+            } else {
+                Ok(prefix.trim().to_string())
+            }
+        });
+        assert_eq!(pipeline.position().0, pipeline.stages.len().max(1));
+        pipeline.stages.into_iter().map(|stage| (stage.text, stage.result)).collect()
+    }
+
+    #[test]
+    fn stages_of_query() {
+        assert_eq!(stages(".a | .b"), vec![
+            (".a".to_string(), Ok(".a".to_string())),
+            (".b".to_string(), Ok(".a | .b".to_string())),
+        ]);
+        assert_eq!(stages(".a | map(.b | .c)").len(), 2);
+        assert_eq!(stages(""), vec![]);
+        assert_eq!(stages("  "), vec![]);
+    }
+
+    #[test]
+    fn trailing_pipe_evaluates_whole_query() {
+        assert_eq!(stages(".a |"), vec![
+            (".a".to_string(), Ok(".a".to_string())),
+            (String::new(), Err("syntax error".to_string())),
+        ]);
+        assert_eq!(stages(".a | | .b").last().unwrap().0, ".b");
+    }
+}
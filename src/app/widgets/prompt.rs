@@ -0,0 +1,196 @@
+// This widget is a popup asking the user to fill one or more text fields.
+// Tab/Enter move to the next field, Enter on the last field submits
+// and Esc cancels.
+
+use ratatui::{
+    widgets::{Block, Borders, Clear, Paragraph},
+    text::{Line, Span},
+    style::Style,
+    backend::Backend,
+};
+
+use crossterm::event;
+
+use crate::app::widgets::{centered_rect, Drawable};
+use crate::app::Config;
+
+pub enum PromptEvent {
+    None,
+    Cancel,
+    Submit,
+}
+
+pub struct Prompt<'a> {
+    title: String,
+    config: &'a Config,
+    fields: Vec<(String, String)>,
+    focused: usize,
+    error: Option<String>,
+}
+
+impl<'a> Prompt<'a> {
+    pub fn new(title: &str, labels: &[&str], config: &'a Config) -> Prompt<'a> {
+        Prompt {
+            title: title.to_string(),
+            config,
+            fields: labels
+                .iter()
+                .map(|label| (label.to_string(), String::new()))
+                .collect(),
+            focused: 0,
+            error: None,
+        }
+    }
+
+    pub fn value(&self, field: usize) -> &str {
+        match self.fields.get(field) {
+            Some((_, value)) => value.as_str(),
+            None => "",
+        }
+    }
+
+    pub fn values(&self) -> Vec<String> {
+        self.fields.iter().map(|(_, value)| value.clone()).collect()
+    }
+
+    pub fn set_value(&mut self, field: usize, value: &str) {
+        if let Some((_, current)) = self.fields.get_mut(field) {
+            *current = value.to_string();
+        }
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> PromptEvent {
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Esc,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => PromptEvent::Cancel,
+            event::KeyEvent {
+                code: event::KeyCode::Enter,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                if self.focused + 1 < self.fields.len() {
+                    self.focused += 1;
+                    PromptEvent::None
+                } else {
+                    PromptEvent::Submit
+                }
+            }
+            event::KeyEvent {
+                code: event::KeyCode::Tab,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.focused = (self.focused + 1) % self.fields.len();
+                PromptEvent::None
+            }
+            event::KeyEvent {
+                code: event::KeyCode::BackTab,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.focused = (self.focused + self.fields.len() - 1) % self.fields.len();
+                PromptEvent::None
+            }
+            event::KeyEvent {
+                code: event::KeyCode::Backspace,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.fields[self.focused].1.pop();
+                PromptEvent::None
+            }
+            event::KeyEvent {
+                code: event::KeyCode::Char(c),
+                modifiers: event::KeyModifiers::NONE | event::KeyModifiers::SHIFT,
+                kind: _,
+                state: _,
+            } => {
+                self.fields[self.focused].1.push(*c);
+                PromptEvent::None
+            }
+            _ => PromptEvent::None,
+        }
+    }
+}
+
+impl Drawable for Prompt<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+        let selected_fg_color = self.config.color.selected_foreground;
+
+        // One line per field, one for the error and two for the borders
+        let height = self.fields.len() as u16 + 3;
+        let popup = centered_rect(60, 100, area);
+        let popup = ratatui::layout::Rect::new(
+            popup.x,
+            area.y + area.height.saturating_sub(height) / 2,
+            popup.width,
+            std::cmp::min(height, area.height),
+        );
+        f.render_widget(Clear, popup);
+
+        let label_width = self.fields
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines: Vec<Line> = Vec::new();
+        for (i, (label, value)) in self.fields.iter().enumerate() {
+            let is_focused = i == self.focused;
+            let mut spans = vec![
+                Span::styled(
+                    format!("{:>width$}: ", label, width = label_width),
+                    Style::default()
+                        .fg(if is_focused {selected_fg_color} else {fg_color})
+                        .bg(bg_color)
+                ),
+                Span::styled(value.as_str(), Style::default().fg(fg_color).bg(bg_color)),
+            ];
+            if is_focused {
+                spans.push(Span::styled(
+                    " ",
+                    Style::default()
+                        .fg(self.config.color.cursor_foreground)
+                        .bg(self.config.color.cursor_background)
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+        if let Some(error) = &self.error {
+            lines.push(Line::from(Span::styled(
+                error.as_str(),
+                Style::default().fg(self.config.color.invalid_foreground).bg(bg_color)
+            )));
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title(Span::styled(
+                    self.title.as_str(),
+                    Style::default().fg(selected_fg_color).bg(bg_color)
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            );
+        f.render_widget(paragraph, popup);
+        Ok(())
+    }
+}
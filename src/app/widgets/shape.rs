@@ -0,0 +1,311 @@
+// This widget is a popup showing the shape of the input document as a tree:
+// the key paths with their types, optional keys, array lengths and examples.
+// Up/Down move, Space/Left/Right fold, Enter inserts the path in the query
+// and Esc or q close it.
+
+use std::cell::Cell;
+use std::collections::HashSet;
+
+use ratatui::{
+    widgets::{Block, Borders, Clear, Paragraph, block::{Title, title::Position}},
+    text::{Line, Span},
+    style::{Modifier, Style},
+    backend::Backend,
+    prelude::Alignment,
+};
+
+use crossterm::event;
+
+use crate::app::shape::{Shape, TYPE_NAMES};
+use crate::app::widgets::{centered_rect, Drawable};
+use crate::app::Config;
+
+pub enum ShapeEvent {
+    None,
+    Close,
+    Insert(String),
+}
+
+pub struct ShapeView<'a> {
+    config: &'a Config,
+    shape: Shape,
+    folded: HashSet<usize>,
+    // Nodes shown, the descendants of the folded ones are skipped
+    lines: Vec<usize>,
+    cursor: usize,
+    // First line shown and number of lines, known once drawn
+    offset: Cell<usize>,
+    height: Cell<usize>,
+}
+
+impl<'a> ShapeView<'a> {
+    pub fn new(shape: Shape, config: &'a Config) -> ShapeView<'a> {
+        let mut view = ShapeView {
+            config,
+            shape,
+            folded: HashSet::new(),
+            lines: Vec::new(),
+            cursor: 0,
+            offset: Cell::new(0),
+            height: Cell::new(1),
+        };
+        view.update_lines();
+        view
+    }
+
+    fn update_lines(&mut self) {
+        self.lines.clear();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            self.lines.push(node);
+            if !self.folded.contains(&node) {
+                stack.extend(self.shape.nodes[node].children.iter().rev());
+            }
+        }
+        self.cursor = std::cmp::min(self.cursor, self.lines.len() - 1);
+    }
+
+    fn move_cursor_to(&mut self, line: usize) {
+        self.cursor = std::cmp::min(line, self.lines.len() - 1);
+    }
+
+    // Fold (or unfold) the node under the cursor, Left on a leaf or
+    // a folded node goes to its parent
+    fn fold(&mut self, fold: Option<bool>) {
+        let node = self.lines[self.cursor];
+        if self.shape.nodes[node].children.is_empty() || (fold == Some(true) && self.folded.contains(&node)) {
+            if fold == Some(true) {
+                let depth = self.shape.nodes[node].depth;
+                if let Some(parent) = self.lines[..self.cursor]
+                    .iter()
+                    .rposition(|line| self.shape.nodes[*line].depth < depth)
+                {
+                    self.cursor = parent;
+                }
+            }
+            return;
+        }
+        let fold = fold.unwrap_or(!self.folded.contains(&node));
+        if fold {
+            self.folded.insert(node);
+        } else {
+            self.folded.remove(&node);
+        }
+        self.update_lines();
+    }
+
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> ShapeEvent {
+        let page = self.height.get();
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Esc | event::KeyCode::Char('q'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => return ShapeEvent::Close,
+            event::KeyEvent {
+                code: event::KeyCode::Enter,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => return ShapeEvent::Insert(self.shape.nodes[self.lines[self.cursor]].path.clone()),
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor.saturating_sub(1));
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor + 1);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageUp,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor.saturating_sub(page));
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageDown,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor + page);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Char('g'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(0);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Char('G'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.lines.len());
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Char(' '),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.fold(None);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Left,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.fold(Some(true));
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Right,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.fold(Some(false));
+            },
+            _ => {}
+        }
+        ShapeEvent::None
+    }
+
+    // ie: `▾ name  string 98% · null 2%  optional 40/41  e.g. "a", "b"`
+    fn line(&self, node: usize, is_cursor: bool) -> Line<'a> {
+        let bg_color = self.config.color.background;
+        let style_default = Style::default().fg(self.config.color.foreground).bg(bg_color);
+        let style_dim = style_default.add_modifier(Modifier::DIM);
+        let shape_node = &self.shape.nodes[node];
+
+        let marker = if shape_node.children.is_empty() {
+            " "
+        } else if self.folded.contains(&node) {
+            "▸"
+        } else {
+            "▾"
+        };
+        let mut spans = vec![
+            Span::styled(
+                if is_cursor {"▷ "} else {"  "},
+                style_default.fg(self.config.color.json_cursor_foreground)
+            ),
+            Span::styled(format!("{}{} ", "  ".repeat(shape_node.depth), marker), style_default),
+            Span::styled(
+                shape_node.label.clone(),
+                style_default.fg(self.config.json.json_key).add_modifier(
+                    if is_cursor {Modifier::REVERSED} else {Modifier::empty()}
+                )
+            ),
+        ];
+
+        // Most frequent type first
+        let count = shape_node.count();
+        let mut types: Vec<(usize, usize)> = shape_node.types
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| **n > 0)
+            .map(|(i, n)| (i, *n))
+            .collect();
+        types.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        let types: Vec<String> = types
+            .iter()
+            .map(|(i, n)| match types.len() {
+                1 => TYPE_NAMES[*i].to_string(),
+                _ => format!("{} {}%", TYPE_NAMES[*i], (n * 100 + count / 2) / count),
+            })
+            .collect();
+        spans.push(Span::styled(format!("  {}", types.join(" · ")), style_default.fg(self.config.json.json_boolean)));
+        spans.push(Span::styled(format!(" ×{}", count), style_dim));
+
+        if shape_node.optional() {
+            spans.push(Span::styled(
+                format!("  optional {}/{}", count, shape_node.parent_objects),
+                style_default.fg(self.config.json.json_null)
+            ));
+        }
+        if let Some((min, max)) = shape_node.lengths {
+            let lengths = if min == max { min.to_string() } else { format!("{}–{}", min, max) };
+            spans.push(Span::styled(format!("  length {}", lengths), style_default));
+        }
+        if !shape_node.examples.is_empty() {
+            spans.push(Span::styled("  e.g. ", style_dim));
+            spans.push(Span::styled(shape_node.examples.join(", "), style_default.fg(self.config.json.json_string)));
+        }
+        Line::from(spans)
+    }
+
+    // First line shown, following the cursor
+    fn window(&self, height: usize) -> usize {
+        let mut first = self.offset.get();
+        if self.cursor < first {
+            first = self.cursor;
+        }
+        if self.cursor >= first + height {
+            first = self.cursor + 1 - height;
+        }
+        self.offset.set(first);
+        self.height.set(height);
+        first
+    }
+}
+
+impl Drawable for ShapeView<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+
+        let popup = centered_rect(80, 80, area);
+        f.render_widget(Clear, popup);
+
+        let height = std::cmp::max(popup.height.saturating_sub(2) as usize, 1);
+        let first = self.window(height);
+        let lines: Vec<Line> = self.lines
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(height)
+            .map(|(i, node)| self.line(*node, i == self.cursor))
+            .collect();
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title(Span::styled(
+                    "Shape of the input",
+                    Style::default().fg(self.config.color.selected_foreground).bg(bg_color)
+                ))
+                .title(
+                    Title::from(Span::styled(
+                        format!("{} paths · Enter insert · Space fold", self.shape.nodes.len()),
+                        Style::default().fg(fg_color).bg(bg_color)
+                    ))
+                    .alignment(Alignment::Right)
+                    .position(Position::Bottom)
+                )
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            )
+            .style(Style::default().fg(fg_color).bg(bg_color));
+        f.render_widget(paragraph, popup);
+        Ok(())
+    }
+}
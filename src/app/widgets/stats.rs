@@ -0,0 +1,238 @@
+// This widget is a popup summarizing the result: the count and types of
+// its values, then statistics and a chart depending on their type.
+// Esc, q or F10 close it.
+
+use ratatui::{
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Clear, Paragraph},
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    style::Style,
+    backend::Backend,
+};
+
+use crossterm::event;
+
+use crate::app::shape::TYPE_NAMES;
+use crate::app::stats::{Stats, Summary};
+use crate::app::widgets::{centered_rect, Drawable};
+use crate::app::Config;
+
+// Width of the labels of the horizontal bars
+const LABEL_WIDTH: usize = 24;
+
+pub struct StatsView<'a> {
+    config: &'a Config,
+    stats: Stats,
+}
+
+// Shortest of a few decimals (ie: 2, 2.5, 0.333)
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else if n.abs() >= 1e15 || (n != 0.0 && n.abs() < 1e-3) {
+        format!("{:e}", n)
+    } else {
+        let text = format!("{:.3}", n);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+fn percent(n: usize, total: usize) -> String {
+    format!("{}%", (n * 100 + total / 2) / total.max(1))
+}
+
+// Cut a label to LABEL_WIDTH characters
+fn label(text: &str) -> String {
+    if text.chars().count() > LABEL_WIDTH {
+        format!("{}…", text.chars().take(LABEL_WIDTH - 1).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+impl<'a> StatsView<'a> {
+    pub fn new(stats: Stats, config: &'a Config) -> StatsView<'a> {
+        StatsView { config, stats }
+    }
+
+    // Returns true when the popup must be closed
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> bool {
+        matches!(
+            event,
+            event::KeyEvent {
+                code: event::KeyCode::Esc | event::KeyCode::Char('q') | event::KeyCode::F(10),
+                modifiers: _,
+                kind: _,
+                state: _,
+            }
+        )
+    }
+
+    fn lines(&self) -> Vec<Line<'a>> {
+        let style_key = Style::default().fg(self.config.json.json_key).bg(self.config.color.background);
+        let style_value = Style::default().fg(self.config.color.foreground).bg(self.config.color.background);
+        let row = |name: &str, value: String| Line::from(vec![
+            Span::styled(format!("{:<10}", name), style_key),
+            Span::styled(value, style_value),
+        ]);
+
+        let stats = &self.stats;
+        let types: Vec<String> = stats.types
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| **n > 0)
+            .map(|(i, n)| format!("{} {}", TYPE_NAMES[i], n))
+            .collect();
+        let mut lines = vec![
+            row("count", stats.count.to_string()),
+            row("types", types.join(" · ")),
+        ];
+        match &stats.summary {
+            Summary::Numbers { min, max, mean, p50, p95, histogram: _ } => {
+                lines.push(row("min", format_number(*min)));
+                lines.push(row("max", format_number(*max)));
+                lines.push(row("mean", format_number(*mean)));
+                lines.push(row("p50", format_number(*p50)));
+                lines.push(row("p95", format_number(*p95)));
+            },
+            Summary::Strings { distinct, top: _ } => {
+                lines.push(row("distinct", distinct.to_string()));
+            },
+            Summary::Objects { fill } => {
+                lines.push(row("keys", fill.len().to_string()));
+            },
+            Summary::Mixed => {},
+        }
+        lines
+    }
+
+    // Labels of the horizontal bars, the chart does not draw them
+    fn labels(&self) -> Vec<String> {
+        match &self.stats.summary {
+            Summary::Strings { distinct: _, top } => top.iter().map(|(value, _)| label(value)).collect(),
+            Summary::Objects { fill } => fill.iter().map(|(key, _)| label(key)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Histogram of the numbers, most frequent strings or fill rate of the keys
+    fn chart(&self) -> Option<(String, BarChart<'a>)> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+        let bars: Vec<Bar> = match &self.stats.summary {
+            Summary::Numbers { histogram, .. } => histogram
+                .iter()
+                .map(|n| Bar::default().value(*n))
+                .collect(),
+            Summary::Strings { distinct: _, top } => top
+                .iter()
+                .map(|(_, n)| Bar::default()
+                    .value(*n as u64)
+                    .text_value(format!("{} ({})", n, percent(*n, self.stats.count))))
+                .collect(),
+            Summary::Objects { fill } => fill
+                .iter()
+                .map(|(_, n)| Bar::default()
+                    .value((n * 100 / self.stats.count.max(1)) as u64)
+                    .text_value(format!("{} ({}/{})", percent(*n, self.stats.count), n, self.stats.count)))
+                .collect(),
+            Summary::Mixed => return None,
+        };
+        let (title, direction) = match &self.stats.summary {
+            Summary::Numbers { min, max, histogram, .. } => (
+                format!(
+                    "Histogram: {} bins of {} from {} to {}",
+                    histogram.len(),
+                    format_number((max - min) / histogram.len() as f64),
+                    format_number(*min),
+                    format_number(*max)
+                ),
+                Direction::Vertical
+            ),
+            Summary::Strings { .. } => ("Most frequent values".to_string(), Direction::Horizontal),
+            _ => ("Fill rate of the keys (set and not null)".to_string(), Direction::Horizontal),
+        };
+        let mut chart = BarChart::default()
+            .data(BarGroup::default().bars(&bars))
+            .direction(direction)
+            .bar_gap(if direction == Direction::Vertical {1} else {0})
+            .bar_style(Style::default().fg(self.config.json.json_number).bg(bg_color))
+            .value_style(Style::default().fg(bg_color).bg(self.config.json.json_number))
+            .label_style(Style::default().fg(fg_color).bg(bg_color))
+            .style(Style::default().fg(fg_color).bg(bg_color));
+        if let Summary::Objects { .. } = &self.stats.summary {
+            chart = chart.max(100);
+        }
+        Some((title, chart))
+    }
+}
+
+impl Drawable for StatsView<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+
+        let popup = centered_rect(80, 80, area);
+        f.render_widget(Clear, popup);
+        let block = Block::default()
+            .title(Span::styled(
+                "Statistics of the result",
+                Style::default().fg(self.config.color.selected_foreground).bg(bg_color)
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(fg_color).bg(bg_color))
+            .style(Style::default().fg(fg_color).bg(bg_color));
+        let inner = block.inner(popup);
+        f.render_widget(block, popup);
+
+        let lines = self.lines();
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(lines.len() as u16 + 1),
+                Constraint::Min(0),
+            ])
+            .split(inner);
+        f.render_widget(Paragraph::new(lines), layout[0]);
+
+        let (title, chart) = match self.chart() {
+            Some(chart) => chart,
+            None => return Ok(()),
+        };
+        let block = Block::default()
+            .title(Span::styled(title, Style::default().fg(self.config.json.json_key).bg(bg_color)))
+            .borders(Borders::TOP)
+            .border_style(Style::default().fg(fg_color).bg(bg_color));
+        let chart_area = block.inner(layout[1]);
+        f.render_widget(block, layout[1]);
+        if let Summary::Numbers { histogram, .. } = &self.stats.summary {
+            // Spread the bars over the width
+            let width = chart_area.width / histogram.len() as u16;
+            f.render_widget(chart.bar_width(std::cmp::max(width.saturating_sub(1), 1)), chart_area);
+            return Ok(());
+        }
+        // The labels on the left of the bars
+        let labels: Vec<Line> = self.labels()
+            .into_iter()
+            .map(|label| Line::from(Span::styled(
+                label,
+                Style::default().fg(self.config.json.json_key).bg(bg_color)
+            )))
+            .collect();
+        let label_width = labels.iter().map(|label| label.width()).max().unwrap_or(0) as u16 + 1;
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Length(label_width),
+                Constraint::Min(0),
+            ])
+            .split(chart_area);
+        f.render_widget(Paragraph::new(labels), columns[0]);
+        f.render_widget(chart, columns[1]);
+        Ok(())
+    }
+}
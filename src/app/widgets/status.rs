@@ -0,0 +1,92 @@
+// This widget is the bottom status bar.
+// It shows the key hints of the focused widget, or a transient message
+// (ie: "Saved to out.json") for a few seconds.
+
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    widgets::Paragraph,
+    text::{Line, Span},
+    style::Style,
+    backend::Backend,
+};
+
+use crate::app::keymap::{hints, Context};
+use crate::app::widgets::Drawable;
+use crate::app::Config;
+
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+pub struct StatusBar<'a> {
+    config: &'a Config,
+    context: Context,
+    // Message and when it was set
+    message: Option<(String, Instant)>,
+}
+
+impl<'a> StatusBar<'a> {
+    pub fn new(config: &'a Config) -> StatusBar<'a> {
+        StatusBar {
+            config,
+            context: Context::Input,
+            message: None,
+        }
+    }
+
+    pub fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some((message, Instant::now()));
+    }
+
+    // Drop the message once it has been shown long enough,
+    // returns true if the bar needs to be redrawn
+    pub fn expire(&mut self) -> bool {
+        match &self.message {
+            Some((_, since)) if since.elapsed() >= MESSAGE_DURATION => {
+                self.message = None;
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Drawable for StatusBar<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+
+        let line = match &self.message {
+            Some((message, _)) => Line::from(Span::styled(
+                message.as_str(),
+                Style::default().fg(self.config.color.valid_foreground).bg(bg_color)
+            )),
+            None => {
+                let mut spans = Vec::new();
+                for binding in hints(self.context) {
+                    if !spans.is_empty() {
+                        spans.push(Span::styled(" · ", Style::default().fg(fg_color).bg(bg_color)));
+                    }
+                    spans.push(Span::styled(
+                        binding.keys,
+                        Style::default().fg(self.config.color.selected_foreground).bg(bg_color)
+                    ));
+                    spans.push(Span::styled(
+                        format!(" {}", binding.action),
+                        Style::default().fg(fg_color).bg(bg_color)
+                    ));
+                }
+                Line::from(spans)
+            },
+        };
+        f.render_widget(Paragraph::new(line).style(Style::default().fg(fg_color).bg(bg_color)), area);
+        Ok(())
+    }
+}
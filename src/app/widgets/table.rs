@@ -0,0 +1,674 @@
+// Table view of an array of objects, shown by the Json widget instead of
+// the tree (t toggles it). The columns are the union of the keys, nested
+// values are shown as compact JSON. Left/Right select a column, s sorts by
+// it, x hides it and X shows every column again.
+
+use std::cell::Cell as StateCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use ratatui::{
+    widgets::{Block, Cell, Row, Table},
+    text::Span,
+    style::{Modifier, Style},
+    backend::Backend,
+    layout::{Constraint, Rect},
+};
+
+use crossterm::event;
+
+use serde_json::Value;
+
+use crate::app::document::{Document, Key, Kind};
+use crate::app::widgets::json::{escape_char, jq_path};
+use crate::app::Config;
+
+// Widths are fitted to the header and the first rows
+const WIDTH_SAMPLE: usize = 100;
+const MAX_COLUMN_WIDTH: usize = 40;
+// Containers with more descendants are summarized instead of printed
+const MAX_INLINE_DESCENDANTS: usize = 50;
+const COLUMN_SPACING: u16 = 1;
+// Rows kept visible above and below the cursor
+const SCROLL_OFF: usize = 3;
+const SCROLL_ROWS: usize = 3;
+
+pub struct TableView<'a> {
+    config: &'a Config,
+    document: Rc<Document>,
+    // Union of the keys, in the order they first appear
+    columns: Vec<String>,
+    widths: Vec<usize>,
+    hidden: HashSet<String>,
+    // Nodes of the array elements, in display order
+    rows: Vec<usize>,
+    // Column and whether the order is descending
+    sort: Option<(String, bool)>,
+    cursor: usize,
+    // Index in the visible columns
+    column: usize,
+    // First row and column shown, they follow the cursor when drawing
+    offset: StateCell<usize>,
+    column_offset: StateCell<usize>,
+    height: StateCell<usize>,
+}
+
+// Order of the kinds in jq's sort
+fn rank(kind: &Kind) -> u8 {
+    match kind {
+        Kind::Null => 0,
+        Kind::Bool(false) => 1,
+        Kind::Bool(true) => 2,
+        Kind::Number(_) => 3,
+        Kind::String(_) => 4,
+        Kind::Array => 5,
+        Kind::Object => 6,
+    }
+}
+
+// Compare two nodes the way jq's sort does, a missing value is null
+fn compare(document: &Document, a: Option<usize>, b: Option<usize>) -> Ordering {
+    let (a, b) = match (a, b) {
+        (None, None) => return Ordering::Equal,
+        (None, Some(b)) => return rank(&Kind::Null).cmp(&rank(&document.node(b).kind)),
+        (Some(a), None) => return rank(&document.node(a).kind).cmp(&rank(&Kind::Null)),
+        (Some(a), Some(b)) => (a, b),
+    };
+    let (a_kind, b_kind) = (&document.node(a).kind, &document.node(b).kind);
+    match (a_kind, b_kind) {
+        (Kind::Number(x), Kind::Number(y)) => {
+            let (x, y) = (x.parse::<f64>().unwrap_or(0.0), y.parse::<f64>().unwrap_or(0.0));
+            x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+        },
+        (Kind::String(x), Kind::String(y)) => x.cmp(y),
+        (Kind::Array, Kind::Array) => {
+            let mut b_children = document.children(b);
+            for a_child in document.children(a) {
+                match b_children.next() {
+                    Some(b_child) => match compare(document, Some(a_child), Some(b_child)) {
+                        Ordering::Equal => {},
+                        other => return other,
+                    },
+                    None => return Ordering::Greater,
+                }
+            }
+            if b_children.next().is_some() { Ordering::Less } else { Ordering::Equal }
+        },
+        (Kind::Object, Kind::Object) => {
+            // By the sorted keys first, then by the values in that order
+            let keys = |node: usize| {
+                let mut keys: Vec<&str> = document.children(node).filter_map(|child| match &document.node(child).key {
+                    Key::Name(name) => Some(&**name),
+                    _ => None,
+                }).collect();
+                keys.sort_unstable();
+                keys.dedup();
+                keys
+            };
+            let (a_keys, b_keys) = (keys(a), keys(b));
+            a_keys.cmp(&b_keys).then_with(|| {
+                for key in a_keys {
+                    match compare(document, field(document, a, key), field(document, b, key)) {
+                        Ordering::Equal => {},
+                        other => return other,
+                    }
+                }
+                Ordering::Equal
+            })
+        },
+        _ => rank(a_kind).cmp(&rank(b_kind)),
+    }
+}
+
+// Value of `key` in the object `node`, jq keeps the last of duplicated keys
+fn field(document: &Document, node: usize, key: &str) -> Option<usize> {
+    document.children(node)
+        .rev()
+        .find(|child| matches!(&document.node(*child).key, Key::Name(name) if **name == *key))
+}
+
+impl<'a> TableView<'a> {
+    // None if the document is not a non-empty array of objects.
+    // The hidden columns and the sort of `previous` are kept.
+    pub fn new(document: Rc<Document>, config: &'a Config, previous: Option<&TableView>) -> Option<TableView<'a>> {
+        if !matches!(document.node(0).kind, Kind::Array) || document.node(0).children == 0 {
+            return None;
+        }
+        let rows: Vec<usize> = document.children(0).collect();
+        if rows.iter().any(|row| !matches!(document.node(*row).kind, Kind::Object)) {
+            return None;
+        }
+        let mut columns = Vec::new();
+        let mut seen = HashSet::new();
+        for row in &rows {
+            for child in document.children(*row) {
+                if let Key::Name(name) = &document.node(child).key {
+                    if seen.insert(name.clone()) {
+                        columns.push(name.to_string());
+                    }
+                }
+            }
+        }
+
+        let mut table = TableView {
+            config,
+            document,
+            columns,
+            widths: Vec::new(),
+            hidden: previous.map(|previous| previous.hidden.clone()).unwrap_or_default(),
+            rows,
+            sort: None,
+            cursor: 0,
+            column: 0,
+            offset: StateCell::new(0),
+            column_offset: StateCell::new(0),
+            height: StateCell::new(1),
+        };
+        table.widths = table.columns
+            .iter()
+            .map(|column| {
+                table.rows
+                    .iter()
+                    .take(WIDTH_SAMPLE)
+                    .map(|row| table.cell_text(field(&table.document, *row, column)).chars().count())
+                    .chain(std::iter::once(column.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+                    .clamp(1, MAX_COLUMN_WIDTH)
+            })
+            .collect();
+        if let Some((column, descending)) = previous.and_then(|previous| previous.sort.clone()) {
+            if table.columns.contains(&column) {
+                table.sort_by(column, descending);
+            }
+        }
+        Some(table)
+    }
+
+    fn visible_columns(&self) -> Vec<usize> {
+        (0..self.columns.len())
+            .filter(|column| !self.hidden.contains(&self.columns[*column]))
+            .collect()
+    }
+
+    fn selected_column(&self) -> Option<usize> {
+        self.visible_columns().get(self.column).copied()
+    }
+
+    // Strings are shown unquoted, escaped on a single line
+    fn cell_text(&self, node: Option<usize>) -> String {
+        let node = match node {
+            Some(node) => node,
+            None => return String::new(),
+        };
+        match &self.document.node(node).kind {
+            Kind::Null => "null".to_string(),
+            Kind::Bool(b) => b.to_string(),
+            Kind::Number(n) => n.to_string(),
+            Kind::String(s) => s
+                .chars()
+                .map(|c| escape_char(c, false).filter(|_| c != '"' && c != '\\').unwrap_or_else(|| c.to_string()))
+                .collect(),
+            Kind::Array if self.document.node(node).descendants > MAX_INLINE_DESCENDANTS => {
+                format!("[…] ({} items)", self.document.node(node).children)
+            },
+            Kind::Object if self.document.node(node).descendants > MAX_INLINE_DESCENDANTS => {
+                format!("{{…}} ({} keys)", self.document.node(node).children)
+            },
+            Kind::Array | Kind::Object => self.document.to_value(node).to_string(),
+        }
+    }
+
+    // Descending is the reverse of the ascending order, equal rows included,
+    // like the `sort_by(...) | reverse` of sort_expression()
+    fn sort_by(&mut self, column: String, descending: bool) {
+        let document = &self.document;
+        self.rows.sort_unstable();
+        self.rows.sort_by(|a, b| compare(document, field(document, *a, &column), field(document, *b, &column)));
+        if descending {
+            self.rows.reverse();
+        }
+        self.sort = Some((column, descending));
+    }
+
+    // Ascending, descending, then the document order again
+    fn cycle_sort(&mut self) {
+        let column = match self.selected_column() {
+            Some(column) => self.columns[column].clone(),
+            None => return,
+        };
+        let row = self.rows.get(self.cursor).copied();
+        match self.sort.take() {
+            Some((sorted, false)) if sorted == column => self.sort_by(column, true),
+            Some((sorted, true)) if sorted == column => self.rows.sort_unstable(),
+            _ => self.sort_by(column, false),
+        }
+        // Keep the same row selected
+        if let Some(row) = row {
+            self.cursor = self.rows.iter().position(|r| *r == row).unwrap_or(0);
+        }
+    }
+
+    // jq expression sorting like the table (ie: `sort_by(.name) | reverse`)
+    pub fn sort_expression(&self) -> Option<String> {
+        let (column, descending) = self.sort.as_ref()?;
+        let expression = format!("sort_by({})", jq_path(&[Value::String(column.clone())]));
+        Some(if *descending { format!("{} | reverse", expression) } else { expression })
+    }
+
+    pub fn clear_sort(&mut self) {
+        self.sort = None;
+        self.rows.sort_unstable();
+    }
+
+    // The selected cell, or the row if it has no such key
+    pub fn cursor_node(&self) -> Option<usize> {
+        let row = *self.rows.get(self.cursor)?;
+        Some(self.selected_column()
+            .and_then(|column| field(&self.document, row, &self.columns[column]))
+            .unwrap_or(row))
+    }
+
+    // Select the row containing `node`
+    pub fn select_node(&mut self, node: usize) {
+        if let Some(position) = self.rows.iter().position(|row| {
+            node >= *row && node <= row + self.document.node(*row).descendants
+        }) {
+            self.cursor = position;
+        }
+    }
+
+    fn move_cursor_to(&mut self, row: usize) {
+        self.cursor = std::cmp::min(row, self.rows.len().saturating_sub(1));
+    }
+
+    pub fn click(&mut self, area: Rect, row: u16) {
+        // The border, then the header
+        if row <= area.y + 1 || row + 1 >= area.y + area.height {
+            return;
+        }
+        self.move_cursor_to(self.offset.get() + (row - area.y - 2) as usize);
+    }
+
+    pub fn scroll(&mut self, down: bool) {
+        if down {
+            self.move_cursor_to(self.cursor + SCROLL_ROWS);
+        } else {
+            self.move_cursor_to(self.cursor.saturating_sub(SCROLL_ROWS));
+        }
+    }
+
+    // ie: "row 3 / 120 · col 2 / 8 · sorted by name ▼"
+    pub fn info(&self) -> String {
+        let mut info = format!(
+            "row {} / {} · col {} / {}",
+            self.cursor + 1,
+            self.rows.len(),
+            self.column + 1,
+            self.visible_columns().len()
+        );
+        if !self.hidden.is_empty() {
+            info.push_str(&format!(" · {} hidden", self.hidden.len()));
+        }
+        if let Some((column, descending)) = &self.sort {
+            info.push_str(&format!(" · sorted by {} {}", column, if *descending {"▼"} else {"▲"}));
+        }
+        info
+    }
+
+    // Returns false if the key is not handled by the table
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> bool {
+        let page = self.height.get();
+        let visible = self.visible_columns().len();
+        match event {
+            event::KeyEvent{ // Handle keyboard up
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor.saturating_sub(1));
+            },
+            event::KeyEvent{ // Handle keyboard down
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor + 1);
+            },
+            event::KeyEvent{ // Handle keyboard page up
+                code: event::KeyCode::PageUp,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor.saturating_sub(page));
+            },
+            event::KeyEvent{ // Handle keyboard page down
+                code: event::KeyCode::PageDown,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor + page);
+            },
+            event::KeyEvent{ // Handle Ctrl-U: half a page up
+                code: event::KeyCode::Char('u'),
+                modifiers: event::KeyModifiers::CONTROL,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor.saturating_sub(std::cmp::max(page / 2, 1)));
+            },
+            event::KeyEvent{ // Handle Ctrl-D: half a page down
+                code: event::KeyCode::Char('d'),
+                modifiers: event::KeyModifiers::CONTROL,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.cursor + std::cmp::max(page / 2, 1));
+            },
+            event::KeyEvent{ // Handle g: first row
+                code: event::KeyCode::Char('g'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(0);
+            },
+            event::KeyEvent{ // Handle G: last row
+                code: event::KeyCode::Char('G'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.move_cursor_to(self.rows.len());
+            },
+            event::KeyEvent{ // Handle keyboard left: previous column
+                code: event::KeyCode::Left,
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.column = self.column.saturating_sub(1);
+            },
+            event::KeyEvent{ // Handle keyboard right: next column
+                code: event::KeyCode::Right,
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.column = std::cmp::min(self.column + 1, visible.saturating_sub(1));
+            },
+            event::KeyEvent{ // Handle s: sort by the column
+                code: event::KeyCode::Char('s'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                self.cycle_sort();
+            },
+            event::KeyEvent{ // Handle x: hide the column, keeping at least one
+                code: event::KeyCode::Char('x'),
+                modifiers: event::KeyModifiers::NONE,
+                kind: _,
+                state: _,
+            } => {
+                if let (Some(column), true) = (self.selected_column(), visible > 1) {
+                    self.hidden.insert(self.columns[column].clone());
+                    self.column = std::cmp::min(self.column, visible - 2);
+                }
+            },
+            event::KeyEvent{ // Handle X: show all the columns
+                code: event::KeyCode::Char('X'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.hidden.clear();
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    // First row shown, with the cursor kept SCROLL_OFF rows away from the edges
+    fn window(&self, height: usize) -> usize {
+        let margin = std::cmp::min(SCROLL_OFF, height.saturating_sub(1) / 2);
+        let mut first = self.offset.get();
+        if self.cursor < first + margin {
+            first = self.cursor.saturating_sub(margin);
+        }
+        if self.cursor + margin >= first + height {
+            first = (self.cursor + margin + 1).saturating_sub(height);
+        }
+        first = std::cmp::min(first, self.rows.len().saturating_sub(height));
+        self.offset.set(first);
+        self.height.set(height);
+        first
+    }
+
+    // Visible columns which fit in `width`, scrolled to show the selected one
+    fn shown_columns(&self, width: usize, index_width: usize) -> Vec<usize> {
+        let visible = self.visible_columns();
+        let available = width.saturating_sub(index_width + COLUMN_SPACING as usize);
+        let fits = |from: usize, to: usize| {
+            visible[from..=to]
+                .iter()
+                .map(|column| self.widths[*column] + COLUMN_SPACING as usize)
+                .sum::<usize>() <= available + COLUMN_SPACING as usize
+        };
+        let mut first = std::cmp::min(self.column_offset.get(), self.column);
+        while first < self.column && !fits(first, self.column) {
+            first += 1;
+        }
+        self.column_offset.set(first);
+        let mut last = first;
+        while last + 1 < visible.len() && fits(first, last + 1) {
+            last += 1;
+        }
+        visible.get(first..=last).map(|columns| columns.to_vec()).unwrap_or_default()
+    }
+
+    pub fn draw<B: Backend>(&self, f: &mut ratatui::Frame<B>, area: Rect, block: Block, selected: bool) {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+        let style_default = Style::default().fg(fg_color).bg(bg_color);
+
+        // Borders and header
+        let height = std::cmp::max(area.height.saturating_sub(3) as usize, 1);
+        let first = self.window(height);
+        let index_width = (self.rows.len().saturating_sub(1)).to_string().len() + 2;
+        let shown = self.shown_columns(area.width.saturating_sub(2) as usize, index_width);
+        let selected_column = self.selected_column();
+
+        let header = Row::new(
+            std::iter::once(Cell::from("#")).chain(shown.iter().map(|column| {
+                let mut style = style_default.fg(self.config.json.json_key).add_modifier(Modifier::BOLD);
+                if Some(*column) == selected_column {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                Cell::from(Span::styled(self.columns[*column].clone(), style))
+            })).collect::<Vec<Cell>>()
+        ).style(style_default);
+
+        let cursor_style = style_default.fg(
+            if selected {self.config.color.json_cursor_foreground} else {fg_color}
+        );
+        let rows: Vec<Row> = self.rows[first..std::cmp::min(first + height, self.rows.len())]
+            .iter()
+            .enumerate()
+            .map(|(position, row)| {
+                let is_cursor = first + position == self.cursor;
+                let index = match self.document.node(*row).key {
+                    Key::Index(index) => index.to_string(),
+                    _ => String::new(),
+                };
+                let index = Cell::from(Span::styled(
+                    format!("{}{}", if is_cursor {"▷ "} else {"  "}, index),
+                    if is_cursor {cursor_style} else {style_default.add_modifier(Modifier::DIM)}
+                ));
+                let cells = shown.iter().map(|column| {
+                    let node = field(&self.document, *row, &self.columns[*column]);
+                    let color = match node.map(|node| &self.document.node(node).kind) {
+                        Some(Kind::String(_)) => self.config.json.json_string,
+                        Some(Kind::Number(_)) => self.config.json.json_number,
+                        Some(Kind::Bool(_)) => self.config.json.json_boolean,
+                        Some(Kind::Null) => self.config.json.json_null,
+                        _ => fg_color,
+                    };
+                    let mut style = style_default.fg(color);
+                    if is_cursor && Some(*column) == selected_column {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+                    Cell::from(Span::styled(self.cell_text(node), style))
+                });
+                Row::new(std::iter::once(index).chain(cells).collect::<Vec<Cell>>())
+            })
+            .collect();
+
+        let widths: Vec<Constraint> = std::iter::once(Constraint::Length(index_width as u16))
+            .chain(shown.iter().map(|column| Constraint::Length(self.widths[*column] as u16)))
+            .collect();
+        let table = Table::new(rows)
+            .header(header)
+            .widths(&widths)
+            .column_spacing(COLUMN_SPACING)
+            .block(block)
+            .style(style_default);
+        f.render_widget(table, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::engine::Engine;
+
+    fn table<'a>(json: &str, config: &'a Config, previous: Option<&TableView>) -> TableView<'a> {
+        TableView::new(Rc::new(Document::parse(json).unwrap()), config, previous).unwrap()
+    }
+
+    fn key(code: event::KeyCode) -> event::KeyEvent {
+        event::KeyEvent::new(code, event::KeyModifiers::NONE)
+    }
+
+    fn select_column(table: &mut TableView, name: &str) {
+        table.column = table.visible_columns().iter().position(|column| table.columns[*column] == name).unwrap();
+    }
+
+    // The rows in display order
+    fn rows(table: &TableView) -> Value {
+        Value::Array(table.rows.iter().map(|row| table.document.to_value(*row)).collect())
+    }
+
+    // Sorting the table by each column, ascending then descending, gives
+    // the order of the expression added to the query
+    fn check_sorts(json: &str) {
+        let config = Config::new();
+        let engine = Engine::new(Vec::new(), std::path::PathBuf::from("."));
+        let input = engine.parse(json);
+        let mut table = table(json, &config, None);
+        for column in table.columns.clone() {
+            select_column(&mut table, &column);
+            for _ in 0..2 {
+                table.handle_event(&key(event::KeyCode::Char('s')));
+                let expression = table.sort_expression().unwrap();
+                // jq prints the numbers its own way (ie: 1e2 as 100)
+                let shown = engine.run(".", &engine.parse(&rows(&table).to_string())).unwrap();
+                assert_eq!(shown, engine.run(&expression, &input).unwrap(), "{}", expression);
+            }
+            table.handle_event(&key(event::KeyCode::Char('s')));
+            assert_eq!(table.sort_expression(), None);
+            assert_eq!(rows(&table), serde_json::from_str::<Value>(json).unwrap());
+        }
+    }
+
+    #[test]
+    fn sorted_like_jq() {
+        // Mixed types and missing keys
+        check_sorts(r#"[
+            {"k": "b", "i": 0}, {"k": 2, "i": 1}, {"i": 2}, {"k": null, "i": 3}, {"k": true, "i": 4},
+            {"k": false, "i": 5}, {"k": [1], "i": 6}, {"k": {"a": 1}, "i": 7}, {"k": "a", "i": 8}, {"k": -1, "i": 9}
+        ]"#);
+        // Equal numbers written differently keep their order
+        check_sorts(r#"[{"n": 100, "i": 0}, {"n": 1e2, "i": 1}, {"n": 1.5, "i": 2}, {"n": 100.0, "i": 3}, {"n": -0.5, "i": 4}]"#);
+        // Arrays and objects, by their keys then their values
+        check_sorts(r#"[
+            {"o": {"b": 1}, "a": [1, 2]}, {"o": {"a": 2}, "a": [1]}, {"o": {"a": 1, "b": 0}, "a": []},
+            {"o": {"a": 1}, "a": [0, 5]}, {"o": {}, "a": [1, "x"]}, {"o": {"a": 1}, "a": [1, 2]}
+        ]"#);
+    }
+
+    #[test]
+    fn sort_keeps_selected_row() {
+        let config = Config::new();
+        let mut table = table(r#"[{"n": 3}, {"n": 1}, {"n": 2}]"#, &config, None);
+        table.cursor = 1;
+        table.handle_event(&key(event::KeyCode::Char('s')));
+        assert_eq!(table.cursor, 0);
+        assert_eq!(table.sort_expression().as_deref(), Some("sort_by(.n)"));
+        table.handle_event(&key(event::KeyCode::Char('s')));
+        assert_eq!(table.cursor, 2);
+        assert_eq!(table.sort_expression().as_deref(), Some("sort_by(.n) | reverse"));
+    }
+
+    #[test]
+    fn previous_sort_and_hidden_columns_kept() {
+        let config = Config::new();
+        let mut previous = table(r#"[{"a": 2, "b": 1}, {"a": 1, "b": 2}]"#, &config, None);
+        select_column(&mut previous, "a");
+        previous.handle_event(&key(event::KeyCode::Char('s')));
+        previous.handle_event(&key(event::KeyCode::Char('x')));
+        let next = table(r#"[{"a": 5, "b": 0}, {"a": 4, "b": 0}, {"a": 6, "b": 0}]"#, &config, Some(&previous));
+        assert_eq!(next.visible_columns(), vec![1]);
+        assert_eq!(rows(&next), serde_json::json!([{"a": 4, "b": 0}, {"a": 5, "b": 0}, {"a": 6, "b": 0}]));
+    }
+
+    #[test]
+    fn all_columns_hidden_by_previous() {
+        let config = Config::new();
+        let mut previous = table(r#"[{"a": 1, "b": 2}]"#, &config, None);
+        select_column(&mut previous, "a");
+        previous.handle_event(&key(event::KeyCode::Char('x')));
+        // The only column of the new table was hidden
+        let mut next = table(r#"[{"a": 1}, {"a": 2}]"#, &config, Some(&previous));
+        assert!(next.visible_columns().is_empty());
+        assert_eq!(next.selected_column(), None);
+        assert!(next.shown_columns(80, 3).is_empty());
+        assert_eq!(next.cursor_node(), Some(1));
+        for code in [event::KeyCode::Right, event::KeyCode::Char('x'), event::KeyCode::Char('s')] {
+            next.handle_event(&key(code));
+        }
+        assert_eq!(next.sort_expression(), None);
+        let backend = ratatui::backend::TestBackend::new(40, 10);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal.draw(|f| next.draw(f, f.size(), Block::default(), true)).unwrap();
+        next.handle_event(&key(event::KeyCode::Char('X')));
+        assert_eq!(next.visible_columns(), vec![0]);
+        assert_eq!(next.shown_columns(80, 3), vec![0]);
+        assert_eq!(next.cursor_node(), Some(2));
+    }
+
+    #[test]
+    fn shown_columns_follow_selection() {
+        let config = Config::new();
+        let mut table = table(r#"[{"aaaa": 1, "bbbb": 2, "cccc": 3, "dddd": 4}]"#, &config, None);
+        // Each column is 4 wide plus the spacing
+        assert_eq!(table.shown_columns(100, 3), vec![0, 1, 2, 3]);
+        assert_eq!(table.shown_columns(14, 3), vec![0, 1]);
+        table.column = 3;
+        assert_eq!(table.shown_columns(14, 3), vec![2, 3]);
+        table.column = 2;
+        assert_eq!(table.shown_columns(14, 3), vec![2, 3]);
+        table.column = 0;
+        assert_eq!(table.shown_columns(14, 3), vec![0, 1]);
+        // Too narrow for any, the selected one is still shown
+        assert_eq!(table.shown_columns(2, 3), vec![0]);
+        select_column(&mut table, "bbbb");
+        table.handle_event(&key(event::KeyCode::Char('x')));
+        assert_eq!(table.shown_columns(100, 3), vec![0, 2, 3]);
+    }
+}
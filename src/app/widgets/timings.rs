@@ -0,0 +1,103 @@
+// This widget is a small box in the top right corner showing how long
+// the last query took to parse, evaluate and draw. F8 toggles it.
+
+use std::time::Duration;
+
+use ratatui::{
+    widgets::{Block, Borders, Clear, Paragraph},
+    text::{Line, Span},
+    style::Style,
+    backend::Backend,
+    layout::Rect,
+};
+
+use crate::app::widgets::Drawable;
+use crate::app::Config;
+
+const WIDTH: u16 = 36;
+
+pub struct Timings<'a> {
+    config: &'a Config,
+    // Parsing the input, done once
+    pub input_parse: Duration,
+    // Running the query (all the stages in the pipeline debugger)
+    pub query: Duration,
+    pub query_memoized: bool,
+    // Running `path(query)` to highlight the selected nodes
+    pub paths: Duration,
+    pub paths_memoized: bool,
+    // Parsing the result to display it
+    pub result_parse: Duration,
+    // Drawing the last frame
+    pub render: Duration,
+    pub memoized: usize,
+}
+
+fn format_duration(duration: Duration, memoized: bool) -> String {
+    let text = format!("{:.1} ms", duration.as_secs_f64() * 1000.0);
+    if memoized {
+        format!("{} (memoized)", text)
+    } else {
+        text
+    }
+}
+
+impl<'a> Timings<'a> {
+    pub fn new(config: &'a Config) -> Timings<'a> {
+        Timings {
+            config,
+            input_parse: Duration::ZERO,
+            query: Duration::ZERO,
+            query_memoized: false,
+            paths: Duration::ZERO,
+            paths_memoized: false,
+            result_parse: Duration::ZERO,
+            render: Duration::ZERO,
+            memoized: 0,
+        }
+    }
+}
+
+impl Drawable for Timings<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+
+        let rows = [
+            ("input parse", format_duration(self.input_parse, false)),
+            ("query", format_duration(self.query, self.query_memoized)),
+            ("paths", format_duration(self.paths, self.paths_memoized)),
+            ("result parse", format_duration(self.result_parse, false)),
+            ("render", format_duration(self.render, false)),
+            ("memoized", format!("{} queries", self.memoized)),
+        ];
+        let lines: Vec<Line> = rows
+            .into_iter()
+            .map(|(name, value)| Line::from(vec![
+                Span::styled(format!("{:<13}", name), Style::default().fg(self.config.json.json_key).bg(bg_color)),
+                Span::styled(value, Style::default().fg(fg_color).bg(bg_color)),
+            ]))
+            .collect();
+
+        let width = std::cmp::min(WIDTH, area.width);
+        let height = std::cmp::min(lines.len() as u16 + 2, area.height);
+        let popup = Rect::new(area.x + area.width - width, area.y, width, height);
+        f.render_widget(Clear, popup);
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title(Span::styled(
+                    "Timings",
+                    Style::default().fg(self.config.color.selected_foreground).bg(bg_color)
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            )
+            .style(Style::default().fg(fg_color).bg(bg_color));
+        f.render_widget(paragraph, popup);
+        Ok(())
+    }
+}
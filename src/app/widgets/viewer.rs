@@ -0,0 +1,118 @@
+// This widget is a popup showing the full value of a node,
+// strings are shown unquoted and wrapped.
+// Up/Down/PgUp/PgDn scroll, Esc, q or v close it.
+
+use ratatui::{
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    text::Span,
+    style::Style,
+    backend::Backend,
+};
+
+use crossterm::event;
+
+use serde_json::Value;
+
+use crate::app::widgets::{centered_rect, Drawable};
+use crate::app::Config;
+
+pub struct Viewer<'a> {
+    config: &'a Config,
+    title: String,
+    text: String,
+    scroll: usize,
+}
+
+impl<'a> Viewer<'a> {
+    pub fn new(path: String, value: &Value, config: &'a Config) -> Viewer<'a> {
+        let text = match value {
+            Value::String(s) => s.clone(),
+            other => serde_json::to_string_pretty(other).unwrap_or_default(),
+        };
+        Viewer {
+            config,
+            title: path,
+            text,
+            scroll: 0,
+        }
+    }
+
+    // Returns true when the popup must be closed
+    pub fn handle_event(&mut self, event: &event::KeyEvent) -> bool {
+        // Wrapped lines are not counted, let it scroll a page past the end
+        let last_line = self.text.lines().count();
+        match event {
+            event::KeyEvent {
+                code: event::KeyCode::Esc | event::KeyCode::Char('q') | event::KeyCode::Char('v'),
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                return true;
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = self.scroll.saturating_sub(1);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = std::cmp::min(self.scroll + 1, last_line);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageUp,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = self.scroll.saturating_sub(10);
+            },
+            event::KeyEvent {
+                code: event::KeyCode::PageDown,
+                modifiers: _,
+                kind: _,
+                state: _,
+            } => {
+                self.scroll = std::cmp::min(self.scroll + 10, last_line);
+            },
+            _ => {}
+        }
+        false
+    }
+}
+
+impl Drawable for Viewer<'_> {
+    fn draw<B: Backend>(
+            &self,
+            f: &mut ratatui::Frame<B>,
+            area: ratatui::layout::Rect,
+        ) -> Result<(), std::fmt::Error> {
+        let bg_color = self.config.color.background;
+        let fg_color = self.config.color.foreground;
+
+        let popup = centered_rect(80, 80, area);
+        f.render_widget(Clear, popup);
+
+        let paragraph = Paragraph::new(self.text.as_str())
+            .block(Block::default()
+                .title(Span::styled(
+                    self.title.as_str(),
+                    Style::default().fg(self.config.color.selected_foreground).bg(bg_color)
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(fg_color).bg(bg_color))
+            )
+            .style(Style::default().fg(fg_color).bg(bg_color))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll as u16, 0));
+        f.render_widget(paragraph, popup);
+        Ok(())
+    }
+}
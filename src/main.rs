@@ -1,6 +1,8 @@
 mod app;
 use app::App;
 use app::Config;
+use app::Engine;
+use app::Library;
 
 use clap::Parser;
 
@@ -11,15 +13,123 @@ use clap::Parser;
     about = "An Interactive JQ tool"
 )]
 struct Args {
-    #[arg(help = "Input json file")]
-    input: String,
+    #[arg(help = "Input json file", required_unless_present = "diff")]
+    input: Option<String>,
+    #[arg(long, num_args = 2, value_names = ["A", "B"], conflicts_with = "input", help = "Run the query on two json files and diff the results")]
+    diff: Option<Vec<String>>,
+    #[arg(long, value_name = "PATH", help = "Query library file (default: ~/.config/jq_live_query/library.json)")]
+    library: Option<std::path::PathBuf>,
+    #[arg(long, value_name = "NAME", conflicts_with = "from_file", help = "Start with the named query from the library")]
+    query: Option<String>,
+    #[arg(short, long, value_name = "FILE", help = "Read the jq program from a file, Ctrl-S saves it back")]
+    from_file: Option<std::path::PathBuf>,
+    #[arg(short = 'L', value_name = "DIR", help = "Add a directory to the jq module search path (repeatable)")]
+    lib_dirs: Vec<std::path::PathBuf>,
+    #[arg(long, value_parser = ["horizontal", "vertical"], help = "Split the result and base panes side by side or on top of each other")]
+    split: Option<String>,
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u16).range(10..=90), help = "Size of the first pane")]
+    ratio: Option<u16>,
+    #[arg(long, help = "Put the base pane first (left or top) and the result second")]
+    base_first: bool,
+    #[arg(long, help = "Don't capture the mouse, keeps the terminal text selection working")]
+    no_mouse: bool,
+    #[arg(long, help = "Wrap long lines instead of scrolling them horizontally")]
+    wrap: bool,
+    #[arg(long, value_name = "LENGTH", num_args = 0..=1, default_missing_value = "32", help = "Shorten long strings in the middle")]
+    elide: Option<usize>,
+    #[arg(long, value_name = "COUNT", num_args = 0..=1, default_missing_value = "1000", help = "Run the live query on a sample of the input, Ctrl-R runs it on the whole file (default for inputs over 256 MB)")]
+    sample: Option<usize>,
+    #[arg(long, help = "Sample the input at random instead of taking the first values")]
+    random_sample: bool,
+    #[arg(long, help = "Parse the input as [path, leaf] events, like jq --stream")]
+    stream: bool,
+    #[arg(long, value_parser = ["jq", "jsonpath", "jmespath"], help = "Query language, Alt-L switches it at runtime")]
+    language: Option<String>,
+    #[arg(long, value_name = "FILE", help = "Validate the input against a JSON Schema")]
+    schema: Option<std::path::PathBuf>,
+    #[arg(long, value_name = "FILE", help = "Validate each result against a JSON Schema")]
+    output_schema: Option<std::path::PathBuf>,
+    #[arg(short, long, conflicts_with = "join_output", help = "Show string results without quotes, Alt-R toggles it")]
+    raw_output: bool,
+    #[arg(short, long, help = "Like --raw-output, without a newline after each result, Alt-J toggles it")]
+    join_output: bool,
 }
 
 fn main() -> Result<(), std::io::Error> {
     let args = Args::parse();
 
-    let config = Config::new();
-    let mut app = App::new(args.input, &config)?;
+    let library_path = args.library.unwrap_or_else(Library::default_path);
+    let library = Library::load(&library_path)?;
+    let initial_query = match (&args.query, &args.from_file) {
+        (Some(name), _) => match library.find(name) {
+            Some(entry) => entry.query.clone(),
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No query named '{}' in {}", name, library_path.display())
+                ));
+            }
+        },
+        (None, Some(path)) => {
+            let program = std::fs::read_to_string(path)?;
+            program.strip_suffix('\n').unwrap_or(&program).to_string()
+        },
+        (None, None) => String::from("."),
+    };
+
+    // Relative module paths are resolved from the program file, like jq does
+    let program_origin = match args.from_file.as_ref().and_then(|path| path.parent()) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::path::PathBuf::from("."),
+    };
+    let mut engine = Engine::new(args.lib_dirs, program_origin);
+    engine.set_streaming(args.stream);
+    engine.set_language(match args.language.as_deref() {
+        Some("jsonpath") => app::engine::Language::JsonPath,
+        Some("jmespath") => app::engine::Language::JmesPath,
+        _ => app::engine::Language::Jq,
+    });
+
+    let (input, compare) = match (args.input, args.diff) {
+        (_, Some(mut files)) => {
+            let b = files.pop();
+            (files.remove(0), b)
+        },
+        (Some(input), None) => (input, None),
+        // Enforced by clap
+        (None, None) => unreachable!(),
+    };
+
+    let mut config = Config::new();
+    if let Some(split) = args.split {
+        config.layout.direction = match split.as_str() {
+            "vertical" => ratatui::layout::Direction::Vertical,
+            _ => ratatui::layout::Direction::Horizontal,
+        };
+    }
+    if let Some(ratio) = args.ratio {
+        config.layout.ratio = ratio;
+    }
+    config.layout.result_first = !args.base_first;
+    config.mouse_capture = !args.no_mouse;
+    config.json.soft_wrap = args.wrap;
+    if let Some(length) = args.elide {
+        config.json.elide_strings = true;
+        config.json.elide_length = length;
+    }
+    if let Some(count) = args.sample {
+        config.large.threshold = 0;
+        config.large.sample_size = count;
+    }
+    config.large.random_sample = args.random_sample;
+    config.schema.input = args.schema;
+    config.schema.output = args.output_schema;
+    if args.raw_output {
+        config.output_mode = app::config::OutputMode::Raw;
+    } else if args.join_output {
+        config.output_mode = app::config::OutputMode::Join;
+    }
+    let mut app = App::new(input, compare, &initial_query, args.from_file, library, engine, &config)?;
     match app.run() {
         Ok(value) => {
             println!("Return status: {}", value)